@@ -22,7 +22,7 @@ fn main() {
     for row in 0..8 {
         print!("{:02x}: ", row << 4);
         for col in 0..16 {
-            let addr = (row << 4) | col;
+            let addr: u8 = (row << 4) | col;
 
             // For addresses i2cdetect typically skips:
             // 0x00..=0x07 and 0x78..=0x7F
@@ -0,0 +1,190 @@
+use crate::gpio::Pin;
+use std::fmt;
+
+/// FTDI chip with an MPSSE.
+///
+/// This describes the capabilities of a single MPSSE channel, which are
+/// checked by the HAL once the chip is known, see [`FtHal::set_chip`].
+///
+/// [`FtHal::set_chip`]: crate::FtHal::set_chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Chip {
+    /// FT232H, a single MPSSE channel.
+    FT232H,
+    /// FT2232H, MPSSE on channels A and B.
+    FT2232H,
+    /// FT4232H, MPSSE on channels A and B without the upper byte pins.
+    FT4232H,
+    /// FT2232D, MPSSE on channel A with a 6 MHz maximum clock.
+    FT2232D,
+}
+
+impl Chip {
+    /// Number of lower byte pins, ADBUS0-ADBUS7 on the FT232H.
+    pub const fn lower_pins(&self) -> u8 {
+        8
+    }
+
+    /// Number of upper byte pins usable as GPIO in MPSSE mode,
+    /// ACBUS0-ACBUS7 on the FT232H.
+    pub const fn upper_pins(&self) -> u8 {
+        match self {
+            Chip::FT232H | Chip::FT2232H => 8,
+            Chip::FT4232H => 0,
+            Chip::FT2232D => 4,
+        }
+    }
+
+    /// Maximum clock frequency in Hz.
+    pub const fn max_clock_frequency(&self) -> u32 {
+        match self {
+            Chip::FT232H | Chip::FT2232H | Chip::FT4232H => 30_000_000,
+            Chip::FT2232D => 6_000_000,
+        }
+    }
+
+    /// Minimum clock frequency in Hz.
+    pub const fn min_clock_frequency(&self) -> u32 {
+        // 60 MHz / 5 or 12 MHz master clock, divided by 2 * 65536
+        92
+    }
+
+    /// 3-phase data clocking support, required for I2C.
+    pub const fn has_3phase_clocking(&self) -> bool {
+        !matches!(self, Chip::FT2232D)
+    }
+
+    /// Open-drain (drive only zero) output support.
+    pub const fn has_open_drain(&self) -> bool {
+        matches!(self, Chip::FT232H)
+    }
+
+    /// Adaptive clocking support.
+    pub const fn has_adaptive_clocking(&self) -> bool {
+        !matches!(self, Chip::FT2232D)
+    }
+
+    /// Support for clocking without transferring data.
+    pub const fn has_clock_without_data(&self) -> bool {
+        !matches!(self, Chip::FT2232D)
+    }
+
+    /// Returns `true` if the chip supports a capability.
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Pin(Pin::Lower(idx)) => idx < self.lower_pins(),
+            Capability::Pin(Pin::Upper(idx)) => idx < self.upper_pins(),
+            Capability::ClockFrequency(freq) => {
+                (self.min_clock_frequency()..=self.max_clock_frequency()).contains(&freq)
+            }
+            Capability::ThreePhaseClocking => self.has_3phase_clocking(),
+            Capability::OpenDrain => self.has_open_drain(),
+            Capability::AdaptiveClocking => self.has_adaptive_clocking(),
+            Capability::ClockWithoutData => self.has_clock_without_data(),
+        }
+    }
+}
+
+impl fmt::Display for Chip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip::FT232H => write!(f, "FT232H"),
+            Chip::FT2232H => write!(f, "FT2232H"),
+            Chip::FT4232H => write!(f, "FT4232H"),
+            Chip::FT2232D => write!(f, "FT2232D"),
+        }
+    }
+}
+
+/// Chip capability required by a HAL request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    /// A GPIO pin.
+    Pin(Pin),
+    /// A clock frequency in Hz.
+    ClockFrequency(u32),
+    /// 3-phase data clocking.
+    ThreePhaseClocking,
+    /// Open-drain outputs.
+    OpenDrain,
+    /// Adaptive clocking.
+    AdaptiveClocking,
+    /// Clocking without transferring data.
+    ClockWithoutData,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Capability::Pin(pin) => write!(f, "pin {pin}"),
+            Capability::ClockFrequency(freq) => write!(f, "a clock frequency of {freq} Hz"),
+            Capability::ThreePhaseClocking => write!(f, "3-phase data clocking"),
+            Capability::OpenDrain => write!(f, "open-drain outputs"),
+            Capability::AdaptiveClocking => write!(f, "adaptive clocking"),
+            Capability::ClockWithoutData => write!(f, "clocking without data"),
+        }
+    }
+}
+
+/// Devices that know which chip they are.
+///
+/// This is implemented for the device types of the backends, and used by
+/// [`FtHal::detect_chip`].
+/// Only the `libftd2xx` device types know the chip.
+///
+/// [`FtHal::detect_chip`]: crate::FtHal::detect_chip
+pub trait ChipInfo {
+    /// Get the chip, if known.
+    fn chip(&self) -> Option<Chip>;
+}
+
+#[cfg(feature = "libftd2xx")]
+impl ChipInfo for libftd2xx::Ft232h {
+    fn chip(&self) -> Option<Chip> {
+        Some(Chip::FT232H)
+    }
+}
+
+#[cfg(feature = "libftd2xx")]
+impl ChipInfo for libftd2xx::Ft2232h {
+    fn chip(&self) -> Option<Chip> {
+        Some(Chip::FT2232H)
+    }
+}
+
+#[cfg(feature = "libftd2xx")]
+impl ChipInfo for libftd2xx::Ft4232h {
+    fn chip(&self) -> Option<Chip> {
+        Some(Chip::FT4232H)
+    }
+}
+
+#[cfg(feature = "libftd2xx")]
+impl ChipInfo for libftd2xx::Ft4232ha {
+    fn chip(&self) -> Option<Chip> {
+        Some(Chip::FT4232H)
+    }
+}
+
+#[cfg(feature = "ftdi")]
+impl ChipInfo for ftdi::Device {
+    /// The `ftdi` crate does not report the chip type.
+    fn chip(&self) -> Option<Chip> {
+        None
+    }
+}
+
+impl ChipInfo for crate::sim::SimDevice {
+    /// The simulator models an FT232H.
+    fn chip(&self) -> Option<Chip> {
+        Some(Chip::FT232H)
+    }
+}
+
+impl<D: ChipInfo> ChipInfo for crate::trace::TracingExecutor<D> {
+    fn chip(&self) -> Option<Chip> {
+        self.device().chip()
+    }
+}
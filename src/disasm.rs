@@ -0,0 +1,634 @@
+//! MPSSE command stream disassembler.
+//!
+//! This decodes the raw bytes produced by [`MpsseCmdBuilder`] into a list of
+//! typed [`MpsseOp`]s, which is handy when debugging the traffic generated
+//! by the HAL.
+//!
+//! # Example
+//!
+//! ```
+//! use ftdi_embedded_hal::disasm::{MpsseOp, disassemble};
+//! use ftdi_mpsse::{ClockBitsOut, MpsseCmdBuilder};
+//!
+//! let cmd = MpsseCmdBuilder::new()
+//!     .set_gpio_lower(0x03, 0x03)
+//!     .clock_bits_out(ClockBitsOut::MsbNeg, 0xA0, 8)
+//!     .send_immediate();
+//!
+//! let disassembly = disassemble(cmd.as_slice());
+//! assert_eq!(
+//!     disassembly.ops().next(),
+//!     Some(&MpsseOp::SetGpioLower {
+//!         value: 0x03,
+//!         direction: 0x03
+//!     })
+//! );
+//! assert_eq!(
+//!     disassembly.to_string(),
+//!     "0000: set_gpio_lower(0x03, 0x03)\n\
+//!      0003: clock_bits_out(MsbNeg, 0xa0, 8)\n\
+//!      0006: send_immediate()\n"
+//! );
+//! ```
+//!
+//! [`MpsseCmdBuilder`]: ftdi_mpsse::MpsseCmdBuilder
+use ftdi_mpsse::{
+    ClockBits, ClockBitsIn, ClockBitsOut, ClockData, ClockDataIn, ClockDataOut, ClockTMS,
+    ClockTMSOut,
+};
+use std::fmt;
+
+/// Data shifting opcode flag: bit mode.
+pub(crate) const BIT_MODE: u8 = 0x02;
+/// Data shifting opcode flag: write TDI.
+pub(crate) const WRITE_TDI: u8 = 0x10;
+/// Data shifting opcode flag: read TDO.
+pub(crate) const READ_TDO: u8 = 0x20;
+/// Data shifting opcode flag: write TMS.
+pub(crate) const WRITE_TMS: u8 = 0x40;
+
+/// Maximum number of data bytes shown for a single command.
+const DATA_DISPLAY_LEN: usize = 16;
+
+/// Decoded MPSSE command.
+///
+/// Lengths are the number of bits or bytes clocked, not the encoded
+/// `length - 1` value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MpsseOp {
+    /// Clock bytes out, see [`MpsseCmdBuilder::clock_data_out`].
+    ///
+    /// [`MpsseCmdBuilder::clock_data_out`]: ftdi_mpsse::MpsseCmdBuilder::clock_data_out
+    ClockDataOut {
+        /// Clocking mode.
+        mode: ClockDataOut,
+        /// Data clocked out.
+        data: Vec<u8>,
+    },
+    /// Clock bytes in, see [`MpsseCmdBuilder::clock_data_in`].
+    ///
+    /// [`MpsseCmdBuilder::clock_data_in`]: ftdi_mpsse::MpsseCmdBuilder::clock_data_in
+    ClockDataIn {
+        /// Clocking mode.
+        mode: ClockDataIn,
+        /// Number of bytes clocked in.
+        len: usize,
+    },
+    /// Clock bytes in and out, see [`MpsseCmdBuilder::clock_data`].
+    ///
+    /// [`MpsseCmdBuilder::clock_data`]: ftdi_mpsse::MpsseCmdBuilder::clock_data
+    ClockData {
+        /// Clocking mode.
+        mode: ClockData,
+        /// Data clocked out.
+        data: Vec<u8>,
+    },
+    /// Clock bits out, see [`MpsseCmdBuilder::clock_bits_out`].
+    ///
+    /// [`MpsseCmdBuilder::clock_bits_out`]: ftdi_mpsse::MpsseCmdBuilder::clock_bits_out
+    ClockBitsOut {
+        /// Clocking mode.
+        mode: ClockBitsOut,
+        /// Bits clocked out.
+        bits: u8,
+        /// Number of bits, 1-8.
+        len: u8,
+    },
+    /// Clock bits in, see [`MpsseCmdBuilder::clock_bits_in`].
+    ///
+    /// [`MpsseCmdBuilder::clock_bits_in`]: ftdi_mpsse::MpsseCmdBuilder::clock_bits_in
+    ClockBitsIn {
+        /// Clocking mode.
+        mode: ClockBitsIn,
+        /// Number of bits, 1-8.
+        len: u8,
+    },
+    /// Clock bits in and out, see [`MpsseCmdBuilder::clock_bits`].
+    ///
+    /// [`MpsseCmdBuilder::clock_bits`]: ftdi_mpsse::MpsseCmdBuilder::clock_bits
+    ClockBits {
+        /// Clocking mode.
+        mode: ClockBits,
+        /// Bits clocked out.
+        bits: u8,
+        /// Number of bits, 1-8.
+        len: u8,
+    },
+    /// Clock TMS bits out, see [`MpsseCmdBuilder::clock_tms_out`].
+    ///
+    /// [`MpsseCmdBuilder::clock_tms_out`]: ftdi_mpsse::MpsseCmdBuilder::clock_tms_out
+    ClockTmsOut {
+        /// Clocking mode.
+        mode: ClockTMSOut,
+        /// TMS bits, bit 7 is held on TDI.
+        bits: u8,
+        /// Number of bits, 1-8.
+        len: u8,
+    },
+    /// Clock TMS bits out and TDO bits in, see [`MpsseCmdBuilder::clock_tms`].
+    ///
+    /// [`MpsseCmdBuilder::clock_tms`]: ftdi_mpsse::MpsseCmdBuilder::clock_tms
+    ClockTms {
+        /// Clocking mode.
+        mode: ClockTMS,
+        /// TMS bits, bit 7 is held on TDI.
+        bits: u8,
+        /// Number of bits, 1-8.
+        len: u8,
+    },
+    /// Data shifting command with a combination of flags that has no
+    /// clocking mode in [`ftdi_mpsse`], for example data in and out on the
+    /// same clock edge.
+    Shift {
+        /// Raw opcode.
+        opcode: u8,
+        /// Number of bits in bit mode, otherwise number of bytes.
+        len: usize,
+        /// Data clocked out, empty if the command does not write.
+        data: Vec<u8>,
+    },
+    /// Set the lower byte GPIO, see [`MpsseCmdBuilder::set_gpio_lower`].
+    ///
+    /// [`MpsseCmdBuilder::set_gpio_lower`]: ftdi_mpsse::MpsseCmdBuilder::set_gpio_lower
+    SetGpioLower {
+        /// Pin values.
+        value: u8,
+        /// Pin directions, `1` is output.
+        direction: u8,
+    },
+    /// Set the upper byte GPIO, see [`MpsseCmdBuilder::set_gpio_upper`].
+    ///
+    /// [`MpsseCmdBuilder::set_gpio_upper`]: ftdi_mpsse::MpsseCmdBuilder::set_gpio_upper
+    SetGpioUpper {
+        /// Pin values.
+        value: u8,
+        /// Pin directions, `1` is output.
+        direction: u8,
+    },
+    /// Read the lower byte GPIO.
+    GpioLower,
+    /// Read the upper byte GPIO.
+    GpioUpper,
+    /// Connect TDI to TDO internally.
+    EnableLoopback,
+    /// Disconnect TDI from TDO.
+    DisableLoopback,
+    /// Set the TCK clock divisor.
+    SetClockDivisor(u16),
+    /// Flush the read buffer to the host.
+    SendImmediate,
+    /// Wait until GPIOL1 is high.
+    WaitOnIoHigh,
+    /// Wait until GPIOL1 is low.
+    WaitOnIoLow,
+    /// Use the 60 MHz master clock.
+    DisableClockDivide,
+    /// Divide the master clock by 5.
+    EnableClockDivide,
+    /// Enable 3 phase data clocking.
+    Enable3Phase,
+    /// Disable 3 phase data clocking.
+    Disable3Phase,
+    /// Clock without data, in bits.
+    ClockBitsNoData(u8),
+    /// Clock without data, in bytes.
+    ClockBytesNoData(usize),
+    /// Clock without data until GPIOL1 is high.
+    ClockUntilIoHigh,
+    /// Clock without data until GPIOL1 is low.
+    ClockUntilIoLow,
+    /// Enable adaptive clocking on RTCK (GPIOL3).
+    EnableAdaptive,
+    /// Disable adaptive clocking.
+    DisableAdaptive,
+    /// Clock up to a number of bytes without data until GPIOL1 is high.
+    ClockBytesUntilIoHigh(usize),
+    /// Clock up to a number of bytes without data until GPIOL1 is low.
+    ClockBytesUntilIoLow(usize),
+    /// Drive only zero (open drain) on the lines set in the mask.
+    DriveOnlyZero {
+        /// Lower byte mask.
+        lower: u8,
+        /// Upper byte mask.
+        upper: u8,
+    },
+    /// Opcode not recognized by the MPSSE.
+    ///
+    /// The MPSSE replies with `0xFA` followed by the opcode.
+    Invalid(u8),
+}
+
+/// Convert a data shifting opcode to a typed operation.
+fn shift(opcode: u8, len: usize, data: &[u8]) -> MpsseOp {
+    let bits: u8 = data.first().copied().unwrap_or(0);
+    let bit_len: u8 = len as u8;
+    let op: Option<MpsseOp> = match opcode {
+        0x10 | 0x11 | 0x18 | 0x19 => Some(MpsseOp::ClockDataOut {
+            mode: match opcode {
+                0x10 => ClockDataOut::MsbPos,
+                0x11 => ClockDataOut::MsbNeg,
+                0x18 => ClockDataOut::LsbPos,
+                _ => ClockDataOut::LsbNeg,
+            },
+            data: data.to_vec(),
+        }),
+        0x12 | 0x13 | 0x1A | 0x1B => Some(MpsseOp::ClockBitsOut {
+            mode: match opcode {
+                0x12 => ClockBitsOut::MsbPos,
+                0x13 => ClockBitsOut::MsbNeg,
+                0x1A => ClockBitsOut::LsbPos,
+                _ => ClockBitsOut::LsbNeg,
+            },
+            bits,
+            len: bit_len,
+        }),
+        0x20 | 0x24 | 0x28 | 0x2C => Some(MpsseOp::ClockDataIn {
+            mode: match opcode {
+                0x20 => ClockDataIn::MsbPos,
+                0x24 => ClockDataIn::MsbNeg,
+                0x28 => ClockDataIn::LsbPos,
+                _ => ClockDataIn::LsbNeg,
+            },
+            len,
+        }),
+        0x22 | 0x26 | 0x2A | 0x2E => Some(MpsseOp::ClockBitsIn {
+            mode: match opcode {
+                0x22 => ClockBitsIn::MsbPos,
+                0x26 => ClockBitsIn::MsbNeg,
+                0x2A => ClockBitsIn::LsbPos,
+                _ => ClockBitsIn::LsbNeg,
+            },
+            len: bit_len,
+        }),
+        0x31 | 0x34 | 0x39 | 0x3C => Some(MpsseOp::ClockData {
+            mode: match opcode {
+                0x31 => ClockData::MsbPosIn,
+                0x34 => ClockData::MsbNegIn,
+                0x39 => ClockData::LsbPosIn,
+                _ => ClockData::LsbNegIn,
+            },
+            data: data.to_vec(),
+        }),
+        0x33 | 0x36 | 0x3B | 0x3E => Some(MpsseOp::ClockBits {
+            mode: match opcode {
+                0x33 => ClockBits::MsbPosIn,
+                0x36 => ClockBits::MsbNegIn,
+                0x3B => ClockBits::LsbPosIn,
+                _ => ClockBits::LsbNegIn,
+            },
+            bits,
+            len: bit_len,
+        }),
+        0x4A | 0x4B => Some(MpsseOp::ClockTmsOut {
+            mode: if opcode == 0x4A {
+                ClockTMSOut::PosEdge
+            } else {
+                ClockTMSOut::NegEdge
+            },
+            bits,
+            len: bit_len,
+        }),
+        0x6A | 0x6B | 0x6E | 0x6F => Some(MpsseOp::ClockTms {
+            mode: match opcode {
+                0x6A => ClockTMS::PosTMSPosTDO,
+                0x6B => ClockTMS::NegTMSPosTDO,
+                0x6E => ClockTMS::PosTMSNegTDO,
+                _ => ClockTMS::NegTMSNegTDO,
+            },
+            bits,
+            len: bit_len,
+        }),
+        _ => None,
+    };
+
+    op.unwrap_or_else(|| MpsseOp::Shift {
+        opcode,
+        len,
+        data: data.to_vec(),
+    })
+}
+
+impl MpsseOp {
+    /// Decode the command at the start of `bytes`.
+    ///
+    /// Returns the command and the number of bytes it occupies, or `None`
+    /// if `bytes` ends before the command is complete.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal::disasm::MpsseOp;
+    ///
+    /// assert_eq!(MpsseOp::parse(&[0x87, 0x80]), Some((MpsseOp::SendImmediate, 1)));
+    /// assert_eq!(MpsseOp::parse(&[0x80, 0x00]), None);
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Option<(MpsseOp, usize)> {
+        let (&opcode, args) = bytes.split_first()?;
+        let arg = |idx: usize| args.get(idx).copied();
+        let arg16 = || Some(u16::from_le_bytes([arg(0)?, arg(1)?]));
+
+        let (op, nargs) = match opcode {
+            0x00..=0x7F
+                if opcode & (WRITE_TDI | READ_TDO | WRITE_TMS) == 0
+                    || (opcode & WRITE_TMS != 0
+                        && (opcode & BIT_MODE == 0 || opcode & WRITE_TDI != 0)) =>
+            {
+                (MpsseOp::Invalid(opcode), 0)
+            }
+            0x00..=0x7F => {
+                let write: bool = opcode & (WRITE_TDI | WRITE_TMS) != 0;
+                if opcode & BIT_MODE != 0 {
+                    let len: usize = usize::from(arg(0)? & 0x07) + 1;
+                    let data: &[u8] = if write { args.get(1..2)? } else { &[] };
+                    (shift(opcode, len, data), 1 + data.len())
+                } else {
+                    let len: usize = usize::from(arg16()?) + 1;
+                    let data: &[u8] = if write { args.get(2..2 + len)? } else { &[] };
+                    (shift(opcode, len, data), 2 + data.len())
+                }
+            }
+            0x80 => (
+                MpsseOp::SetGpioLower {
+                    value: arg(0)?,
+                    direction: arg(1)?,
+                },
+                2,
+            ),
+            0x82 => (
+                MpsseOp::SetGpioUpper {
+                    value: arg(0)?,
+                    direction: arg(1)?,
+                },
+                2,
+            ),
+            0x81 => (MpsseOp::GpioLower, 0),
+            0x83 => (MpsseOp::GpioUpper, 0),
+            0x84 => (MpsseOp::EnableLoopback, 0),
+            0x85 => (MpsseOp::DisableLoopback, 0),
+            0x86 => (MpsseOp::SetClockDivisor(arg16()?), 2),
+            0x87 => (MpsseOp::SendImmediate, 0),
+            0x88 => (MpsseOp::WaitOnIoHigh, 0),
+            0x89 => (MpsseOp::WaitOnIoLow, 0),
+            0x8A => (MpsseOp::DisableClockDivide, 0),
+            0x8B => (MpsseOp::EnableClockDivide, 0),
+            0x8C => (MpsseOp::Enable3Phase, 0),
+            0x8D => (MpsseOp::Disable3Phase, 0),
+            0x8E => (MpsseOp::ClockBitsNoData((arg(0)? & 0x07) + 1), 1),
+            0x8F => (MpsseOp::ClockBytesNoData(usize::from(arg16()?) + 1), 2),
+            0x94 => (MpsseOp::ClockUntilIoHigh, 0),
+            0x95 => (MpsseOp::ClockUntilIoLow, 0),
+            0x96 => (MpsseOp::EnableAdaptive, 0),
+            0x97 => (MpsseOp::DisableAdaptive, 0),
+            0x9C => (MpsseOp::ClockBytesUntilIoHigh(usize::from(arg16()?) + 1), 2),
+            0x9D => (MpsseOp::ClockBytesUntilIoLow(usize::from(arg16()?) + 1), 2),
+            0x9E => (
+                MpsseOp::DriveOnlyZero {
+                    lower: arg(0)?,
+                    upper: arg(1)?,
+                },
+                2,
+            ),
+            _ => (MpsseOp::Invalid(opcode), 0),
+        };
+
+        Some((op, 1 + nargs))
+    }
+
+    /// Number of bytes the MPSSE returns to the host for this command.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal::disasm::MpsseOp;
+    /// use ftdi_mpsse::ClockDataIn;
+    ///
+    /// let op = MpsseOp::ClockDataIn {
+    ///     mode: ClockDataIn::MsbPos,
+    ///     len: 4,
+    /// };
+    /// assert_eq!(op.response_len(), 4);
+    /// assert_eq!(MpsseOp::SendImmediate.response_len(), 0);
+    /// ```
+    pub fn response_len(&self) -> usize {
+        match self {
+            MpsseOp::ClockDataIn { len, .. } => *len,
+            MpsseOp::ClockData { data, .. } => data.len(),
+            MpsseOp::ClockBitsIn { .. } | MpsseOp::ClockBits { .. } | MpsseOp::ClockTms { .. } => 1,
+            MpsseOp::Shift { opcode, len, .. } if opcode & READ_TDO != 0 => {
+                if opcode & BIT_MODE != 0 { 1 } else { *len }
+            }
+            MpsseOp::GpioLower | MpsseOp::GpioUpper => 1,
+            MpsseOp::Invalid(_) => 2,
+            _ => 0,
+        }
+    }
+
+    /// Raw opcode, length and output data of a data shifting command.
+    ///
+    /// The length is in bits for bit mode commands and bytes otherwise.
+    pub(crate) fn as_shift(&self) -> Option<(u8, usize, &[u8])> {
+        match self {
+            MpsseOp::ClockDataOut { mode, data } => Some(((*mode).into(), data.len(), data)),
+            MpsseOp::ClockDataIn { mode, len } => Some(((*mode).into(), *len, &[])),
+            MpsseOp::ClockData { mode, data } => Some(((*mode).into(), data.len(), data)),
+            MpsseOp::ClockBitsOut { mode, bits, len } => Some((
+                (*mode).into(),
+                usize::from(*len),
+                std::slice::from_ref(bits),
+            )),
+            MpsseOp::ClockBitsIn { mode, len } => Some(((*mode).into(), usize::from(*len), &[])),
+            MpsseOp::ClockBits { mode, bits, len } => Some((
+                (*mode).into(),
+                usize::from(*len),
+                std::slice::from_ref(bits),
+            )),
+            MpsseOp::ClockTmsOut { mode, bits, len } => Some((
+                (*mode).into(),
+                usize::from(*len),
+                std::slice::from_ref(bits),
+            )),
+            MpsseOp::ClockTms { mode, bits, len } => Some((
+                (*mode).into(),
+                usize::from(*len),
+                std::slice::from_ref(bits),
+            )),
+            MpsseOp::Shift { opcode, len, data } => Some((*opcode, *len, data)),
+            _ => None,
+        }
+    }
+}
+
+/// Hex listing of data bytes, truncated for long transfers.
+struct DataDisplay<'a>(&'a [u8]);
+
+impl fmt::Display for DataDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (idx, byte) in self.0.iter().take(DATA_DISPLAY_LEN).enumerate() {
+            if idx != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        if self.0.len() > DATA_DISPLAY_LEN {
+            write!(f, " ... {} bytes", self.0.len())?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for MpsseOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MpsseOp::ClockDataOut { mode, data } => {
+                write!(f, "clock_data_out({mode:?}, {})", DataDisplay(data))
+            }
+            MpsseOp::ClockDataIn { mode, len } => write!(f, "clock_data_in({mode:?}, {len})"),
+            MpsseOp::ClockData { mode, data } => {
+                write!(f, "clock_data({mode:?}, {})", DataDisplay(data))
+            }
+            MpsseOp::ClockBitsOut { mode, bits, len } => {
+                write!(f, "clock_bits_out({mode:?}, {bits:#04x}, {len})")
+            }
+            MpsseOp::ClockBitsIn { mode, len } => write!(f, "clock_bits_in({mode:?}, {len})"),
+            MpsseOp::ClockBits { mode, bits, len } => {
+                write!(f, "clock_bits({mode:?}, {bits:#04x}, {len})")
+            }
+            MpsseOp::ClockTmsOut { mode, bits, len } => write!(
+                f,
+                "clock_tms_out({mode:?}, {:#04x}, {}, {len})",
+                bits & 0x7F,
+                bits & 0x80 != 0
+            ),
+            MpsseOp::ClockTms { mode, bits, len } => write!(
+                f,
+                "clock_tms({mode:?}, {:#04x}, {}, {len})",
+                bits & 0x7F,
+                bits & 0x80 != 0
+            ),
+            MpsseOp::Shift { opcode, len, data } => {
+                let unit: &str = if opcode & BIT_MODE != 0 {
+                    "bits"
+                } else {
+                    "bytes"
+                };
+                write!(f, "shift({opcode:#04x}, {len} {unit}")?;
+                if !data.is_empty() {
+                    write!(f, ", {}", DataDisplay(data))?;
+                }
+                write!(f, ")")
+            }
+            MpsseOp::SetGpioLower { value, direction } => {
+                write!(f, "set_gpio_lower({value:#04x}, {direction:#04x})")
+            }
+            MpsseOp::SetGpioUpper { value, direction } => {
+                write!(f, "set_gpio_upper({value:#04x}, {direction:#04x})")
+            }
+            MpsseOp::GpioLower => write!(f, "gpio_lower()"),
+            MpsseOp::GpioUpper => write!(f, "gpio_upper()"),
+            MpsseOp::EnableLoopback => write!(f, "enable_loopback()"),
+            MpsseOp::DisableLoopback => write!(f, "disable_loopback()"),
+            MpsseOp::SetClockDivisor(divisor) => write!(f, "set_clock_divisor({divisor})"),
+            MpsseOp::SendImmediate => write!(f, "send_immediate()"),
+            MpsseOp::WaitOnIoHigh => write!(f, "wait_on_io_high()"),
+            MpsseOp::WaitOnIoLow => write!(f, "wait_on_io_low()"),
+            MpsseOp::DisableClockDivide => write!(f, "disable_clock_divide()"),
+            MpsseOp::EnableClockDivide => write!(f, "enable_clock_divide()"),
+            MpsseOp::Enable3Phase => write!(f, "enable_3phase_data_clocking()"),
+            MpsseOp::Disable3Phase => write!(f, "disable_3phase_data_clocking()"),
+            MpsseOp::ClockBitsNoData(len) => write!(f, "clock_bits_no_data({len})"),
+            MpsseOp::ClockBytesNoData(len) => write!(f, "clock_bytes_no_data({len})"),
+            MpsseOp::ClockUntilIoHigh => write!(f, "clock_until_io_high()"),
+            MpsseOp::ClockUntilIoLow => write!(f, "clock_until_io_low()"),
+            MpsseOp::EnableAdaptive => write!(f, "enable_adaptive_data_clocking()"),
+            MpsseOp::DisableAdaptive => write!(f, "disable_adaptive_data_clocking()"),
+            MpsseOp::ClockBytesUntilIoHigh(len) => write!(f, "clock_bytes_until_io_high({len})"),
+            MpsseOp::ClockBytesUntilIoLow(len) => write!(f, "clock_bytes_until_io_low({len})"),
+            MpsseOp::DriveOnlyZero { lower, upper } => {
+                write!(f, "drive_only_zero({lower:#04x}, {upper:#04x})")
+            }
+            MpsseOp::Invalid(opcode) => write!(f, "invalid({opcode:#04x})"),
+        }
+    }
+}
+
+/// Disassembled MPSSE command stream.
+///
+/// This is created by [`disassemble`].
+/// The [`Display`] implementation lists one command per line, prefixed by
+/// its offset in the stream.
+///
+/// [`Display`]: fmt::Display
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Disassembly {
+    /// Commands and their offsets.
+    ops: Vec<(usize, MpsseOp)>,
+    /// Offset of the incomplete command.
+    end: usize,
+    /// Trailing bytes of an incomplete command.
+    incomplete: Vec<u8>,
+}
+
+impl Disassembly {
+    /// Iterate over the decoded commands.
+    pub fn ops(&self) -> impl Iterator<Item = &MpsseOp> {
+        self.ops.iter().map(|(_, op)| op)
+    }
+
+    /// Iterate over the decoded commands and their byte offsets.
+    pub fn ops_with_offsets(&self) -> impl Iterator<Item = (usize, &MpsseOp)> {
+        self.ops.iter().map(|(offset, op)| (*offset, op))
+    }
+
+    /// Trailing bytes of an incomplete command, empty if the stream ends on
+    /// a command boundary.
+    pub fn incomplete(&self) -> &[u8] {
+        &self.incomplete
+    }
+
+    /// Total number of bytes the MPSSE returns to the host for the stream.
+    pub fn response_len(&self) -> usize {
+        self.ops().map(MpsseOp::response_len).sum()
+    }
+}
+
+impl From<Disassembly> for Vec<MpsseOp> {
+    fn from(disassembly: Disassembly) -> Self {
+        disassembly.ops.into_iter().map(|(_, op)| op).collect()
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (offset, op) in self.ops.iter() {
+            writeln!(f, "{offset:04x}: {op}")?;
+        }
+        if !self.incomplete.is_empty() {
+            writeln!(
+                f,
+                "{:04x}: incomplete({})",
+                self.end,
+                DataDisplay(&self.incomplete)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Disassemble an MPSSE command stream.
+///
+/// This never fails, unknown opcodes are decoded as [`MpsseOp::Invalid`]
+/// and a truncated command at the end of the stream is available from
+/// [`Disassembly::incomplete`].
+pub fn disassemble(bytes: &[u8]) -> Disassembly {
+    let mut disassembly: Disassembly = Disassembly::default();
+    let mut offset: usize = 0;
+    while let Some((op, len)) = MpsseOp::parse(&bytes[offset..]) {
+        disassembly.ops.push((offset, op));
+        offset += len;
+    }
+    disassembly.end = offset;
+    disassembly.incomplete = bytes[offset..].to_vec();
+    disassembly
+}
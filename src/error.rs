@@ -1,5 +1,6 @@
+use crate::{Capability, Chip, Pin, PinUse};
 use eh1::i2c::NoAcknowledgeSource;
-use std::{fmt, io};
+use std::{fmt, io, time::Duration};
 
 /// Error type.
 #[derive(Debug)]
@@ -18,18 +19,80 @@ pub enum Error<E: std::error::Error> {
 pub enum ErrorKind {
     /// No ACK from the I2C slave
     I2cNoAck(NoAcknowledgeSource),
+    /// The 10-bit I2C address is out of range 0 - 0x3FF.
+    I2cAddress(u16),
+    /// An I2C target held SCL low for longer than the stretch timeout.
+    I2cStretchTimeout,
+    /// The pin is already allocated.
+    PinInUse {
+        /// Pin that was requested.
+        pin: Pin,
+        /// Requested use of the pin.
+        requested: PinUse,
+        /// Current use of the pin.
+        current: PinUse,
+    },
+    /// The pin index is out of range 0 - 7.
+    PinOutOfRange(Pin),
+    /// The clock frequency in Hz is out of range.
+    ClockFrequency(u32),
+    /// The chip select time of a
+    /// [`SpiDeviceConfig`](crate::SpiDeviceConfig) is too long.
+    ChipSelectTime(Duration),
+    /// The chip does not support the request.
+    Unsupported {
+        /// Chip set with [`FtHal::set_chip`](crate::FtHal::set_chip).
+        chip: Chip,
+        /// Unsupported capability.
+        capability: Capability,
+    },
+    /// The request is only supported by some chips, and the chip was not set
+    /// with [`FtHal::set_chip`](crate::FtHal::set_chip).
+    UnknownChip(Capability),
 }
 
-impl ErrorKind {
-    fn as_str(&self) -> &str {
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ErrorKind::I2cNoAck(NoAcknowledgeSource::Address) => {
-                "No ACK from slave during addressing"
+                write!(f, "No ACK from slave during addressing")
             }
             ErrorKind::I2cNoAck(NoAcknowledgeSource::Data) => {
-                "No ACK from slave during data transfer"
+                write!(f, "No ACK from slave during data transfer")
+            }
+            ErrorKind::I2cNoAck(NoAcknowledgeSource::Unknown) => write!(f, "No ACK from slave"),
+            ErrorKind::I2cAddress(address) => {
+                write!(f, "I2C address {address:#X} is out of range 0 - 0x3FF")
+            }
+            ErrorKind::I2cStretchTimeout => {
+                write!(
+                    f,
+                    "SCL held low by a slave for longer than the stretch timeout"
+                )
+            }
+            ErrorKind::PinInUse {
+                pin,
+                requested,
+                current,
+            } => write!(
+                f,
+                "Unable to allocate pin {pin} for {requested}, pin is already allocated for {current}"
+            ),
+            ErrorKind::PinOutOfRange(pin) => {
+                write!(f, "Pin {pin} is out of range 0 - 7")
+            }
+            ErrorKind::ClockFrequency(freq) => {
+                write!(f, "Clock frequency {freq} Hz is out of range")
+            }
+            ErrorKind::ChipSelectTime(time) => {
+                write!(f, "Chip select time {time:?} is too long")
+            }
+            ErrorKind::Unsupported { chip, capability } => {
+                write!(f, "The {chip} does not support {capability}")
+            }
+            ErrorKind::UnknownChip(capability) => {
+                write!(f, "The chip must be known to use {capability}")
             }
-            ErrorKind::I2cNoAck(NoAcknowledgeSource::Unknown) => "No ACK from slave",
         }
     }
 }
@@ -38,6 +101,7 @@ impl<E: std::error::Error> eh1::i2c::Error for Error<E> {
     fn kind(&self) -> eh1::i2c::ErrorKind {
         match self {
             Self::Hal(ErrorKind::I2cNoAck(src)) => eh1::i2c::ErrorKind::NoAcknowledge(*src),
+            Self::Hal(ErrorKind::I2cStretchTimeout) => eh1::i2c::ErrorKind::Bus,
             _ => eh1::i2c::ErrorKind::Other,
         }
     }
@@ -54,7 +118,7 @@ impl<E: std::error::Error> fmt::Display for Error<E> {
         match self {
             Error::Io(e) => e.fmt(f),
             Error::Backend(e) => fmt::Display::fmt(&e, f),
-            Error::Hal(e) => write!(f, "A regular error occurred {:?}", e.as_str()),
+            Error::Hal(e) => write!(f, "A regular error occurred {:?}", e.to_string()),
         }
     }
 }
@@ -88,3 +152,15 @@ impl From<libftd2xx::TimeoutError> for Error<libftd2xx::TimeoutError> {
         Error::Backend(e)
     }
 }
+
+impl From<crate::sim::SimError> for Error<crate::sim::SimError> {
+    fn from(e: crate::sim::SimError) -> Self {
+        Error::Backend(e)
+    }
+}
+
+impl From<crate::trace::ReplayError> for Error<crate::trace::ReplayError> {
+    fn from(e: crate::trace::ReplayError) -> Self {
+        Error::Backend(e)
+    }
+}
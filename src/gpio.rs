@@ -1,21 +1,45 @@
 use crate::error::Error;
-use crate::{FtInner, PinUse};
+use crate::trace::Origin;
+use crate::{Capability, FtInner, PinUse};
 use ftdi_mpsse::{MpsseCmdBuilder, MpsseCmdExecutor};
 use std::sync::{Arc, Mutex};
 
 /// Pin number
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum Pin {
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Pin {
+    /// Lower byte pin, ADBUS0-ADBUS7 on the FT232H.
     Lower(u8),
+    /// Upper byte pin, ACBUS0-ACBUS7 on the FT232H.
     Upper(u8),
 }
 
+impl Pin {
+    /// Line number, 0-7 for the lower byte and 8-15 for the upper byte.
+    pub(crate) fn line(&self) -> usize {
+        match *self {
+            Pin::Lower(idx) => usize::from(idx),
+            Pin::Upper(idx) => usize::from(idx) + 8,
+        }
+    }
+}
+
+impl std::fmt::Display for Pin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pin::Lower(idx) => write!(f, "AD{idx}"),
+            Pin::Upper(idx) => write!(f, "AC{idx}"),
+        }
+    }
+}
+
 /// FTDI output pin.
 ///
-/// This is created by calling [`FtHal::ad0`] - [`FtHal::ad7`].
+/// This is created by calling [`FtHal::ad0`] - [`FtHal::ad7`], or
+/// [`FtHal::open_drain_pin`] for an open-drain output.
 ///
 /// [`FtHal::ad0`]: crate::FtHal::ad0
 /// [`FtHal::ad7`]: crate::FtHal::ad7
+/// [`FtHal::open_drain_pin`]: crate::FtHal::open_drain_pin
 #[derive(Debug)]
 pub struct OutputPin<Device: MpsseCmdExecutor> {
     /// Parent FTDI device.
@@ -33,30 +57,60 @@ where
     pub(crate) fn new(
         mtx: Arc<Mutex<FtInner<Device>>>,
         pin: Pin,
+    ) -> Result<OutputPin<Device>, Error<E>> {
+        Self::with_drive(mtx, pin, false)
+    }
+
+    pub(crate) fn new_open_drain(
+        mtx: Arc<Mutex<FtInner<Device>>>,
+        pin: Pin,
+    ) -> Result<OutputPin<Device>, Error<E>> {
+        Self::with_drive(mtx, pin, true)
+    }
+
+    fn with_drive(
+        mtx: Arc<Mutex<FtInner<Device>>>,
+        pin: Pin,
+        open_drain: bool,
     ) -> Result<OutputPin<Device>, Error<E>> {
         {
             let mut lock = mtx.lock().expect("Failed to aquire FTDI mutex");
+            let _origin = Origin::OutputPin(pin).enter();
+
+            if open_drain {
+                lock.require(Capability::OpenDrain).map_err(Error::Hal)?;
+            }
+            lock.allocate_pin_any(pin, PinUse::Output)
+                .map_err(Error::Hal)?;
 
-            lock.allocate_pin_any(pin, PinUse::Output);
+            let mut cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
+            if open_drain {
+                // set before the direction, so the pin never drives high
+                match pin {
+                    Pin::Lower(idx) => lock.lower.drive_zero |= 1 << idx,
+                    Pin::Upper(idx) => lock.upper.drive_zero |= 1 << idx,
+                }
+                cmd = lock.drive_zero_cmd(cmd);
+            }
 
             let (byte, idx) = match pin {
                 Pin::Lower(idx) => (&mut lock.lower, idx),
                 Pin::Upper(idx) => (&mut lock.upper, idx),
             };
             byte.direction |= 1 << idx;
-            let cmd = MpsseCmdBuilder::new();
             let cmd = match pin {
                 Pin::Lower(_) => cmd.set_gpio_lower(byte.value, byte.direction),
                 Pin::Upper(_) => cmd.set_gpio_upper(byte.value, byte.direction),
             }
             .send_immediate();
-            lock.ft.send(cmd.as_slice())?;
+            lock.send_or_release(cmd.as_slice(), &[pin])?;
         }
         Ok(OutputPin { mtx, pin })
     }
 
     pub(crate) fn set(&self, state: bool) -> Result<(), Error<E>> {
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::OutputPin(self.pin).enter();
 
         let byte = match self.pin {
             Pin::Lower(_) => &mut lock.lower,
@@ -92,6 +146,16 @@ impl<Device: MpsseCmdExecutor> OutputPin<Device> {
     }
 }
 
+impl<Device: MpsseCmdExecutor> Drop for OutputPin<Device> {
+    fn drop(&mut self) {
+        // a poisoned mutex has nothing left worth releasing
+        if let Ok(mut lock) = self.mtx.lock() {
+            let _origin = Origin::OutputPin(self.pin).enter();
+            let _ = lock.release_pins(&[self.pin]);
+        }
+    }
+}
+
 impl<Device, E> eh1::digital::ErrorType for OutputPin<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
@@ -159,8 +223,10 @@ where
     ) -> Result<InputPin<Device>, Error<E>> {
         {
             let mut lock = mtx.lock().expect("Failed to aquire FTDI mutex");
+            let _origin = Origin::InputPin(pin).enter();
 
-            lock.allocate_pin_any(pin, PinUse::Input);
+            lock.allocate_pin_any(pin, PinUse::Input)
+                .map_err(Error::Hal)?;
 
             let (byte, idx) = match pin {
                 Pin::Lower(idx) => (&mut lock.lower, idx),
@@ -173,13 +239,14 @@ where
                 Pin::Upper(_) => cmd.set_gpio_upper(byte.value, byte.direction),
             }
             .send_immediate();
-            lock.ft.send(cmd.as_slice())?;
+            lock.send_or_release(cmd.as_slice(), &[pin])?;
         }
         Ok(InputPin { mtx, pin })
     }
 
     pub(crate) fn get(&self) -> Result<bool, Error<E>> {
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::InputPin(self.pin).enter();
 
         let mut buffer = [0u8; 1];
         let cmd = MpsseCmdBuilder::new();
@@ -206,6 +273,16 @@ impl<Device: MpsseCmdExecutor> InputPin<Device> {
     }
 }
 
+impl<Device: MpsseCmdExecutor> Drop for InputPin<Device> {
+    fn drop(&mut self) {
+        // a poisoned mutex has nothing left worth releasing
+        if let Ok(mut lock) = self.mtx.lock() {
+            let _origin = Origin::InputPin(self.pin).enter();
+            let _ = lock.release_pins(&[self.pin]);
+        }
+    }
+}
+
 impl<Device, E> eh1::digital::ErrorType for InputPin<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
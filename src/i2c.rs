@@ -1,17 +1,102 @@
 use crate::error::Error;
-use crate::error::ErrorKind::I2cNoAck;
-use crate::{FtInner, PinUse};
-use eh1::i2c::{NoAcknowledgeSource, Operation, SevenBitAddress};
-use ftdi_mpsse::{ClockBitsIn, ClockBitsOut, MpsseCmdBuilder, MpsseCmdExecutor};
+use crate::error::ErrorKind::{I2cAddress, I2cNoAck, I2cStretchTimeout};
+use crate::trace::Origin;
+use crate::{Capability, FtInner, Pin, PinUse};
+use eh1::i2c::{NoAcknowledgeSource, Operation, SevenBitAddress, TenBitAddress};
+use ftdi_mpsse::{ClockBits, ClockBitsIn, ClockBitsOut, MpsseCmdBuilder, MpsseCmdExecutor};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// SCL bitmask
 const SCL: u8 = 1 << 0;
 /// SDA bitmask
 const SDA: u8 = 1 << 1;
+/// SDA input bitmask
+const SDA_IN: u8 = 1 << 2;
+
+/// Pin sensing SCL for adaptive clocking, GPIOL3.
+const RTCK: Pin = Pin::Lower(7);
+
+/// Most SCL pulses sent by [`I2c::recover_bus`], a byte and the ACK bit.
+const RECOVERY_PULSES: usize = 9;
 
 const BITS_IN: ClockBitsIn = ClockBitsIn::MsbPos;
 const BITS_OUT: ClockBitsOut = ClockBitsOut::MsbNeg;
+const BITS_IN_OUT: ClockBits = ClockBits::MsbPosIn;
+
+/// I2C target address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Address {
+    /// 7-bit address.
+    Seven(SevenBitAddress),
+    /// 10-bit address.
+    Ten(TenBitAddress),
+}
+
+impl Address {
+    /// 10-bit address, the upper 6 bits must be zero.
+    fn ten<E: std::error::Error>(address: TenBitAddress) -> Result<Address, Error<E>> {
+        if address > 0x3FF {
+            Err(Error::Hal(I2cAddress(address)))
+        } else {
+            Ok(Address::Ten(address))
+        }
+    }
+
+    /// First byte after a start or repeated start condition.
+    ///
+    /// For 10-bit addresses this is the `11110xx` header with the two most
+    /// significant address bits, the low address byte follows in writes.
+    fn header(self, read: bool) -> u8 {
+        let header: u8 = match self {
+            Address::Seven(address) => address << 1,
+            Address::Ten(address) => 0xF0 | ((address >> 7) as u8 & 0x06),
+        };
+        header | u8::from(read)
+    }
+}
+
+/// I2C clock stretching support, set with [`I2c::set_clock_stretching`].
+///
+/// Targets stretch the clock by holding SCL low until they are ready to
+/// continue.
+/// By default SCL is driven without waiting for targets.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ClockStretching {
+    /// SCL is driven without waiting for targets.
+    #[default]
+    Disabled,
+    /// MPSSE adaptive clocking.
+    ///
+    /// SCL must also be connected to AD7 (GPIOL3).
+    /// After every SCL edge the MPSSE waits in hardware until AD7 follows
+    /// it.
+    ///
+    /// A target can only hold SCL low against an open-drain output, see
+    /// [`I2c::set_open_drain`].
+    /// Without open-drain outputs AD0 drives SCL high, and needs a series
+    /// resistor or buffer to the bus, with AD7 on the bus side.
+    ///
+    /// The MPSSE waits indefinitely, a target holding SCL low fails the
+    /// next read from the device with the read timeout error of the
+    /// backend.
+    /// Start, repeated start and stop conditions do not wait for SCL.
+    Adaptive,
+    /// Release SCL and poll its level.
+    ///
+    /// SCL is released for the first bit of every byte and for the ACK bit
+    /// of bytes written by the controller, the level of SCL is read back
+    /// until the target releases it.
+    /// A target holding SCL low for longer than the stretch timeout fails
+    /// with [`ErrorKind::I2cStretchTimeout`].
+    ///
+    /// This takes a USB round trip for every poll, and every operation is
+    /// sent one bit at a time, regardless of [`I2c::set_fast`].
+    ///
+    /// [`ErrorKind::I2cStretchTimeout`]: crate::ErrorKind::I2cStretchTimeout
+    Polling,
+}
 
 /// FTDI I2C interface.
 ///
@@ -29,6 +114,12 @@ pub struct I2c<Device: MpsseCmdExecutor> {
     start_stop_cmds: u8,
     /// Send I2C commands faster.
     fast: bool,
+    /// Clock stretching support.
+    stretching: ClockStretching,
+    /// Longest clock stretch when polling SCL.
+    stretch_timeout: Duration,
+    /// Drive SCL and SDA as open-drain outputs.
+    open_drain: bool,
 }
 
 impl<Device, E> I2c<Device>
@@ -40,10 +131,16 @@ where
     pub(crate) fn new(mtx: Arc<Mutex<FtInner<Device>>>) -> Result<I2c<Device>, Error<E>> {
         {
             let mut lock = mtx.lock().expect("Failed to aquire FTDI mutex");
+            let _origin = Origin::I2c.enter();
 
-            lock.allocate_pin(0, PinUse::I2c);
-            lock.allocate_pin(1, PinUse::I2c);
-            lock.allocate_pin(2, PinUse::I2c);
+            lock.require(Capability::ThreePhaseClocking)
+                .map_err(Error::Hal)?;
+            lock.allocate_pins(&[
+                (Pin::Lower(0), PinUse::I2c),
+                (Pin::Lower(1), PinUse::I2c),
+                (Pin::Lower(2), PinUse::I2c),
+            ])
+            .map_err(Error::Hal)?;
 
             // clear direction and value of first 3 pins
 
@@ -59,14 +156,58 @@ where
                 .set_gpio_lower(lock.value, lock.direction)
                 .enable_3phase_data_clocking()
                 .send_immediate();
-            lock.ft.send(cmd.as_slice())?;
+            lock.send_or_release(
+                cmd.as_slice(),
+                &[Pin::Lower(0), Pin::Lower(1), Pin::Lower(2)],
+            )?;
         }
 
-        Ok(I2c {
+        let mut i2c: I2c<Device> = I2c {
             mtx,
             start_stop_cmds: 3,
             fast: false,
-        })
+            stretching: ClockStretching::Disabled,
+            stretch_timeout: Duration::from_millis(25),
+            open_drain: false,
+        };
+        let recovery: bool = i2c
+            .mtx
+            .lock()
+            .expect("Failed to aquire FTDI mutex")
+            .i2c_recovery;
+        if recovery {
+            i2c.recover_bus()?;
+        }
+        Ok(i2c)
+    }
+
+    /// Set the SCL frequency in Hz.
+    ///
+    /// I2C uses 3-phase data clocking, which stretches each clock period to
+    /// 3/2 of the MPSSE clock period.
+    /// This sets the MPSSE clock to 3/2 of `frequency` to compensate, and
+    /// returns the actual SCL frequency.
+    ///
+    /// The MPSSE clock is shared, this changes the clock frequency of all
+    /// peripherals.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut i2c = hal.i2c()?;
+    /// assert_eq!(i2c.set_frequency(400_000)?, 400_000);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_frequency(&mut self, frequency: u32) -> Result<u32, Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+        let clock: u32 = frequency.saturating_mul(3) / 2;
+        let actual: u32 = lock.set_clock(clock)?;
+        Ok(actual * 2 / 3)
     }
 
     /// Set the length of start and stop conditions.
@@ -97,22 +238,28 @@ where
 
     /// Enable faster I2C transactions by sending commands in a single write.
     ///
-    /// This is disabled by default, and currently has no effect when using
-    /// version 1 of the `embedded-hal` traits.
+    /// This is disabled by default.
     ///
     /// Normally the I2C methods will send commands with a delay after each
     /// slave ACK to read from the USB device.
     /// Enabling this will send I2C commands without a delay, but slave ACKs
-    /// will only be checked at the end of each call to `read`, `write`, or
-    /// `write_read`.
+    /// will only be checked at the end of each call to `read`, `write`,
+    /// `write_read`, or `transaction`.
+    /// A transaction, including its repeated start conditions, is sent in a
+    /// single write, and the ACKs and data are read back in a single read.
     ///
-    /// Additionally this changes the type of errors that can occur:
+    /// Additionally this changes the type of errors that can occur with
+    /// version 0.2 of the `embedded-hal` traits:
     ///
     /// * enabled: NAK errors will be reported as
     ///   `NoAcknowledgeSource::Unknown`.
     /// * disabled: NAK errors will be reported as
     ///   `NoAcknowledgeSource::Address` or `NoAcknowledgeSource::Data`.
     ///
+    /// Version 1 of the `embedded-hal` traits locates the byte that was not
+    /// acknowledged, and always reports `NoAcknowledgeSource::Address` or
+    /// `NoAcknowledgeSource::Data`.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -134,138 +281,662 @@ where
         self.fast = fast
     }
 
-    fn read_fast(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error<E>> {
-        assert!(!buffer.is_empty(), "buffer must be a non-empty slice");
+    /// Set the clock stretching support.
+    ///
+    /// [`ClockStretching::Adaptive`] allocates AD7.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::ClockStretching;
+    /// use hal::sim::SimDevice;
+    /// use std::time::Duration;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut i2c = hal.i2c()?;
+    /// i2c.set_clock_stretching(ClockStretching::Polling)?;
+    /// i2c.set_stretch_timeout(Duration::from_millis(35));
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_clock_stretching(&mut self, stretching: ClockStretching) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+
+        let adaptive: bool = stretching == ClockStretching::Adaptive;
+        let was_adaptive: bool = self.stretching == ClockStretching::Adaptive;
+        if adaptive && !was_adaptive {
+            lock.require(Capability::AdaptiveClocking)
+                .map_err(Error::Hal)?;
+            lock.allocate_pins(&[(RTCK, PinUse::I2c)])
+                .map_err(Error::Hal)?;
+            lock.direction &= !0x80;
+            lock.value &= !0x80;
+            let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
+                .set_gpio_lower(lock.value, lock.direction)
+                .enable_adaptive_data_clocking()
+                .send_immediate();
+            lock.send_or_release(cmd.as_slice(), &[RTCK])?;
+        } else if !adaptive && was_adaptive {
+            let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
+                .disable_adaptive_data_clocking()
+                .send_immediate();
+            lock.ft.send(cmd.as_slice())?;
+            lock.release_pins(&[RTCK])?;
+        }
 
+        self.stretching = stretching;
+        Ok(())
+    }
+
+    /// Set the longest clock stretch when polling SCL.
+    ///
+    /// This is only used by [`ClockStretching::Polling`], the default is
+    /// 25 ms, the SMBus clock low timeout.
+    pub fn set_stretch_timeout(&mut self, timeout: Duration) {
+        self.stretch_timeout = timeout
+    }
+
+    /// Drive SCL and SDA as open-drain outputs, with the FT232H drive only
+    /// zero command.
+    ///
+    /// Without this, open-drain outputs are emulated by switching SCL and SDA
+    /// between outputs and inputs for every bus state.
+    /// With drive only zero the pins only ever pull the bus low, and the
+    /// controller sends a data byte and clocks in the ACK with two MPSSE
+    /// commands, instead of four.
+    /// Each transaction is sent in a single write and read back in a single
+    /// read, regardless of [`I2c::set_fast`], and [`ClockStretching::Polling`]
+    /// keeps sending one bit at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::Unsupported`] if the chip does not support
+    /// [`Capability::OpenDrain`], which only the FT232H does, and
+    /// [`ErrorKind::UnknownChip`] if the chip was not set with
+    /// [`FtHal::set_chip`] or [`FtHal::detect_chip`].
+    ///
+    /// [`ErrorKind::Unsupported`]: crate::ErrorKind::Unsupported
+    /// [`ErrorKind::UnknownChip`]: crate::ErrorKind::UnknownChip
+    /// [`FtHal::set_chip`]: crate::FtHal::set_chip
+    /// [`FtHal::detect_chip`]: crate::FtHal::detect_chip
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::Chip;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// hal.set_chip(Chip::FT232H)?;
+    /// let mut i2c = hal.i2c()?;
+    /// i2c.set_open_drain(true)?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_open_drain(&mut self, enable: bool) -> Result<(), Error<E>> {
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+
+        if enable {
+            lock.require(Capability::OpenDrain).map_err(Error::Hal)?;
+            lock.lower.drive_zero |= SCL | SDA;
+        } else {
+            lock.lower.drive_zero &= !(SCL | SDA);
+        }
+        let cmd: MpsseCmdBuilder = lock.drive_zero_cmd(MpsseCmdBuilder::new()).send_immediate();
+        lock.ft.send(cmd.as_slice())?;
+        self.open_drain = enable;
+        Ok(())
+    }
+
+    /// Recover a bus with a target holding SDA low.
+    ///
+    /// A target interrupted while sending a byte or an ACK bit holds SDA low
+    /// until it sees the remaining SCL pulses.
+    /// This samples SDA on AD2, and pulses SCL until the target releases SDA,
+    /// up to 9 times, enough to finish a byte and the ACK bit.
+    /// A stop condition then returns the targets to idle.
+    ///
+    /// Returns `true` if SCL and SDA are both high after the stop condition.
+    ///
+    /// This can also be run automatically with
+    /// [`FtHal::set_i2c_recovery`].
+    ///
+    /// [`FtHal::set_i2c_recovery`]: crate::FtHal::set_i2c_recovery
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::Pin;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let mut device = SimDevice::new();
+    /// device.connect(Pin::Lower(1), Pin::Lower(2));
+    /// let hal = hal::FtHal::init_default(device)?;
+    /// let mut i2c = hal.i2c()?;
+    /// assert!(i2c.recover_bus()?);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn recover_bus(&mut self) -> Result<bool, Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
-        // ST
         let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
+        for pulse in 0..=RECOVERY_PULSES {
+            if pulse != 0 {
+                // SCL low, then released, with SDA released
+                for _ in 0..self.start_stop_cmds {
+                    mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | lock.direction)
+                }
+                for _ in 0..self.start_stop_cmds {
+                    mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, lock.direction)
+                }
+            }
+            mpsse_cmd = mpsse_cmd.gpio_lower().send_immediate();
+            lock.ft.send(mpsse_cmd.as_slice())?;
+            let mut pins: [u8; 1] = [0; 1];
+            lock.ft.recv(&mut pins)?;
+            if pins[0] & SDA_IN != 0 {
+                break;
+            }
+            mpsse_cmd = MpsseCmdBuilder::new();
+        }
+
+        // SP, starting from SCL low so that SDA only changes while SCL is low
+        let mpsse_cmd: MpsseCmdBuilder =
+            MpsseCmdBuilder::new().set_gpio_lower(lock.value, SCL | lock.direction);
+        let mpsse_cmd: MpsseCmdBuilder = self
+            .stop_cmd(mpsse_cmd, &lock)
+            .gpio_lower()
+            .send_immediate();
+        lock.ft.send(mpsse_cmd.as_slice())?;
+        let mut pins: [u8; 1] = [0; 1];
+        lock.ft.recv(&mut pins)?;
+        Ok(pins[0] & (SCL | SDA_IN) == SCL | SDA_IN)
+    }
+
+    /// Recover the bus after a NAK, if enabled with
+    /// [`FtHal::set_i2c_recovery`].
+    ///
+    /// [`FtHal::set_i2c_recovery`]: crate::FtHal::set_i2c_recovery
+    fn recover_after_nak(&mut self, result: Result<(), Error<E>>) -> Result<(), Error<E>> {
+        if let Err(Error::Hal(I2cNoAck(_))) = result {
+            let recovery: bool = self
+                .mtx
+                .lock()
+                .expect("Failed to aquire FTDI mutex")
+                .i2c_recovery;
+            if recovery {
+                self.recover_bus()?;
+            }
+        }
+        result
+    }
+
+    /// Append a start condition.
+    fn start_cmd(&self, mut mpsse_cmd: MpsseCmdBuilder, lock: &FtInner<Device>) -> MpsseCmdBuilder {
         for _ in 0..self.start_stop_cmds {
             mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
         }
         for _ in 0..self.start_stop_cmds {
             mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
         }
+        mpsse_cmd
+    }
+
+    /// Append a repeated start condition.
+    fn repeated_start_cmd(
+        &self,
+        mut mpsse_cmd: MpsseCmdBuilder,
+        lock: &FtInner<Device>,
+    ) -> MpsseCmdBuilder {
+        mpsse_cmd = self.start_cmd(mpsse_cmd, lock);
+        for _ in 0..self.start_stop_cmds {
+            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+        }
+        mpsse_cmd
+    }
+
+    /// Append a stop condition and return to idle.
+    fn stop_cmd(&self, mut mpsse_cmd: MpsseCmdBuilder, lock: &FtInner<Device>) -> MpsseCmdBuilder {
+        for _ in 0..self.start_stop_cmds {
+            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+        }
+        for _ in 0..self.start_stop_cmds {
+            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
+        }
+        for _ in 0..self.start_stop_cmds {
+            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
+        }
+        mpsse_cmd.set_gpio_lower(lock.value, lock.direction)
+    }
 
-        mpsse_cmd = mpsse_cmd
-            // SAD+R
+    /// Append an address or data byte written by the controller, followed by
+    /// the target ACK.
+    fn byte_cmd(mpsse_cmd: MpsseCmdBuilder, lock: &FtInner<Device>, byte: u8) -> MpsseCmdBuilder {
+        mpsse_cmd
             .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, (address << 1) | 1, 8)
+            .clock_bits_out(BITS_OUT, byte, 8)
             // SAK
             .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1);
+            .clock_bits_in(BITS_IN, 1)
+    }
 
-        for idx in 0..buffer.len() {
+    /// Append `len` data bytes read by the controller, each followed by a
+    /// controller ACK, and a NAK after the last.
+    fn read_cmd(
+        mut mpsse_cmd: MpsseCmdBuilder,
+        lock: &FtInner<Device>,
+        len: usize,
+    ) -> MpsseCmdBuilder {
+        for idx in 0..len {
             // Bn
             mpsse_cmd = mpsse_cmd
                 .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 8);
-            if idx == buffer.len() - 1 {
-                // NMAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x80, 1)
-            } else {
-                // MAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x00, 1)
+                .clock_bits_in(BITS_IN, 8)
+                .set_gpio_lower(lock.value, SCL | SDA | lock.direction);
+            // MAK or NMAK
+            let nak: u8 = if idx == len - 1 { 0x80 } else { 0x00 };
+            mpsse_cmd = mpsse_cmd.clock_bits_out(BITS_OUT, nak, 1);
+        }
+        mpsse_cmd
+    }
+
+    /// Append the address phase following a start condition.
+    ///
+    /// A 10-bit address is written as the header and the low address byte,
+    /// reads then repeat the start condition and send the read header.
+    ///
+    /// Returns the command and the number of ACK bits it clocks in.
+    fn address_cmd(
+        &self,
+        mut mpsse_cmd: MpsseCmdBuilder,
+        lock: &FtInner<Device>,
+        address: Address,
+        read: bool,
+    ) -> (MpsseCmdBuilder, usize) {
+        let Address::Ten(ten_bit) = address else {
+            // SAD+R/W
+            return (Self::byte_cmd(mpsse_cmd, lock, address.header(read)), 1);
+        };
+
+        // header + W, low address byte
+        mpsse_cmd = Self::byte_cmd(mpsse_cmd, lock, address.header(false));
+        mpsse_cmd = Self::byte_cmd(mpsse_cmd, lock, ten_bit as u8);
+        if !read {
+            return (mpsse_cmd, 2);
+        }
+
+        // SR
+        mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, lock);
+
+        // header + R
+        (Self::byte_cmd(mpsse_cmd, lock, address.header(true)), 3)
+    }
+
+    /// Clock one bit with SCL released, waiting while a target stretches
+    /// SCL.
+    ///
+    /// SCL must be low.
+    /// `sda` is the bit written by the controller, or `None` to release SDA.
+    ///
+    /// Sends `mpsse_cmd` along with the bit, and returns the level of SDA
+    /// sampled once SCL is high, and a command that drives SCL low again.
+    fn stretch_bit(
+        &self,
+        lock: &mut FtInner<Device>,
+        mpsse_cmd: MpsseCmdBuilder,
+        sda: Option<bool>,
+    ) -> Result<(bool, MpsseCmdBuilder), Error<E>> {
+        let (value, direction): (u8, u8) = match sda {
+            Some(true) => (lock.value | SDA, SDA | lock.direction),
+            Some(false) => (lock.value, SDA | lock.direction),
+            None => (lock.value, lock.direction),
+        };
+
+        let mut mpsse_cmd: MpsseCmdBuilder = mpsse_cmd
+            .set_gpio_lower(value, SCL | direction)
+            // release SCL
+            .set_gpio_lower(value, direction)
+            .gpio_lower()
+            .send_immediate();
+
+        let start: Instant = Instant::now();
+        loop {
+            lock.ft.send(mpsse_cmd.as_slice())?;
+            let mut pins: [u8; 1] = [0; 1];
+            lock.ft.recv(&mut pins)?;
+            if pins[0] & SCL != 0 {
+                let mpsse_cmd: MpsseCmdBuilder =
+                    MpsseCmdBuilder::new().set_gpio_lower(value, SCL | direction);
+                return Ok((pins[0] & SDA_IN != 0, mpsse_cmd));
+            }
+            if start.elapsed() > self.stretch_timeout {
+                return Err(Error::Hal(I2cStretchTimeout));
+            }
+            mpsse_cmd = MpsseCmdBuilder::new().gpio_lower().send_immediate();
+        }
+    }
+
+    /// Write a byte with SCL released for the first bit and the target ACK.
+    ///
+    /// Returns a command that continues with SCL low, or an error with
+    /// `source` when the target does not acknowledge the byte.
+    fn write_byte_stretch(
+        &self,
+        lock: &mut FtInner<Device>,
+        mpsse_cmd: MpsseCmdBuilder,
+        byte: u8,
+        source: NoAcknowledgeSource,
+    ) -> Result<MpsseCmdBuilder, Error<E>> {
+        let (_, mpsse_cmd) = self.stretch_bit(lock, mpsse_cmd, Some(byte & 0x80 != 0))?;
+        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd
+            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+            .clock_bits_out(BITS_OUT, byte << 1, 7);
+        // SAK
+        let (nak, mpsse_cmd) = self.stretch_bit(lock, mpsse_cmd, None)?;
+        if nak {
+            Err(Error::Hal(I2cNoAck(source)))
+        } else {
+            Ok(mpsse_cmd)
+        }
+    }
+
+    /// Read a byte with SCL released for the first bit.
+    ///
+    /// Returns the byte and a command that continues with SCL low.
+    fn read_byte_stretch(
+        &self,
+        lock: &mut FtInner<Device>,
+        mpsse_cmd: MpsseCmdBuilder,
+        last: bool,
+    ) -> Result<(u8, MpsseCmdBuilder), Error<E>> {
+        let (msb, mpsse_cmd) = self.stretch_bit(lock, mpsse_cmd, None)?;
+        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd
+            .clock_bits_in(BITS_IN, 7)
+            // MAK or NMAK
+            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+            .clock_bits_out(BITS_OUT, if last { 0x80 } else { 0x00 }, 1)
+            .send_immediate();
+        lock.ft.send(mpsse_cmd.as_slice())?;
+        let mut rest: [u8; 1] = [0; 1];
+        lock.ft.recv(&mut rest)?;
+        Ok((
+            (u8::from(msb) << 7) | (rest[0] & 0x7F),
+            MpsseCmdBuilder::new(),
+        ))
+    }
+
+    /// Run a transaction with [`ClockStretching::Polling`].
+    fn transaction_stretch(
+        &mut self,
+        address: Address,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+
+        // ST
+        let mut mpsse_cmd: MpsseCmdBuilder = self
+            .start_cmd(MpsseCmdBuilder::new(), &lock)
+            .set_gpio_lower(lock.value, SCL | SDA | lock.direction);
+
+        let mut prev_op_was_a_read: bool = false;
+        for (idx, operation) in operations.iter_mut().enumerate() {
+            let read: bool = matches!(operation, Operation::Read(_));
+            if idx == 0 || read != prev_op_was_a_read {
+                if idx != 0 {
+                    // SR
+                    mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
+                }
+                // the preceding write sent the 10-bit address for reads
+                if let Address::Ten(ten_bit) = address
+                    && (idx == 0 || !read)
+                {
+                    for byte in [address.header(false), ten_bit as u8] {
+                        mpsse_cmd = self.write_byte_stretch(
+                            &mut lock,
+                            mpsse_cmd,
+                            byte,
+                            NoAcknowledgeSource::Address,
+                        )?;
+                    }
+                    if read {
+                        mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
+                    }
+                }
+                if read || matches!(address, Address::Seven(_)) {
+                    // SAD + R/W
+                    mpsse_cmd = self.write_byte_stretch(
+                        &mut lock,
+                        mpsse_cmd,
+                        address.header(read),
+                        NoAcknowledgeSource::Address,
+                    )?;
+                }
+            }
+
+            match operation {
+                Operation::Read(buffer) => {
+                    let len: usize = buffer.len();
+                    for (idx, byte) in buffer.iter_mut().enumerate() {
+                        (*byte, mpsse_cmd) =
+                            self.read_byte_stretch(&mut lock, mpsse_cmd, idx == len - 1)?;
+                    }
+                }
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        mpsse_cmd = self.write_byte_stretch(
+                            &mut lock,
+                            mpsse_cmd,
+                            *byte,
+                            NoAcknowledgeSource::Data,
+                        )?;
+                    }
+                }
             }
+            prev_op_was_a_read = read;
         }
 
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+        let mpsse_cmd: MpsseCmdBuilder = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
+        lock.ft.send(mpsse_cmd.as_slice())?;
+
+        Ok(())
+    }
+
+    /// Append a byte written by the controller to a batched transaction,
+    /// followed by the target ACK.
+    ///
+    /// With open-drain outputs the ACK is clocked in while writing a one,
+    /// which releases SDA.
+    fn byte_cmd_batched(
+        &self,
+        mpsse_cmd: MpsseCmdBuilder,
+        lock: &FtInner<Device>,
+        layout: &mut Vec<Option<NoAcknowledgeSource>>,
+        byte: u8,
+        source: NoAcknowledgeSource,
+    ) -> MpsseCmdBuilder {
+        layout.push(Some(source));
+        if self.open_drain {
+            mpsse_cmd
+                .clock_bits_out(BITS_OUT, byte, 8)
+                // SAK
+                .clock_bits(BITS_IN_OUT, 0x80, 1)
+        } else {
+            Self::byte_cmd(mpsse_cmd, lock, byte)
         }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
+    }
+
+    /// Run a transaction in a single write and a single read, with
+    /// [`I2c::set_fast`] or [`I2c::set_open_drain`].
+    fn transaction_batched(
+        &mut self,
+        address: Address,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+
+        // the source of each ACK in the response, `None` for data read
+        let mut layout: Vec<Option<NoAcknowledgeSource>> = Vec::new();
+        let mut reads: Vec<usize> = Vec::new();
+
+        // ST
+        let mut mpsse_cmd: MpsseCmdBuilder = self
+            .start_cmd(MpsseCmdBuilder::new(), &lock)
+            .set_gpio_lower(lock.value, SCL | SDA | lock.direction);
+
+        let mut prev_op_was_a_read: bool = false;
+        for (idx, operation) in operations.iter().enumerate() {
+            let read: bool = matches!(operation, Operation::Read(_));
+            if idx == 0 || read != prev_op_was_a_read {
+                if idx != 0 {
+                    // SR
+                    mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
+                }
+                // the preceding write sent the 10-bit address for reads
+                if let Address::Ten(ten_bit) = address
+                    && (idx == 0 || !read)
+                {
+                    for byte in [address.header(false), ten_bit as u8] {
+                        mpsse_cmd = self.byte_cmd_batched(
+                            mpsse_cmd,
+                            &lock,
+                            &mut layout,
+                            byte,
+                            NoAcknowledgeSource::Address,
+                        );
+                    }
+                    if read {
+                        mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
+                    }
+                }
+                if read || matches!(address, Address::Seven(_)) {
+                    // SAD + R/W
+                    mpsse_cmd = self.byte_cmd_batched(
+                        mpsse_cmd,
+                        &lock,
+                        &mut layout,
+                        address.header(read),
+                        NoAcknowledgeSource::Address,
+                    );
+                }
+            }
+
+            match operation {
+                Operation::Read(buffer) => {
+                    reads.push(layout.len());
+                    layout.resize(layout.len() + buffer.len(), None);
+                    for idx in 0..buffer.len() {
+                        // Bn with SDA released
+                        mpsse_cmd = if self.open_drain {
+                            mpsse_cmd.clock_bits(BITS_IN_OUT, 0xFF, 8)
+                        } else {
+                            mpsse_cmd
+                                .set_gpio_lower(lock.value, SCL | lock.direction)
+                                .clock_bits_in(BITS_IN, 8)
+                                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
+                        };
+                        // MAK or NMAK
+                        let nak: u8 = if idx == buffer.len() - 1 { 0x80 } else { 0x00 };
+                        mpsse_cmd = mpsse_cmd.clock_bits_out(BITS_OUT, nak, 1);
+                    }
+                }
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        mpsse_cmd = self.byte_cmd_batched(
+                            mpsse_cmd,
+                            &lock,
+                            &mut layout,
+                            *byte,
+                            NoAcknowledgeSource::Data,
+                        );
+                    }
+                }
+            }
+            prev_op_was_a_read = read;
         }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
+
+        // SP
+        let mpsse_cmd: MpsseCmdBuilder = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
+        lock.ft.send(mpsse_cmd.as_slice())?;
+        let mut response: Vec<u8> = vec![0; layout.len()];
+        lock.ft.recv(&mut response)?;
+
+        let nak = layout
+            .iter()
+            .zip(response.iter())
+            .find_map(|(source, byte)| source.filter(|_| byte & 0b1 != 0));
+        if let Some(source) = nak {
+            return Err(Error::Hal(I2cNoAck(source)));
+        }
+        let buffers = operations
+            .iter_mut()
+            .filter_map(|operation| match operation {
+                Operation::Read(buffer) => Some(buffer),
+                Operation::Write(_) => None,
+            });
+        for (buffer, pos) in buffers.zip(reads) {
+            buffer.copy_from_slice(&response[pos..pos + buffer.len()]);
         }
 
-        mpsse_cmd = mpsse_cmd
-            // Idle
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
+        Ok(())
+    }
+
+    fn read_fast(&mut self, address: Address, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        assert!(!buffer.is_empty(), "buffer must be a non-empty slice");
+
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
+
+        // ST
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mut mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, address, true);
+
+        mpsse_cmd = Self::read_cmd(mpsse_cmd, &lock, buffer.len());
+
+        // SP
+        mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: [u8; 1] = [0; 1];
+        let mut ack_buf: Vec<u8> = vec![0; acks];
         lock.ft.recv(&mut ack_buf)?;
         lock.ft.recv(buffer)?;
 
-        if (ack_buf[0] & 0b1) != 0x00 {
+        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Unknown)));
         }
 
         Ok(())
     }
 
-    fn read_slow(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error<E>> {
+    fn read_slow(&mut self, address: Address, buffer: &mut [u8]) -> Result<(), Error<E>> {
         assert!(!buffer.is_empty(), "buffer must be a non-empty slice");
 
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
         // ST
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // SAD+R
-            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, (address << 1) | 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1)
-            .send_immediate();
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, address, true);
+        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd.send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: [u8; 1] = [0; 1];
+        let mut ack_buf: Vec<u8> = vec![0; acks];
         lock.ft.recv(&mut ack_buf)?;
-        if (ack_buf[0] & 0b1) != 0x00 {
+        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
         }
 
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for idx in 0..buffer.len() {
-            // Bn
-            mpsse_cmd = mpsse_cmd
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 8);
-            if idx == buffer.len() - 1 {
-                // NMAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x80, 1)
-            } else {
-                // MAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x00, 1)
-            }
-        }
+        let mut mpsse_cmd: MpsseCmdBuilder =
+            Self::read_cmd(MpsseCmdBuilder::new(), &lock, buffer.len());
 
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // Idle
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
+        mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
         lock.ft.recv(buffer)?;
@@ -273,57 +944,24 @@ where
         Ok(())
     }
 
-    fn write_fast(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error<E>> {
-        assert!(!bytes.is_empty(), "bytes must be a non-empty slice");
-
-        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
+    fn write_fast(&mut self, addr: Address, bytes: &[u8]) -> Result<(), Error<E>> {
+        assert!(!bytes.is_empty(), "bytes must be a non-empty slice");
 
-        // ST
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
-        mpsse_cmd = mpsse_cmd
-            // SAD+W
-            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, addr << 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1);
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mut mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, addr, false);
 
         for byte in bytes.iter() {
-            mpsse_cmd = mpsse_cmd
-                // Bi
-                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                .clock_bits_out(BITS_OUT, *byte, 8)
-                // SAK
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 1);
+            mpsse_cmd = Self::byte_cmd(mpsse_cmd, &lock, *byte);
         }
 
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // Idle
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
+        mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: Vec<u8> = vec![0; 1 + bytes.len()];
+        let mut ack_buf: Vec<u8> = vec![0; acks + bytes.len()];
         lock.ft.recv(ack_buf.as_mut_slice())?;
         if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
             Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Unknown)))
@@ -332,62 +970,32 @@ where
         }
     }
 
-    fn write_slow(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error<E>> {
+    fn write_slow(&mut self, addr: Address, bytes: &[u8]) -> Result<(), Error<E>> {
         assert!(!bytes.is_empty(), "bytes must be a non-empty slice");
 
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
         // ST
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(SCL | SDA | lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(SCL | lock.value, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // SAD+W
-            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, addr << 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1)
-            .send_immediate();
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, addr, false);
+        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd.send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: [u8; 1] = [0; 1];
+        let mut ack_buf: Vec<u8> = vec![0; acks];
         lock.ft.recv(&mut ack_buf)?;
-        if (ack_buf[0] & 0b1) != 0x00 {
+        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
         }
 
         for (idx, byte) in bytes.iter().enumerate() {
-            let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-                // Bi
-                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                .clock_bits_out(BITS_OUT, *byte, 8)
-                // SAK
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 1);
+            let mut mpsse_cmd: MpsseCmdBuilder =
+                Self::byte_cmd(MpsseCmdBuilder::new(), &lock, *byte);
 
             // last byte
             if idx == bytes.len() - 1 {
                 // SP
-                for _ in 0..self.start_stop_cmds {
-                    mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                }
-                for _ in 0..self.start_stop_cmds {
-                    mpsse_cmd =
-                        mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-                }
-                for _ in 0..self.start_stop_cmds {
-                    mpsse_cmd =
-                        mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-                }
-
-                // Idle
-                mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, lock.direction)
+                mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock);
             }
 
             mpsse_cmd = mpsse_cmd.send_immediate();
@@ -405,7 +1013,7 @@ where
 
     fn write_read_fast(
         &mut self,
-        address: u8,
+        address: Address,
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Error<E>> {
@@ -415,88 +1023,27 @@ where
         // lock at the start to prevent GPIO from being modified while we build
         // the MPSSE command
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-
-        // ST
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // SAD + W
-            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, address << 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1);
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mut mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, address, false);
 
         for byte in bytes {
-            mpsse_cmd = mpsse_cmd
-                // Oi
-                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                .clock_bits_out(BITS_OUT, *byte, 8)
-                // SAK
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 1);
+            mpsse_cmd = Self::byte_cmd(mpsse_cmd, &lock, *byte);
         }
 
         // SR
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // SAD + R
-            .clock_bits_out(BITS_OUT, (address << 1) | 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1);
+        mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
+        // SAD + R, 10-bit addresses only repeat the header
+        mpsse_cmd = Self::byte_cmd(mpsse_cmd, &lock, address.header(true));
 
-        for idx in 0..buffer.len() {
-            mpsse_cmd = mpsse_cmd
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 8);
-            if idx == buffer.len() - 1 {
-                // NMAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x80, 1)
-            } else {
-                // MAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x00, 1)
-            }
-        }
+        mpsse_cmd = Self::read_cmd(mpsse_cmd, &lock, buffer.len());
 
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // Idle
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
+        mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: Vec<u8> = vec![0; 2 + bytes.len()];
+        let mut ack_buf: Vec<u8> = vec![0; acks + 1 + bytes.len()];
         lock.ft.recv(&mut ack_buf)?;
         lock.ft.recv(buffer)?;
 
@@ -509,7 +1056,7 @@ where
 
     fn write_read_slow(
         &mut self,
-        address: u8,
+        address: Address,
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Error<E>> {
@@ -519,41 +1066,23 @@ where
         // lock at the start to prevent GPIO from being modified while we build
         // the MPSSE command
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
         // ST
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // SAD + W
-            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-            .clock_bits_out(BITS_OUT, address << 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1)
-            .send_immediate();
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
+        let (mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, address, false);
+        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd.send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
-        let mut ack_buf: [u8; 1] = [0; 1];
+        let mut ack_buf: Vec<u8> = vec![0; acks];
         lock.ft.recv(&mut ack_buf)?;
-        if (ack_buf[0] & 0b1) != 0x00 {
+        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
         }
 
         for byte in bytes {
-            let mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-                // Oi
-                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                .clock_bits_out(BITS_OUT, *byte, 8)
-                // SAK
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 1)
-                .send_immediate();
+            let mpsse_cmd: MpsseCmdBuilder =
+                Self::byte_cmd(MpsseCmdBuilder::new(), &lock, *byte).send_immediate();
 
             lock.ft.send(mpsse_cmd.as_slice())?;
             let mut ack_buf: [u8; 1] = [0; 1];
@@ -564,65 +1093,24 @@ where
         }
 
         // SR
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
+        let mpsse_cmd: MpsseCmdBuilder = self.repeated_start_cmd(MpsseCmdBuilder::new(), &lock);
 
-        mpsse_cmd = mpsse_cmd
-            // SAD + R
-            .clock_bits_out(BITS_OUT, (address << 1) | 1, 8)
-            // SAK
-            .set_gpio_lower(lock.value, SCL | lock.direction)
-            .clock_bits_in(BITS_IN, 1)
-            .send_immediate();
+        // SAD + R, 10-bit addresses only repeat the header
+        let mpsse_cmd: MpsseCmdBuilder =
+            Self::byte_cmd(mpsse_cmd, &lock, address.header(true)).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
         let mut ack_buf: [u8; 1] = [0; 1];
         lock.ft.recv(&mut ack_buf)?;
         if (ack_buf[0] & 0b1) != 0x00 {
-            return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Data)));
+            return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
         }
 
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for idx in 0..buffer.len() {
-            mpsse_cmd = mpsse_cmd
-                .set_gpio_lower(lock.value, SCL | lock.direction)
-                .clock_bits_in(BITS_IN, 8);
-            if idx == buffer.len() - 1 {
-                // NMAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x80, 1)
-            } else {
-                // MAK
-                mpsse_cmd = mpsse_cmd
-                    .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                    .clock_bits_out(BITS_OUT, 0x00, 1)
-            }
-        }
+        let mut mpsse_cmd: MpsseCmdBuilder =
+            Self::read_cmd(MpsseCmdBuilder::new(), &lock, buffer.len());
 
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-
-        mpsse_cmd = mpsse_cmd
-            // Idle
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
+        mpsse_cmd = self.stop_cmd(mpsse_cmd, &lock).send_immediate();
 
         lock.ft.send(mpsse_cmd.as_slice())?;
         lock.ft.recv(buffer)?;
@@ -630,23 +1118,84 @@ where
         Ok(())
     }
 
+    fn dispatch_read(&mut self, address: Address, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        let result: Result<(), Error<E>> = if self.stretching == ClockStretching::Polling {
+            self.transaction_stretch(address, &mut [Operation::Read(buffer)])
+        } else if self.open_drain {
+            self.transaction_batched(address, &mut [Operation::Read(buffer)])
+        } else if self.fast {
+            self.read_fast(address, buffer)
+        } else {
+            self.read_slow(address, buffer)
+        };
+        self.recover_after_nak(result)
+    }
+
+    fn dispatch_write(&mut self, address: Address, bytes: &[u8]) -> Result<(), Error<E>> {
+        let result: Result<(), Error<E>> = if self.stretching == ClockStretching::Polling {
+            self.transaction_stretch(address, &mut [Operation::Write(bytes)])
+        } else if self.open_drain {
+            self.transaction_batched(address, &mut [Operation::Write(bytes)])
+        } else if self.fast {
+            self.write_fast(address, bytes)
+        } else {
+            self.write_slow(address, bytes)
+        };
+        self.recover_after_nak(result)
+    }
+
+    fn dispatch_write_read(
+        &mut self,
+        address: Address,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        let result: Result<(), Error<E>> = if self.stretching == ClockStretching::Polling {
+            self.transaction_stretch(
+                address,
+                &mut [Operation::Write(bytes), Operation::Read(buffer)],
+            )
+        } else if self.open_drain {
+            self.transaction_batched(
+                address,
+                &mut [Operation::Write(bytes), Operation::Read(buffer)],
+            )
+        } else if self.fast {
+            self.write_read_fast(address, bytes, buffer)
+        } else {
+            self.write_read_slow(address, bytes, buffer)
+        };
+        self.recover_after_nak(result)
+    }
+
     fn transaction(
         &mut self,
-        address: u8,
+        address: Address,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error<E>> {
+        let result: Result<(), Error<E>> = if self.stretching == ClockStretching::Polling {
+            self.transaction_stretch(address, operations)
+        } else if self.fast || self.open_drain {
+            self.transaction_batched(address, operations)
+        } else {
+            self.transaction_slow(address, operations)
+        };
+        self.recover_after_nak(result)
+    }
+
+    /// Run a transaction with a USB round trip for every ACK.
+    fn transaction_slow(
+        &mut self,
+        address: Address,
         operations: &mut [Operation<'_>],
     ) -> Result<(), Error<E>> {
         // lock at the start to prevent GPIO from being modified while we build
         // the MPSSE command
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::I2c.enter();
 
         // ST
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
+        let mpsse_cmd: MpsseCmdBuilder = self.start_cmd(MpsseCmdBuilder::new(), &lock);
         lock.ft.send(mpsse_cmd.as_slice())?;
 
         let mut prev_op_was_a_read: bool = false;
@@ -657,56 +1206,27 @@ where
                         let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
                         if idx != 0 {
                             // SR
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd = mpsse_cmd.set_gpio_lower(
-                                    lock.value | SCL | SDA,
-                                    SCL | SDA | lock.direction,
-                                )
-                            }
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd = mpsse_cmd
-                                    .set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-                            }
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd =
-                                    mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                            }
+                            mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
                         }
 
-                        mpsse_cmd = mpsse_cmd
-                            // SAD + R
-                            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                            .clock_bits_out(BITS_OUT, (address << 1) | 1, 8)
-                            // SAK
-                            .set_gpio_lower(lock.value, SCL | lock.direction)
-                            .clock_bits_in(BITS_IN, 1)
-                            .send_immediate();
+                        // SAD + R, a 10-bit address was sent by the preceding write
+                        let (mpsse_cmd, acks) = if idx == 0 {
+                            self.address_cmd(mpsse_cmd, &lock, address, true)
+                        } else {
+                            (Self::byte_cmd(mpsse_cmd, &lock, address.header(true)), 1)
+                        };
+                        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd.send_immediate();
 
                         lock.ft.send(mpsse_cmd.as_slice())?;
-                        let mut ack_buf: [u8; 1] = [0; 1];
+                        let mut ack_buf: Vec<u8> = vec![0; acks];
                         lock.ft.recv(&mut ack_buf)?;
-                        if (ack_buf[0] & 0b1) != 0x00 {
+                        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
                             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
                         }
                     }
 
-                    let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-                    for idx in 0..buffer.len() {
-                        mpsse_cmd = mpsse_cmd
-                            .set_gpio_lower(lock.value, SCL | lock.direction)
-                            .clock_bits_in(BITS_IN, 8);
-                        if idx == buffer.len() - 1 {
-                            // NMAK
-                            mpsse_cmd = mpsse_cmd
-                                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                                .clock_bits_out(BITS_OUT, 0x80, 1)
-                        } else {
-                            // MAK
-                            mpsse_cmd = mpsse_cmd
-                                .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                                .clock_bits_out(BITS_OUT, 0x00, 1)
-                        }
-                    }
+                    let mpsse_cmd: MpsseCmdBuilder =
+                        Self::read_cmd(MpsseCmdBuilder::new(), &lock, buffer.len());
                     lock.ft.send(mpsse_cmd.as_slice())?;
                     lock.ft.recv(buffer)?;
 
@@ -717,49 +1237,24 @@ where
                         let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
                         if idx != 0 {
                             // SR
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd = mpsse_cmd.set_gpio_lower(
-                                    lock.value | SCL | SDA,
-                                    SCL | SDA | lock.direction,
-                                )
-                            }
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd = mpsse_cmd
-                                    .set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-                            }
-                            for _ in 0..self.start_stop_cmds {
-                                mpsse_cmd =
-                                    mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                            }
+                            mpsse_cmd = self.repeated_start_cmd(mpsse_cmd, &lock);
                         }
 
-                        mpsse_cmd = mpsse_cmd
-                            // SAD + W
-                            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                            .clock_bits_out(BITS_OUT, address << 1, 8)
-                            // SAK
-                            .set_gpio_lower(lock.value, SCL | lock.direction)
-                            .clock_bits_in(BITS_IN, 1)
-                            .send_immediate();
+                        // SAD + W
+                        let (mpsse_cmd, acks) = self.address_cmd(mpsse_cmd, &lock, address, false);
+                        let mpsse_cmd: MpsseCmdBuilder = mpsse_cmd.send_immediate();
 
                         lock.ft.send(mpsse_cmd.as_slice())?;
-                        let mut ack_buf: [u8; 1] = [0; 1];
+                        let mut ack_buf: Vec<u8> = vec![0; acks];
                         lock.ft.recv(&mut ack_buf)?;
-                        if (ack_buf[0] & 0b1) != 0x00 {
+                        if ack_buf.iter().any(|&ack| (ack & 0b1) != 0x00) {
                             return Err(Error::Hal(I2cNoAck(NoAcknowledgeSource::Address)));
                         }
                     }
 
                     for byte in *bytes {
-                        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
-                        mpsse_cmd = mpsse_cmd
-                            // Oi
-                            .set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-                            .clock_bits_out(BITS_OUT, *byte, 8)
-                            // SAK
-                            .set_gpio_lower(lock.value, SCL | lock.direction)
-                            .clock_bits_in(BITS_IN, 1)
-                            .send_immediate();
+                        let mpsse_cmd: MpsseCmdBuilder =
+                            Self::byte_cmd(MpsseCmdBuilder::new(), &lock, *byte).send_immediate();
 
                         lock.ft.send(mpsse_cmd.as_slice())?;
                         let mut ack_buf: [u8; 1] = [0; 1];
@@ -774,21 +1269,9 @@ where
             }
         }
 
-        let mut mpsse_cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
         // SP
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL, SCL | SDA | lock.direction)
-        }
-        for _ in 0..self.start_stop_cmds {
-            mpsse_cmd = mpsse_cmd.set_gpio_lower(lock.value | SCL | SDA, SCL | SDA | lock.direction)
-        }
-
-        // Idle
-        mpsse_cmd = mpsse_cmd
-            .set_gpio_lower(lock.value, lock.direction)
+        let mpsse_cmd: MpsseCmdBuilder = self
+            .stop_cmd(MpsseCmdBuilder::new(), &lock)
             .send_immediate();
         lock.ft.send(mpsse_cmd.as_slice())?;
 
@@ -796,6 +1279,24 @@ where
     }
 }
 
+impl<Device: MpsseCmdExecutor> Drop for I2c<Device> {
+    fn drop(&mut self) {
+        // a poisoned mutex has nothing left worth releasing
+        if let Ok(mut lock) = self.mtx.lock() {
+            let _origin = Origin::I2c.enter();
+            // 3-phase clocking would break SPI on the same pins
+            let mut cmd: MpsseCmdBuilder = MpsseCmdBuilder::new().disable_3phase_data_clocking();
+            let mut pins: Vec<Pin> = vec![Pin::Lower(0), Pin::Lower(1), Pin::Lower(2)];
+            if self.stretching == ClockStretching::Adaptive {
+                cmd = cmd.disable_adaptive_data_clocking();
+                pins.push(RTCK);
+            }
+            let _ = lock.ft.send(cmd.send_immediate().as_slice());
+            let _ = lock.release_pins(&pins);
+        }
+    }
+}
+
 impl<Device, E> eh0::blocking::i2c::Read for I2c<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
@@ -805,11 +1306,20 @@ where
     type Error = Error<E>;
 
     fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Error<E>> {
-        if self.fast {
-            self.read_fast(address, buffer)
-        } else {
-            self.read_slow(address, buffer)
-        }
+        self.dispatch_read(Address::Seven(address), buffer)
+    }
+}
+
+impl<Device, E> eh0::blocking::i2c::Read<TenBitAddress> for I2c<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    type Error = Error<E>;
+
+    fn read(&mut self, address: TenBitAddress, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        self.dispatch_read(Address::ten(address)?, buffer)
     }
 }
 
@@ -822,11 +1332,20 @@ where
     type Error = Error<E>;
 
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Error<E>> {
-        if self.fast {
-            self.write_fast(addr, bytes)
-        } else {
-            self.write_slow(addr, bytes)
-        }
+        self.dispatch_write(Address::Seven(addr), bytes)
+    }
+}
+
+impl<Device, E> eh0::blocking::i2c::Write<TenBitAddress> for I2c<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    type Error = Error<E>;
+
+    fn write(&mut self, addr: TenBitAddress, bytes: &[u8]) -> Result<(), Error<E>> {
+        self.dispatch_write(Address::ten(addr)?, bytes)
     }
 }
 
@@ -839,11 +1358,25 @@ where
     type Error = Error<E>;
 
     fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Error<E>> {
-        if self.fast {
-            self.write_read_fast(address, bytes, buffer)
-        } else {
-            self.write_read_slow(address, bytes, buffer)
-        }
+        self.dispatch_write_read(Address::Seven(address), bytes, buffer)
+    }
+}
+
+impl<Device, E> eh0::blocking::i2c::WriteRead<TenBitAddress> for I2c<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    type Error = Error<E>;
+
+    fn write_read(
+        &mut self,
+        address: TenBitAddress,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.dispatch_write_read(Address::ten(address)?, bytes, buffer)
     }
 }
 
@@ -867,6 +1400,21 @@ where
         address: SevenBitAddress,
         operations: &mut [Operation<'_>],
     ) -> Result<(), Self::Error> {
-        self.transaction(address, operations)
+        self.transaction(Address::Seven(address), operations)
+    }
+}
+
+impl<Device, E> eh1::i2c::I2c<TenBitAddress> for I2c<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction(Address::ten(address)?, operations)
     }
 }
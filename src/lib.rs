@@ -26,7 +26,6 @@
 //!
 //! * Limited trait support: SPI, I2C, Delay, InputPin, and OutputPin traits are implemented.
 //! * Limited device support: FT232H, FT2232H, FT4232H.
-//! * Limited SPI modes support: MODE0, MODE2.
 //!
 //! # Examples
 //!
@@ -112,7 +111,7 @@
 //! let device = libftd2xx::Ft232h::with_description("Single RS232-HS")?;
 //!
 //! let hal = hal::FtHal::init_default(device)?;
-//! let gpio = hal.ad6();
+//! let gpio = hal.ad6()?;
 //! # }
 //! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
 //! ```
@@ -128,11 +127,24 @@
 //!     .open()?;
 //!
 //! let hal = hal::FtHal::init_default(device)?;
-//! let gpio = hal.ad6();
+//! let gpio = hal.ad6()?;
 //! # }
 //! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
 //! ```
 //!
+//! ## Simulation
+//!
+//! The [`sim`] module provides a simulated FT232H for testing without hardware:
+//! ```
+//! use ftdi_embedded_hal as hal;
+//!
+//! let device = hal::sim::SimDevice::new();
+//!
+//! let hal = hal::FtHal::init_freq(device, 400_000)?;
+//! let i2c = hal.i2c()?;
+//! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+//! ```
+//!
 //! ## More examples
 //!
 //! * [newAM/eeprom25aa02e48-rs]: read data from Microchip 25AA02E48 SPI EEPROM
@@ -159,29 +171,41 @@ pub use ftdi;
 #[cfg(feature = "libftd2xx")]
 pub use libftd2xx;
 
+mod chip;
 mod delay;
+pub mod disasm;
 mod error;
 mod gpio;
 mod i2c;
+pub mod sim;
 mod spi;
+pub mod trace;
 
 pub use crate::error::{Error, ErrorKind};
+pub use chip::{Capability, Chip, ChipInfo};
 pub use delay::Delay;
-pub use gpio::{InputPin, OutputPin};
-pub use i2c::I2c;
-pub use spi::{Spi, SpiDevice};
+pub use gpio::{InputPin, OutputPin, Pin};
+pub use i2c::{ClockStretching, I2c};
+pub use spi::{BitOrder, ByteOrder, Spi, SpiDevice, SpiDeviceBus, SpiDeviceConfig};
 
-use gpio::Pin;
-
-use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use crate::trace::Origin;
+use ftdi_mpsse::{MpsseCmdBuilder, MpsseCmdExecutor, MpsseSettings};
 use std::sync::{Arc, Mutex};
 
+/// MPSSE command setting the open-drain outputs, FT232H only.
+const DRIVE_ONLY_ZERO: u8 = 0x9E;
+
 /// State tracker for each pin on the FTDI chip.
-#[derive(Debug, Clone, Copy)]
-enum PinUse {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PinUse {
+    /// Allocated by [`I2c`].
     I2c,
+    /// Allocated by [`Spi`] or [`SpiDevice`].
     Spi,
+    /// Allocated by [`OutputPin`], or as a [`SpiDevice`] chip select.
     Output,
+    /// Allocated by [`InputPin`].
     Input,
 }
 
@@ -202,6 +226,8 @@ struct GpioByte {
     direction: u8,
     /// GPIO value.
     value: u8,
+    /// Open-drain (drive only zero) outputs.
+    drive_zero: u8,
     /// Pin allocation.
     pins: [Option<PinUse>; 8],
 }
@@ -212,6 +238,23 @@ struct FtInner<Device: MpsseCmdExecutor> {
     ft: Device,
     lower: GpioByte,
     upper: GpioByte,
+    /// Tri-state pins when they are released.
+    tristate_on_release: bool,
+    /// Recover the I2C bus when an I2C peripheral is created and after a NAK.
+    i2c_recovery: bool,
+    /// Chip, if known.
+    chip: Option<Chip>,
+    /// Clock frequency, `None` if not set by the HAL.
+    clock: Option<u32>,
+    /// Clock frequency of the bus, used by SPI devices without a frequency
+    /// of their own.
+    ///
+    /// This differs from `clock` while such a device has the bus.
+    bus_clock: Option<u32>,
+    /// Number of SPI devices sharing the SPI bus pins.
+    spi_devices: usize,
+    /// USB in transfer size in bytes, the most data read at once.
+    in_transfer_size: usize,
 }
 
 // FtInner deref's into .lower because SPI and I2C code were not adjusted yet to handle the split;
@@ -231,34 +274,200 @@ impl<Device: MpsseCmdExecutor> core::ops::DerefMut for FtInner<Device> {
 }
 
 impl<Device: MpsseCmdExecutor> FtInner<Device> {
-    /// Allocate a pin in the lower byte for a specific use.
-    pub fn allocate_pin(&mut self, idx: u8, purpose: PinUse) {
-        assert!(idx < 8, "Pin index {idx} is out of range 0 - 7");
-
-        if let Some(current) = self.lower.pins[usize::from(idx)] {
-            panic!(
-                "Unable to allocate pin {idx} for {purpose}, pin is already allocated for {current}"
-            );
-        } else {
-            self.lower.pins[usize::from(idx)] = Some(purpose)
+    /// Check that the chip, if known, supports a capability.
+    ///
+    /// Open-drain outputs also require a known chip, the drive only zero
+    /// command is an invalid command on the other chips.
+    pub fn require(&self, capability: Capability) -> Result<(), ErrorKind> {
+        match self.chip {
+            Some(chip) if !chip.supports(capability) => {
+                Err(ErrorKind::Unsupported { chip, capability })
+            }
+            None if capability == Capability::OpenDrain => Err(ErrorKind::UnknownChip(capability)),
+            _ => Ok(()),
         }
     }
 
-    /// Allocate a pin for a specific use.
-    pub fn allocate_pin_any(&mut self, pin: Pin, purpose: PinUse) {
-        let (byte, idx) = match pin {
-            Pin::Lower(idx) => (&mut self.lower, idx),
-            Pin::Upper(idx) => (&mut self.upper, idx),
+    /// Build the command setting the clock frequency.
+    ///
+    /// Returns the command and the actual frequency, the highest frequency
+    /// that does not exceed the requested frequency.
+    pub fn clock_cmd(&self, frequency: u32) -> Result<(MpsseCmdBuilder, u32), ErrorKind> {
+        self.require(Capability::ClockFrequency(frequency))?;
+
+        if frequency == 0 {
+            return Err(ErrorKind::ClockFrequency(frequency));
+        }
+        let bases: &[(u32, Option<bool>)] = match self.chip {
+            // FT2232D has a fixed 12 MHz master clock
+            Some(Chip::FT2232D) => &[(6_000_000, None)],
+            // 60 MHz divided by 5, which is kept on a tie, or not
+            _ => &[(6_000_000, Some(true)), (30_000_000, Some(false))],
         };
-        assert!(idx < 8, "Pin index {idx} is out of range 0 - 7");
-
-        if let Some(current) = byte.pins[usize::from(idx)] {
-            panic!(
-                "Unable to allocate pin {idx} for {purpose}, pin is already allocated for {current}"
-            );
-        } else {
-            byte.pins[usize::from(idx)] = Some(purpose)
+        let (divisor, clkdiv, actual): (u32, Option<bool>, u32) = bases
+            .iter()
+            .filter(|&&(base, _)| frequency <= base)
+            .map(|&(base, clkdiv)| {
+                let divisor: u32 = base.div_ceil(frequency) - 1;
+                (divisor, clkdiv, base / (divisor + 1))
+            })
+            .filter(|&(divisor, _, _)| divisor <= 0xFFFF)
+            .reduce(|best, next| if next.2 > best.2 { next } else { best })
+            .ok_or(ErrorKind::ClockFrequency(frequency))?;
+
+        Ok((MpsseCmdBuilder::new().set_clock(divisor, clkdiv), actual))
+    }
+
+    /// Check the clock frequency and the pins in use against the chip.
+    fn check_chip(&self) -> Result<(), ErrorKind> {
+        for freq in self.clock.into_iter().chain(self.bus_clock) {
+            self.require(Capability::ClockFrequency(freq))?;
+        }
+
+        let lower = (0..)
+            .zip(self.lower.pins)
+            .map(|(idx, p)| (Pin::Lower(idx), p));
+        let upper = (0..)
+            .zip(self.upper.pins)
+            .map(|(idx, p)| (Pin::Upper(idx), p));
+        for (pin, allocation) in lower.chain(upper) {
+            if let Some(purpose) = allocation {
+                self.require(Capability::Pin(pin))?;
+                if purpose == PinUse::I2c {
+                    self.require(Capability::ThreePhaseClocking)?;
+                }
+            }
+        }
+        if self.lower.drive_zero != 0 || self.upper.drive_zero != 0 {
+            self.require(Capability::OpenDrain)?;
+        }
+        Ok(())
+    }
+
+    /// Allocate pins for specific uses.
+    ///
+    /// Either all pins are allocated, or none are.
+    pub fn allocate_pins(&mut self, pins: &[(Pin, PinUse)]) -> Result<(), ErrorKind> {
+        let mut lower: [Option<PinUse>; 8] = self.lower.pins;
+        let mut upper: [Option<PinUse>; 8] = self.upper.pins;
+
+        for &(pin, purpose) in pins {
+            let (allocation, idx) = match pin {
+                Pin::Lower(idx) => (&mut lower, idx),
+                Pin::Upper(idx) => (&mut upper, idx),
+            };
+            let slot: &mut Option<PinUse> = allocation
+                .get_mut(usize::from(idx))
+                .ok_or(ErrorKind::PinOutOfRange(pin))?;
+            self.require(Capability::Pin(pin))?;
+
+            if let Some(current) = *slot {
+                return Err(ErrorKind::PinInUse {
+                    pin,
+                    requested: purpose,
+                    current,
+                });
+            }
+            *slot = Some(purpose);
         }
+
+        self.lower.pins = lower;
+        self.upper.pins = upper;
+        Ok(())
+    }
+
+    /// Allocate a pin for a specific use.
+    pub fn allocate_pin_any(&mut self, pin: Pin, purpose: PinUse) -> Result<(), ErrorKind> {
+        self.allocate_pins(&[(pin, purpose)])
+    }
+
+    /// Append the command setting the open-drain outputs to the
+    /// `drive_zero` masks.
+    ///
+    /// Open-drain outputs drive a low level and tri-state for a high level.
+    /// This is only supported by the FT232H, check
+    /// [`Capability::OpenDrain`] first.
+    pub fn drive_zero_cmd(&self, mut cmd: MpsseCmdBuilder) -> MpsseCmdBuilder {
+        // ftdi-mpsse has no builder method for this command
+        cmd.0.extend_from_slice(&[
+            DRIVE_ONLY_ZERO,
+            self.lower.drive_zero,
+            self.upper.drive_zero,
+        ]);
+        cmd
+    }
+
+    /// Free pins, tri-stating them if enabled with
+    /// [`FtHal::set_tristate_on_release`].
+    ///
+    /// This is called from `Drop` implementations, so pins are freed even if
+    /// tri-stating them fails.
+    pub fn release_pins(&mut self, pins: &[Pin]) -> Result<(), Device::Error> {
+        let mut lower_mask: u8 = 0;
+        let mut upper_mask: u8 = 0;
+        for &pin in pins {
+            let (byte, mask, idx) = match pin {
+                Pin::Lower(idx) => (&mut self.lower, &mut lower_mask, idx),
+                Pin::Upper(idx) => (&mut self.upper, &mut upper_mask, idx),
+            };
+            if let Some(slot) = byte.pins.get_mut(usize::from(idx)) {
+                *slot = None;
+                *mask |= 1 << idx;
+            }
+        }
+
+        let mut cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
+        if self.lower.drive_zero & lower_mask != 0 || self.upper.drive_zero & upper_mask != 0 {
+            self.lower.drive_zero &= !lower_mask;
+            self.upper.drive_zero &= !upper_mask;
+            cmd = self.drive_zero_cmd(cmd);
+        }
+
+        if !self.tristate_on_release {
+            if cmd.as_slice().is_empty() {
+                return Ok(());
+            }
+            return self.ft.send(cmd.send_immediate().as_slice());
+        }
+
+        if lower_mask != 0 {
+            self.lower.direction &= !lower_mask;
+            cmd = cmd.set_gpio_lower(self.lower.value, self.lower.direction);
+        }
+        if upper_mask != 0 {
+            self.upper.direction &= !upper_mask;
+            cmd = cmd.set_gpio_upper(self.upper.value, self.upper.direction);
+        }
+        self.ft.send(cmd.send_immediate().as_slice())
+    }
+
+    /// Send the command setting up the pins of a new peripheral, freeing
+    /// `pins` again if it fails.
+    ///
+    /// Pins are otherwise freed when the peripheral is dropped, which is
+    /// not created when its setup fails.
+    pub fn send_or_release(&mut self, cmd: &[u8], pins: &[Pin]) -> Result<(), Device::Error> {
+        let result: Result<(), Device::Error> = self.ft.send(cmd);
+        if result.is_err() {
+            let _ = self.release_pins(pins);
+        }
+        result
+    }
+}
+
+impl<Device, E> FtInner<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    /// Set the clock frequency, returning the actual frequency.
+    pub fn set_clock(&mut self, frequency: u32) -> Result<u32, Error<E>> {
+        let (cmd, actual) = self.clock_cmd(frequency).map_err(Error::Hal)?;
+        self.ft.send(cmd.send_immediate().as_slice())?;
+        self.clock = Some(actual);
+        self.bus_clock = Some(actual);
+        Ok(actual)
     }
 }
 
@@ -268,6 +477,13 @@ impl<Device: MpsseCmdExecutor> From<Device> for FtInner<Device> {
             ft,
             lower: Default::default(),
             upper: Default::default(),
+            tristate_on_release: false,
+            i2c_recovery: false,
+            chip: None,
+            clock: None,
+            bus_clock: None,
+            spi_devices: 0,
+            in_transfer_size: MpsseSettings::default().in_transfer_size as usize,
         }
     }
 }
@@ -346,6 +562,10 @@ where
     /// **Note:** The clock frequency will be 2/3 of the specified value when in
     /// I2C mode.
     ///
+    /// The actual clock frequency is the highest the MPSSE clock divisor can
+    /// produce without exceeding the `clock_frequency` field, as with
+    /// [`FtHal::set_clock_frequency`].
+    ///
     /// # Panics
     ///
     /// Panics if the `clock_frequency` field of [`MpsseSettings`] is `None`.
@@ -379,8 +599,20 @@ where
     pub fn init(mut device: Device, mpsse_settings: &MpsseSettings) -> Result<FtHal<Device>, E> {
         device.init(mpsse_settings)?;
 
+        let mut inner: FtInner<Device> = device.into();
+        // the backends round the clock divisor differently, program the one
+        // the HAL uses and keep the actual frequency
+        if let Some(frequency) = mpsse_settings.clock_frequency
+            && let Ok((cmd, actual)) = inner.clock_cmd(frequency)
+        {
+            let _origin = Origin::FtHal.enter();
+            inner.ft.send(cmd.send_immediate().as_slice())?;
+            inner.clock = Some(actual);
+            inner.bus_clock = Some(actual);
+        }
+        inner.in_transfer_size = mpsse_settings.in_transfer_size as usize;
         Ok(FtHal {
-            mtx: Arc::new(Mutex::new(device.into())),
+            mtx: Arc::new(Mutex::new(inner)),
         })
     }
 }
@@ -420,6 +652,143 @@ where
         result
     }
 
+    /// Set the clock frequency in Hz.
+    ///
+    /// This changes the clock frequency of all peripherals, the actual
+    /// frequency is returned.
+    /// The actual frequency is the highest the MPSSE clock divisor can
+    /// produce without exceeding `frequency`.
+    ///
+    /// With I2C the SCL frequency is two thirds of the clock frequency,
+    /// use [`I2c::set_frequency`] to set the SCL frequency.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::ClockFrequency`] if the frequency is out of
+    /// range, or [`ErrorKind::Unsupported`] if the chip does not support the
+    /// frequency.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// assert_eq!(hal.set_clock_frequency(1_000_000)?, 1_000_000);
+    /// assert_eq!(hal.set_clock_frequency(7_000_000)?, 6_000_000);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_clock_frequency(&self, frequency: u32) -> Result<u32, Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::FtHal.enter();
+        lock.set_clock(frequency)
+    }
+
+    /// Get the chip, if known.
+    pub fn chip(&self) -> Option<Chip> {
+        self.mtx.lock().expect("Failed to aquire FTDI mutex").chip
+    }
+
+    /// Set the chip.
+    ///
+    /// Once the chip is known requests it does not support return
+    /// [`ErrorKind::Unsupported`], for example the upper byte pins of the
+    /// FT4232H or I2C on the FT2232D.
+    /// Without a chip, the HAL assumes everything is supported, except for
+    /// open-drain outputs which return [`ErrorKind::UnknownChip`].
+    ///
+    /// The chip is set by [`FtHal::detect_chip`] for the `libftd2xx` device
+    /// types, the `ftdi` backend does not report the chip.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::Unsupported`] if the clock frequency or the
+    /// pins already in use are not supported by the chip.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::Chip;
+    ///
+    /// # #[cfg(feature = "ftdi")]
+    /// # {
+    /// let device = ftdi::find_by_vid_pid(0x0403, 0x6010)
+    ///     .interface(ftdi::Interface::A)
+    ///     .open()?;
+    /// let hal = hal::FtHal::init_freq(device, 1_000_000)?;
+    /// hal.set_chip(Chip::FT2232D)?;
+    /// assert!(hal.i2c().is_err());
+    /// # }
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_chip(&self, chip: Chip) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let previous: Option<Chip> = lock.chip.replace(chip);
+        let result: Result<(), ErrorKind> = lock.check_chip();
+        if result.is_err() {
+            lock.chip = previous;
+        }
+        result.map_err(Error::Hal)
+    }
+
+    /// Tri-state pins when they are released.
+    ///
+    /// Pins are released when the [`Spi`], [`SpiDevice`], [`I2c`],
+    /// [`OutputPin`] or [`InputPin`] that allocated them is dropped, after
+    /// which they can be allocated again.
+    /// By default released pins keep their direction and value, when enabled
+    /// they are returned to inputs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// hal.set_tristate_on_release(true);
+    ///
+    /// let i2c = hal.i2c()?;
+    /// drop(i2c);
+    /// let spi = hal.spi()?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_tristate_on_release(&self, enable: bool) {
+        self.mtx
+            .lock()
+            .expect("Failed to aquire FTDI mutex")
+            .tristate_on_release = enable;
+    }
+
+    /// Recover the I2C bus with [`I2c::recover_bus`] when an [`I2c`] is
+    /// created, and after a NAK.
+    ///
+    /// This is disabled by default.
+    /// A target left holding SDA low by an interrupted transfer, for example
+    /// when the host program was stopped, otherwise fails every following
+    /// transfer with [`ErrorKind::I2cNoAck`].
+    /// The NAK is still returned after the bus is recovered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// hal.set_i2c_recovery(true);
+    /// let i2c = hal.i2c()?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_i2c_recovery(&self, enable: bool) {
+        self.mtx
+            .lock()
+            .expect("Failed to aquire FTDI mutex")
+            .i2c_recovery = enable;
+    }
+
     /// Aquire the SPI peripheral for the FT232H.
     ///
     /// Pin assignments:
@@ -427,9 +796,9 @@ where
     /// * AD1 => MOSI
     /// * AD2 => MISO
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if pin 0, 1, or 2 are already in use.
+    /// Returns [`ErrorKind::PinInUse`] if pin 0, 1, or 2 are already in use.
     ///
     /// # Example
     ///
@@ -457,9 +826,15 @@ where
     /// * AD1 => MOSI
     /// * AD2 => MISO
     ///
-    /// # Panics
+    /// Multiple SPI devices with different chip select pins share the bus,
+    /// each with its own clock frequency and mode, see
+    /// [`SpiDevice::set_frequency`].
     ///
-    /// Panics if pin 0, 1, 2 or the CS pin are already in use.
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::PinInUse`] if the CS pin is already in use, or
+    /// pin 0, 1 or 2 are in use by something other than an SPI device, and
+    /// [`ErrorKind::PinOutOfRange`] if `cs_idx` is not in the range 0 - 7.
     ///
     /// # Example
     ///
@@ -475,7 +850,74 @@ where
     /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
     /// ```
     pub fn spi_device(&self, cs_idx: u8) -> Result<SpiDevice<Device>, Error<E>> {
-        SpiDevice::new(self.mtx.clone(), cs_idx)
+        self.spi_device_pin(Pin::Lower(cs_idx))
+    }
+
+    /// Aquire the SPI peripheral with a chip select pin on the lower or the
+    /// upper byte.
+    ///
+    /// This is the same as [`FtHal::spi_device`], with more chip select
+    /// pins to choose from.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::PinInUse`] if the CS pin is already in use, or
+    /// pin 0, 1 or 2 are in use by something other than an SPI device,
+    /// [`ErrorKind::PinOutOfRange`] if the pin index is not in the range
+    /// 0 - 7, and [`ErrorKind::Unsupported`] if the chip does not have the
+    /// pin.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::Pin;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_freq(SimDevice::new(), 3_000_000)?;
+    /// let flash = hal.spi_device_pin(Pin::Upper(0))?;
+    /// let adc = hal.spi_device_pin(Pin::Upper(1))?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn spi_device_pin(&self, cs: Pin) -> Result<SpiDevice<Device>, Error<E>> {
+        SpiDevice::new(self.mtx.clone(), cs)
+    }
+
+    /// Aquire an open-drain digital output on any lower or upper byte pin.
+    ///
+    /// The pin drives a low level, and is tri-stated for a high level, for
+    /// wired-AND signals such as interrupt or reset lines shared with other
+    /// devices.
+    /// Drive only zero is disabled again when the pin is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use,
+    /// [`ErrorKind::PinOutOfRange`] if the pin index is not in the range
+    /// 0 - 7, and [`ErrorKind::Unsupported`] if the chip does not support
+    /// [`Capability::OpenDrain`], which only the FT232H does, or does not
+    /// have the pin.
+    /// Returns [`ErrorKind::UnknownChip`] if the chip was not set with
+    /// [`FtHal::set_chip`] or [`FtHal::detect_chip`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::eh1::digital::OutputPin;
+    /// use hal::sim::SimDevice;
+    /// use hal::{Chip, Pin};
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// hal.set_chip(Chip::FT232H)?;
+    /// let mut reset = hal.open_drain_pin(Pin::Upper(3))?;
+    /// reset.set_low()?;
+    /// // released, the pull-up returns the line high
+    /// reset.set_high()?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_drain_pin(&self, pin: Pin) -> Result<OutputPin<Device>, Error<E>> {
+        OutputPin::new_open_drain(self.mtx.clone(), pin)
     }
 
     /// Aquire the I2C peripheral for the FT232H.
@@ -488,9 +930,9 @@ where
     /// Yes, AD1 and AD2 are both SDA.
     /// These pins must be shorted together for I2C operation.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if pin 0, 1, or 2 are already in use.
+    /// Returns [`ErrorKind::PinInUse`] if pin 0, 1, or 2 are already in use.
     ///
     /// # Example
     ///
@@ -511,289 +953,329 @@ where
 
     /// Aquire the digital output pin 0 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad0(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(0))
     }
 
     /// Aquire the digital input pin 0 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi0(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(0))
     }
 
     /// Aquire the digital output pin 1 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad1(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(1))
     }
 
     /// Aquire the digital input pin 1 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi1(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(1))
     }
 
     /// Aquire the digital output pin 2 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad2(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(2))
     }
 
     /// Aquire the digital input pin 2 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi2(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(2))
     }
 
     /// Aquire the digital output pin 3 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad3(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(3))
     }
 
     /// Aquire the digital input pin 3 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi3(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(3))
     }
 
     /// Aquire the digital output pin 4 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad4(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(4))
     }
 
     /// Aquire the digital input pin 4 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi4(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(4))
     }
 
     /// Aquire the digital output pin 5 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad5(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(5))
     }
 
     /// Aquire the digital input pin 5 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi5(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(5))
     }
 
     /// Aquire the digital output pin 6 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad6(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(6))
     }
 
     /// Aquire the digital input pin 6 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi6(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(6))
     }
 
     /// Aquire the digital output pin 7 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ad7(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Lower(7))
     }
 
     /// Aquire the digital input pin 7 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn adi7(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Lower(7))
     }
 
     /// Aquire the digital output upper pin 0 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c0(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(0))
     }
 
     /// Aquire the digital input upper pin 0 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci0(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(0))
     }
 
     /// Aquire the digital output upper pin 1 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c1(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(1))
     }
 
     /// Aquire the digital input upper pin 1 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci1(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(1))
     }
 
     /// Aquire the digital output upper pin 2 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c2(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(2))
     }
 
     /// Aquire the digital input upper pin 2 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci2(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(2))
     }
 
     /// Aquire the digital output upper pin 3 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c3(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(3))
     }
 
     /// Aquire the digital input upper pin 3 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci3(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(3))
     }
 
     /// Aquire the digital output upper pin 4 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c4(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(4))
     }
 
     /// Aquire the digital input upper pin 4 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci4(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(4))
     }
 
     /// Aquire the digital output upper pin 5 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c5(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(5))
     }
 
     /// Aquire the digital input upper pin 5 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci5(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(5))
     }
 
     /// Aquire the digital output upper pin 6 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c6(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(6))
     }
 
     /// Aquire the digital input upper pin 6 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci6(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(6))
     }
 
     /// Aquire the digital output upper pin 7 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn c7(&self) -> Result<OutputPin<Device>, Error<E>> {
         OutputPin::new(self.mtx.clone(), Pin::Upper(7))
     }
 
     /// Aquire the digital input upper pin 7 for the FT232H.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the pin is already in-use.
+    /// Returns [`ErrorKind::PinInUse`] if the pin is already in use.
     pub fn ci7(&self) -> Result<InputPin<Device>, Error<E>> {
         InputPin::new(self.mtx.clone(), Pin::Upper(7))
     }
 }
+
+impl<Device, E> FtHal<Device>
+where
+    Device: MpsseCmdExecutor<Error = E> + ChipInfo,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    /// Set the chip reported by the device, see [`FtHal::set_chip`].
+    ///
+    /// Only the `libftd2xx` device types report the chip, this returns
+    /// `None` for the `ftdi` backend, which needs [`FtHal::set_chip`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ftdi_embedded_hal as hal;
+    ///
+    /// # #[cfg(feature = "libftd2xx")]
+    /// # {
+    /// let device = libftd2xx::Ft4232h::with_description("Quad RS232-HS A")?;
+    /// let hal = hal::FtHal::init_default(device)?;
+    /// hal.detect_chip()?;
+    /// // the FT4232H has no upper byte pins
+    /// assert!(hal.c0().is_err());
+    /// # }
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn detect_chip(&self) -> Result<Option<Chip>, Error<E>> {
+        let chip: Option<Chip> = self
+            .mtx
+            .lock()
+            .expect("Failed to aquire FTDI mutex")
+            .ft
+            .chip();
+        if let Some(chip) = chip {
+            self.set_chip(chip)?;
+        }
+        Ok(chip)
+    }
+}
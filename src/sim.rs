@@ -0,0 +1,822 @@
+//! Simulated MPSSE device.
+//!
+//! [`SimDevice`] implements [`MpsseCmdExecutor`] without any hardware.
+//! It interprets the MPSSE command stream the same way an FT232H does:
+//! GPIO commands update the ADBUS and ACBUS pins, data shifting commands
+//! toggle TCK and sample TDO, and read commands queue the bytes that are
+//! returned by [`recv`].
+//!
+//! The simulation runs on a virtual clock.
+//! Every command advances the clock by the time the MPSSE would take to
+//! execute it, see [`SimDevice::elapsed`].
+//!
+//! Undriven lines are pulled high, which is what an I2C bus with pull-up
+//! resistors, or an unconnected input on an FTDI breakout board, reads.
+//!
+//! # Peripherals
+//!
+//! Behavioral models are attached to the lines with [`SimDevice::attach`].
+//! A [`Peripheral`] observes the line levels and drives lines in response.
+//! Adapters are provided for the common cases:
+//!
+//! * [`I2cPeripheral`] turns the SCL and SDA edges into calls to an
+//!   [`I2cTarget`].
+//! * [`SpiPeripheral`] turns the edges of the SPI lines into calls to a
+//!   [`SpiTarget`] while its chip select is asserted.
+//! * [`Waveform`] drives a scripted signal, for example into an [`InputPin`].
+//!
+//! Reference models of an [`At24c04`] EEPROM, an [`Lm75`] temperature sensor
+//! and a [`SpiFlash`] NOR flash are included.
+//!
+//! ```
+//! use eh1::i2c::I2c;
+//! use ftdi_embedded_hal as hal;
+//! use hal::Pin;
+//! use hal::sim::{I2cPeripheral, Lm75, SimDevice};
+//!
+//! let sensor = Lm75::new(0x48);
+//! sensor.set_temperature(21.5);
+//!
+//! let mut device = SimDevice::new();
+//! device.connect(Pin::Lower(1), Pin::Lower(2));
+//! device.attach(I2cPeripheral::new(sensor.clone()));
+//!
+//! let hal = hal::FtHal::init_freq(device, 400_000)?;
+//! let mut i2c = hal.i2c()?;
+//! let mut buf: [u8; 2] = [0; 2];
+//! i2c.write_read(0x48_u8, &[0x00], &mut buf)?;
+//! assert_eq!(buf, [21, 0x80]);
+//! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use ftdi_embedded_hal as hal;
+//! use hal::Pin;
+//! use hal::eh1::spi::SpiBus;
+//! use hal::sim::SimDevice;
+//!
+//! let mut device = SimDevice::new();
+//! // short SDO (AD1) to SDI (AD2)
+//! device.connect(Pin::Lower(1), Pin::Lower(2));
+//!
+//! let hal = hal::FtHal::init_freq(device, 1_000_000)?;
+//! let mut spi = hal.spi()?;
+//!
+//! let mut read: [u8; 4] = [0; 4];
+//! spi.transfer(&mut read, &[0xde, 0xad, 0xbe, 0xef])?;
+//! assert_eq!(read, [0xde, 0xad, 0xbe, 0xef]);
+//! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`recv`]: MpsseCmdExecutor::recv
+//! [`InputPin`]: crate::InputPin
+use crate::disasm::{BIT_MODE, MpsseOp, READ_TDO, WRITE_TMS};
+use crate::gpio::Pin;
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use std::collections::VecDeque;
+use std::{fmt, ops, time::Duration};
+
+mod at24c04;
+mod flash;
+mod i2c;
+mod lm75;
+mod spi;
+mod waveform;
+
+pub use at24c04::At24c04;
+pub use flash::SpiFlash;
+pub use i2c::{I2cPeripheral, I2cRegisters, I2cTarget};
+pub use lm75::Lm75;
+pub use spi::{SpiPeripheral, SpiTarget};
+pub use waveform::Waveform;
+
+/// Number of MPSSE lines, ADBUS0-7 followed by ACBUS0-7.
+const LINES: usize = 16;
+
+/// TCK / SK line mask.
+const TCK: u16 = 1 << 0;
+/// TDI / DO line mask.
+const TDI: u16 = 1 << 1;
+/// TDO / DI line mask.
+const TDO: u16 = 1 << 2;
+/// TMS / CS line mask.
+const TMS: u16 = 1 << 3;
+/// GPIOL1 line mask, used by the wait on I/O and clock until I/O commands.
+const GPIOL1: u16 = 1 << 5;
+/// GPIOL3 line mask, used as RTCK for adaptive clocking.
+const GPIOL3: u16 = 1 << 7;
+
+/// Data shifting opcode flag: write on the negative edge.
+const NEG_OUT: u8 = 0x01;
+/// Data shifting opcode flag: read on the negative edge.
+const NEG_IN: u8 = 0x04;
+/// Data shifting opcode flag: LSB first.
+const LSB_FIRST: u8 = 0x08;
+
+/// Frequency of the MPSSE master clock in Hz.
+///
+/// Virtual time is counted in periods of this clock.
+const MASTER_CLOCK: u64 = 60_000_000;
+
+/// Master clock periods taken by commands that do not toggle TCK.
+const CMD_TICKS: u64 = 1;
+
+/// Number of TCK cycles after which a clock until I/O command is considered
+/// to be stuck.
+const STALL_CYCLES: usize = 1 << 20;
+
+/// Maximum number of peripheral updates before the lines are considered to
+/// be oscillating.
+const SETTLE_LIMIT: usize = 16;
+
+/// Simulator error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SimError {
+    /// A command was sent before [`MpsseCmdExecutor::init`].
+    NotInitialized,
+    /// `recv` requested more bytes than the MPSSE produced.
+    ///
+    /// Real hardware would wait for the read timeout to expire.
+    ReadTimeout {
+        /// Number of bytes requested.
+        requested: usize,
+        /// Number of bytes available.
+        available: usize,
+    },
+    /// The MPSSE is waiting on a line that will never change.
+    ///
+    /// Real hardware would stop processing commands.
+    Stalled,
+    /// The clock frequency requested in [`MpsseSettings`] is out of range.
+    ClockFrequency(u32),
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::NotInitialized => write!(f, "MPSSE is not initialized"),
+            SimError::ReadTimeout {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Read timeout, requested {requested} bytes but only {available} are available"
+            ),
+            SimError::Stalled => write!(f, "MPSSE is stalled waiting on an I/O line"),
+            SimError::ClockFrequency(freq) => {
+                write!(f, "Clock frequency {freq} Hz is out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/// Set of MPSSE lines.
+///
+/// ADBUS0-ADBUS7 are bits 0-7 and ACBUS0-ACBUS7 are bits 8-15.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lines(pub u16);
+
+impl Lines {
+    /// Empty set.
+    pub const NONE: Lines = Lines(0);
+
+    /// Returns `true` if the set contains `pin`.
+    pub fn contains(self, pin: Pin) -> bool {
+        self.0 & Lines::from(pin).0 != 0
+    }
+
+    /// Add `pin` to the set if `present` is `true`, otherwise remove it.
+    pub fn set(&mut self, pin: Pin, present: bool) {
+        if present {
+            self.0 |= Lines::from(pin).0;
+        } else {
+            self.0 &= !Lines::from(pin).0;
+        }
+    }
+}
+
+impl From<Pin> for Lines {
+    fn from(pin: Pin) -> Self {
+        Lines(1 << pin.line())
+    }
+}
+
+impl ops::BitOr for Lines {
+    type Output = Lines;
+
+    fn bitor(self, rhs: Lines) -> Lines {
+        Lines(self.0 | rhs.0)
+    }
+}
+
+/// Simulated peripheral attached to the MPSSE lines.
+///
+/// The FTDI outputs are stronger than any peripheral; a line driven by the
+/// MPSSE ignores the peripherals.
+/// Otherwise a line is low if any peripheral drives it low, high if any
+/// peripheral drives it high, and at its idle level if nothing drives it.
+pub trait Peripheral: Send {
+    /// Observe the line levels.
+    ///
+    /// This is called after the levels change, when the time reported by
+    /// [`next_event`] is reached, and may be called at any other time.
+    ///
+    /// [`next_event`]: Peripheral::next_event
+    fn update(&mut self, now: Duration, levels: Lines);
+
+    /// Lines driven low by the peripheral.
+    fn drive_low(&self) -> Lines;
+
+    /// Lines driven high by the peripheral.
+    fn drive_high(&self) -> Lines {
+        Lines::NONE
+    }
+
+    /// Time at which the peripheral will change its outputs without any
+    /// change on its inputs.
+    fn next_event(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Simulated FT232H MPSSE.
+///
+/// See the [module documentation](self) for an overview.
+pub struct SimDevice {
+    /// Set by [`MpsseCmdExecutor::init`].
+    initialized: bool,
+    /// GPIO value, ADBUS in the lower byte and ACBUS in the upper byte.
+    value: u16,
+    /// GPIO direction, `1` is output.
+    direction: u16,
+    /// Lines that only drive a `0` and tri-state on a `1`.
+    drive_zero: u16,
+    /// Level of lines that nothing drives.
+    bias: u16,
+    /// Mask of the lines electrically connected to each line.
+    nets: [u16; LINES],
+    /// Resolved line levels.
+    levels: u16,
+    /// TCK clock divisor.
+    divisor: u16,
+    /// Divide the master clock by 5.
+    div5: bool,
+    /// 3 phase data clocking.
+    three_phase: bool,
+    /// Adaptive clocking.
+    adaptive: bool,
+    /// Internal TDI to TDO loopback.
+    loopback: bool,
+    /// Incomplete command waiting for more bytes.
+    pending: Vec<u8>,
+    /// Bytes waiting to be read.
+    rx: VecDeque<u8>,
+    /// Virtual time in master clock periods.
+    ticks: u64,
+    /// Attached peripherals.
+    peripherals: Vec<Box<dyn Peripheral>>,
+}
+
+impl fmt::Debug for SimDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimDevice")
+            .field("initialized", &self.initialized)
+            .field("value", &self.value)
+            .field("direction", &self.direction)
+            .field("levels", &self.levels)
+            .field("ticks", &self.ticks)
+            .field("peripherals", &self.peripherals.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for SimDevice {
+    fn default() -> Self {
+        SimDevice::new()
+    }
+}
+
+impl SimDevice {
+    /// Create a new simulated FT232H.
+    ///
+    /// All lines are inputs, pulled high, and not connected to each other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    ///
+    /// let hal = hal::FtHal::init_default(hal::sim::SimDevice::new())?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new() -> SimDevice {
+        let mut nets: [u16; LINES] = [0; LINES];
+        for (line, net) in nets.iter_mut().enumerate() {
+            *net = 1 << line;
+        }
+
+        SimDevice {
+            initialized: false,
+            value: 0,
+            direction: 0,
+            drive_zero: 0,
+            bias: 0xFFFF,
+            nets,
+            levels: 0xFFFF,
+            divisor: 0,
+            div5: true,
+            three_phase: false,
+            adaptive: false,
+            loopback: false,
+            pending: Vec::new(),
+            rx: VecDeque::new(),
+            ticks: 0,
+            peripherals: Vec::new(),
+        }
+    }
+
+    /// Attach a peripheral to the lines.
+    pub fn attach<P: Peripheral + 'static>(&mut self, peripheral: P) {
+        self.peripherals.push(Box::new(peripheral));
+        self.settle();
+    }
+
+    /// Let virtual time pass without executing any commands.
+    ///
+    /// This is the simulated equivalent of sleeping between transfers, for
+    /// example to wait for an EEPROM write cycle or a [`Waveform`] edge.
+    pub fn advance(&mut self, duration: Duration) {
+        let ticks: u128 = duration.as_nanos() * u128::from(MASTER_CLOCK) / 1_000_000_000;
+        self.tick(ticks as u64);
+    }
+
+    /// Electrically connect two pins.
+    ///
+    /// This is the equivalent of a jumper wire between the pins, for example
+    /// AD1 and AD2 must be connected for I2C.
+    pub fn connect(&mut self, a: Pin, b: Pin) {
+        let net: u16 = self.nets[a.line()] | self.nets[b.line()];
+        for line in 0..LINES {
+            if net & (1 << line) != 0 {
+                self.nets[line] = net;
+            }
+        }
+        self.settle();
+    }
+
+    /// Set the level of a pin when nothing drives it.
+    ///
+    /// This models a pull-up or pull-down resistor, or an external signal
+    /// that is weaker than the FTDI outputs.
+    pub fn set_level(&mut self, pin: Pin, high: bool) {
+        if high {
+            self.bias |= 1 << pin.line();
+        } else {
+            self.bias &= !(1 << pin.line());
+        }
+        self.settle();
+    }
+
+    /// Get the level of a pin.
+    pub fn level(&self, pin: Pin) -> bool {
+        self.levels & (1 << pin.line()) != 0
+    }
+
+    /// GPIO value of the lower byte (ADBUS) as last set by the MPSSE.
+    pub fn lower_value(&self) -> u8 {
+        self.value as u8
+    }
+
+    /// GPIO direction of the lower byte (ADBUS), `1` is output.
+    pub fn lower_direction(&self) -> u8 {
+        self.direction as u8
+    }
+
+    /// GPIO value of the upper byte (ACBUS) as last set by the MPSSE.
+    pub fn upper_value(&self) -> u8 {
+        (self.value >> 8) as u8
+    }
+
+    /// GPIO direction of the upper byte (ACBUS), `1` is output.
+    pub fn upper_direction(&self) -> u8 {
+        (self.direction >> 8) as u8
+    }
+
+    /// Open-drain (drive only zero) outputs of the lower byte (ADBUS).
+    pub fn lower_drive_zero(&self) -> u8 {
+        self.drive_zero as u8
+    }
+
+    /// Open-drain (drive only zero) outputs of the upper byte (ACBUS).
+    pub fn upper_drive_zero(&self) -> u8 {
+        (self.drive_zero >> 8) as u8
+    }
+
+    /// TCK frequency in Hz.
+    pub fn clock_frequency(&self) -> u32 {
+        let base: u64 = if self.div5 {
+            MASTER_CLOCK / 5
+        } else {
+            MASTER_CLOCK
+        };
+        (base / (2 * (u64::from(self.divisor) + 1))) as u32
+    }
+
+    /// Returns `true` if 3 phase data clocking is enabled.
+    pub fn is_3phase(&self) -> bool {
+        self.three_phase
+    }
+
+    /// Returns `true` if adaptive clocking is enabled.
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// Returns `true` if the internal TDI to TDO loopback is enabled.
+    pub fn is_loopback(&self) -> bool {
+        self.loopback
+    }
+
+    /// Virtual time spent executing commands since the device was created.
+    pub fn elapsed(&self) -> Duration {
+        let nanos: u128 = u128::from(self.ticks) * 1_000_000_000 / u128::from(MASTER_CLOCK);
+        Duration::from_nanos(nanos as u64)
+    }
+
+    /// Number of bytes waiting to be read with `recv`.
+    pub fn rx_len(&self) -> usize {
+        self.rx.len()
+    }
+
+    /// Resolve the line levels from the MPSSE and peripheral drivers.
+    fn resolve(&self) -> u16 {
+        let host_low: u16 = self.direction & !self.value;
+        let host_high: u16 = self.direction & self.value & !self.drive_zero;
+        let (low, high): (u16, u16) =
+            self.peripherals
+                .iter()
+                .fold((0, 0), |(low, high), peripheral| {
+                    (
+                        low | peripheral.drive_low().0,
+                        high | peripheral.drive_high().0,
+                    )
+                });
+
+        let mut levels: u16 = 0;
+        let mut done: u16 = 0;
+        for &net in self.nets.iter() {
+            if done & net != 0 {
+                continue;
+            }
+            done |= net;
+
+            let high: bool = if host_low & net != 0 {
+                false
+            } else if host_high & net != 0 {
+                true
+            } else if low & net != 0 {
+                false
+            } else if high & net != 0 {
+                true
+            } else {
+                self.bias & net == net
+            };
+            if high {
+                levels |= net;
+            }
+        }
+        levels
+    }
+
+    /// Recompute the line levels and let the peripherals react to them.
+    fn settle(&mut self) {
+        let now: Duration = self.elapsed();
+        let mut levels: u16 = self.resolve();
+        for _ in 0..SETTLE_LIMIT {
+            self.levels = levels;
+            for peripheral in self.peripherals.iter_mut() {
+                peripheral.update(now, Lines(levels));
+            }
+            let settled: u16 = self.resolve();
+            if settled == levels {
+                return;
+            }
+            levels = settled;
+        }
+        self.levels = levels;
+    }
+
+    /// Earliest peripheral event in master clock periods.
+    fn next_event(&self) -> Option<u64> {
+        self.peripherals
+            .iter()
+            .filter_map(|peripheral| peripheral.next_event())
+            .min()
+            .map(|time| {
+                let ticks: u128 = time.as_nanos() * u128::from(MASTER_CLOCK);
+                ticks.div_ceil(1_000_000_000) as u64
+            })
+    }
+
+    /// Advance virtual time, running any peripheral events on the way.
+    fn tick(&mut self, ticks: u64) {
+        let end: u64 = self.ticks + ticks;
+        while let Some(event) = self.next_event() {
+            if event <= self.ticks || event > end {
+                break;
+            }
+            self.ticks = event;
+            self.settle();
+        }
+        self.ticks = end;
+    }
+
+    /// Half of a TCK period in master clock periods.
+    fn half_period(&self) -> u64 {
+        let ticks: u64 = u64::from(self.divisor) + 1;
+        if self.div5 { ticks * 5 } else { ticks }
+    }
+
+    /// Wait until a line reaches a level.
+    fn wait_for(&mut self, line: u16, high: bool) -> Result<(), SimError> {
+        while (self.levels & line != 0) != high {
+            match self.next_event() {
+                Some(event) if event > self.ticks => {
+                    self.ticks = event;
+                    self.settle();
+                }
+                _ => return Err(SimError::Stalled),
+            }
+        }
+        Ok(())
+    }
+
+    /// Set or clear bits of the GPIO value.
+    fn set_value(&mut self, mask: u16, high: bool) {
+        if high {
+            self.value |= mask;
+        } else {
+            self.value &= !mask;
+        }
+        self.settle();
+    }
+
+    /// Sample the TDO line.
+    fn tdo(&self) -> bool {
+        if self.loopback {
+            self.value & TDI != 0
+        } else {
+            self.levels & TDO != 0
+        }
+    }
+
+    /// Run one TCK cycle, optionally shifting a bit out on `out`.
+    ///
+    /// Returns the bit sampled on TDO.
+    fn clock_cycle(&mut self, opcode: u8, out: Option<(u16, bool)>) -> Result<bool, SimError> {
+        let idle: bool = self.value & TCK != 0;
+        // the leading edge is falling when the clock idles high
+        let out_on_leading: bool = (opcode & NEG_OUT != 0) == idle;
+        let in_on_leading: bool = (opcode & NEG_IN != 0) == idle;
+        let half: u64 = self.half_period();
+
+        if let Some((line, bit)) = out
+            && !out_on_leading
+        {
+            self.set_value(line, bit);
+        }
+        self.tick(half);
+
+        // both sides sample before either side changes its outputs
+        let lead: bool = self.tdo();
+        self.set_value(TCK, !idle);
+        if self.adaptive {
+            self.wait_for(GPIOL3, !idle)?;
+        }
+        if let Some((line, bit)) = out
+            && out_on_leading
+        {
+            self.set_value(line, bit);
+        }
+        self.tick(half);
+
+        let trail: bool = self.tdo();
+        self.set_value(TCK, idle);
+        if self.adaptive {
+            self.wait_for(GPIOL3, idle)?;
+        }
+        if self.three_phase {
+            self.tick(half);
+        }
+
+        Ok(if in_on_leading { lead } else { trail })
+    }
+
+    /// Execute a data shifting command.
+    fn shift(&mut self, opcode: u8, len: usize, data: &[u8]) -> Result<(), SimError> {
+        let lsb: bool = opcode & LSB_FIRST != 0;
+        let read: bool = opcode & READ_TDO != 0;
+
+        if opcode & WRITE_TMS != 0 {
+            // TMS bits are always shifted LSB first, bit 7 is held on TDI
+            let byte: u8 = data[0];
+            self.set_value(TDI, byte & 0x80 != 0);
+            let mut rx: u8 = 0;
+            for idx in 0..len {
+                let bit: bool = self.clock_cycle(opcode, Some((TMS, byte & (1 << idx) != 0)))?;
+                rx = (rx >> 1) | (u8::from(bit) << 7);
+            }
+            if read {
+                self.rx.push_back(rx);
+            }
+            return Ok(());
+        }
+
+        let (nbytes, nbits): (usize, usize) = if opcode & BIT_MODE != 0 {
+            (1, len)
+        } else {
+            (len, 8)
+        };
+
+        for idx in 0..nbytes {
+            let byte: Option<u8> = data.get(idx).copied();
+            let mut rx: u8 = 0;
+            for bit_idx in 0..nbits {
+                let out: Option<(u16, bool)> = byte.map(|byte| {
+                    let mask: u8 = if lsb { 1 << bit_idx } else { 0x80 >> bit_idx };
+                    (TDI, byte & mask != 0)
+                });
+                let bit: bool = self.clock_cycle(opcode, out)?;
+                rx = if lsb {
+                    (rx >> 1) | (u8::from(bit) << 7)
+                } else {
+                    (rx << 1) | u8::from(bit)
+                };
+            }
+            if read {
+                self.rx.push_back(rx);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clock without data until GPIOL1 reaches a level.
+    fn clock_until_io(&mut self, high: bool, max_cycles: Option<usize>) -> Result<(), SimError> {
+        let mut cycles: usize = 0;
+        while (self.levels & GPIOL1 != 0) != high {
+            if max_cycles == Some(cycles) {
+                break;
+            } else if cycles == STALL_CYCLES {
+                return Err(SimError::Stalled);
+            }
+            self.clock_cycle(0, None)?;
+            cycles += 1;
+        }
+        Ok(())
+    }
+
+    /// Set a GPIO byte.
+    fn set_gpio(&mut self, shift: u32, value: u8, direction: u8) {
+        let mask: u16 = 0xFF << shift;
+        self.value = (self.value & !mask) | (u16::from(value) << shift);
+        self.direction = (self.direction & !mask) | (u16::from(direction) << shift);
+        self.settle();
+        self.tick(CMD_TICKS);
+    }
+
+    /// Read a GPIO byte.
+    fn gpio(&mut self, shift: u32) {
+        self.rx.push_back((self.levels >> shift) as u8);
+        self.tick(CMD_TICKS);
+    }
+
+    /// Execute a single command.
+    fn execute(&mut self, op: MpsseOp) -> Result<(), SimError> {
+        if let Some((opcode, len, data)) = op.as_shift() {
+            return self.shift(opcode, len, data);
+        }
+
+        match op {
+            MpsseOp::SetGpioLower { value, direction } => self.set_gpio(0, value, direction),
+            MpsseOp::SetGpioUpper { value, direction } => self.set_gpio(8, value, direction),
+            MpsseOp::GpioLower => self.gpio(0),
+            MpsseOp::GpioUpper => self.gpio(8),
+            MpsseOp::EnableLoopback => self.loopback = true,
+            MpsseOp::DisableLoopback => self.loopback = false,
+            MpsseOp::SetClockDivisor(divisor) => self.divisor = divisor,
+            MpsseOp::SendImmediate => {}
+            MpsseOp::WaitOnIoHigh => self.wait_for(GPIOL1, true)?,
+            MpsseOp::WaitOnIoLow => self.wait_for(GPIOL1, false)?,
+            MpsseOp::DisableClockDivide => self.div5 = false,
+            MpsseOp::EnableClockDivide => self.div5 = true,
+            MpsseOp::Enable3Phase => self.three_phase = true,
+            MpsseOp::Disable3Phase => self.three_phase = false,
+            MpsseOp::ClockBitsNoData(len) => {
+                for _ in 0..len {
+                    self.clock_cycle(0, None)?;
+                }
+            }
+            MpsseOp::ClockBytesNoData(len) => {
+                for _ in 0..len * 8 {
+                    self.clock_cycle(0, None)?;
+                }
+            }
+            MpsseOp::ClockUntilIoHigh => self.clock_until_io(true, None)?,
+            MpsseOp::ClockUntilIoLow => self.clock_until_io(false, None)?,
+            MpsseOp::ClockBytesUntilIoHigh(len) => self.clock_until_io(true, Some(len * 8))?,
+            MpsseOp::ClockBytesUntilIoLow(len) => self.clock_until_io(false, Some(len * 8))?,
+            MpsseOp::EnableAdaptive => self.adaptive = true,
+            MpsseOp::DisableAdaptive => self.adaptive = false,
+            MpsseOp::DriveOnlyZero { lower, upper } => {
+                self.drive_zero = u16::from_le_bytes([lower, upper]);
+                self.settle();
+            }
+            MpsseOp::Invalid(opcode) => {
+                // bad command response
+                self.rx.push_back(0xFA);
+                self.rx.push_back(opcode);
+            }
+            _ => unreachable!("data shifting commands are handled above"),
+        }
+        Ok(())
+    }
+}
+
+impl MpsseCmdExecutor for SimDevice {
+    type Error = SimError;
+
+    fn init(&mut self, settings: &MpsseSettings) -> Result<(), SimError> {
+        if settings.reset {
+            *self = SimDevice {
+                nets: self.nets,
+                bias: self.bias,
+                ticks: self.ticks,
+                peripherals: std::mem::take(&mut self.peripherals),
+                ..SimDevice::new()
+            };
+            self.settle();
+        }
+        self.pending.clear();
+        self.rx.clear();
+        self.initialized = true;
+
+        if let Some(frequency) = settings.clock_frequency {
+            if frequency == 0 || frequency > 30_000_000 {
+                return Err(SimError::ClockFrequency(frequency));
+            }
+            let (divisor, div5): (u32, bool) = if frequency <= 6_000_000 {
+                (6_000_000 / frequency - 1, true)
+            } else {
+                (30_000_000 / frequency - 1, false)
+            };
+            self.divisor =
+                u16::try_from(divisor).map_err(|_| SimError::ClockFrequency(frequency))?;
+            self.div5 = div5;
+        }
+
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), SimError> {
+        if !self.initialized {
+            return Err(SimError::NotInitialized);
+        }
+
+        let mut buf: Vec<u8> = std::mem::take(&mut self.pending);
+        buf.extend_from_slice(data);
+
+        let mut offset: usize = 0;
+        while let Some((op, len)) = MpsseOp::parse(&buf[offset..]) {
+            offset += len;
+            self.execute(op)?;
+        }
+
+        buf.drain(..offset);
+        self.pending = buf;
+        Ok(())
+    }
+
+    fn recv(&mut self, data: &mut [u8]) -> Result<(), SimError> {
+        if !self.initialized {
+            return Err(SimError::NotInitialized);
+        }
+        if data.len() > self.rx.len() {
+            return Err(SimError::ReadTimeout {
+                requested: data.len(),
+                available: self.rx.len(),
+            });
+        }
+
+        let len: usize = data.len();
+        for (dst, src) in data.iter_mut().zip(self.rx.drain(..len)) {
+            *dst = src;
+        }
+        Ok(())
+    }
+}
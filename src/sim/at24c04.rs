@@ -0,0 +1,127 @@
+use super::I2cTarget;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Memory size in bytes.
+const SIZE: usize = 512;
+/// Page size in bytes.
+const PAGE: usize = 16;
+
+/// EEPROM state.
+#[derive(Debug)]
+struct Eeprom {
+    address: u8,
+    memory: Vec<u8>,
+    write_time: Duration,
+    /// End of the internal write cycle.
+    busy_until: Duration,
+    /// Address counter.
+    pointer: usize,
+    /// The word address was received in the current write.
+    addressed: bool,
+    /// Bytes latched in the page buffer.
+    page: Vec<(usize, u8)>,
+}
+
+/// Atmel AT24C04 4 Kbit (512 byte) I2C EEPROM.
+///
+/// The EEPROM responds to two consecutive addresses, the least significant
+/// bit of the device address selects the upper or lower 256 bytes.
+/// Page writes wrap within 16 byte pages and are committed at the stop
+/// condition; the EEPROM does not acknowledge its address until the write
+/// cycle (5 ms by default) completes.
+///
+/// This is a handle, clones refer to the same EEPROM.
+///
+/// # Example
+///
+/// ```
+/// use ftdi_embedded_hal::sim::{At24c04, I2cPeripheral, SimDevice};
+///
+/// let eeprom = At24c04::new(0x50);
+///
+/// let mut device = SimDevice::new();
+/// device.attach(I2cPeripheral::new(eeprom.clone()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct At24c04 {
+    inner: Arc<Mutex<Eeprom>>,
+}
+
+impl At24c04 {
+    /// Create a new erased EEPROM.
+    ///
+    /// `address` is the 7-bit device address of the lower 256 bytes,
+    /// `0x50` when the A2 and A1 pins are tied low.
+    pub fn new(address: u8) -> At24c04 {
+        At24c04 {
+            inner: Arc::new(Mutex::new(Eeprom {
+                address: address & !1,
+                memory: vec![0xFF; SIZE],
+                write_time: Duration::from_millis(5),
+                busy_until: Duration::ZERO,
+                pointer: 0,
+                addressed: false,
+                page: Vec::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Eeprom> {
+        self.inner.lock().expect("Failed to aquire EEPROM mutex")
+    }
+
+    /// Set the duration of the internal write cycle.
+    pub fn set_write_time(&self, write_time: Duration) {
+        self.lock().write_time = write_time;
+    }
+
+    /// Get the memory contents.
+    pub fn memory(&self) -> Vec<u8> {
+        self.lock().memory.clone()
+    }
+}
+
+impl I2cTarget for At24c04 {
+    fn start(&mut self, now: Duration, address: u8, _read: bool) -> bool {
+        let mut eeprom = self.lock();
+        if address & !1 != eeprom.address || now < eeprom.busy_until {
+            return false;
+        }
+        eeprom.addressed = false;
+        eeprom.page.clear();
+        eeprom.pointer = (usize::from(address & 1) << 8) | (eeprom.pointer & 0xFF);
+        true
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        let mut eeprom = self.lock();
+        if eeprom.addressed {
+            let pointer: usize = eeprom.pointer;
+            eeprom.page.push((pointer, byte));
+            eeprom.pointer = (pointer & !(PAGE - 1)) | ((pointer + 1) & (PAGE - 1));
+        } else {
+            eeprom.pointer = (eeprom.pointer & 0x100) | usize::from(byte);
+            eeprom.addressed = true;
+        }
+        true
+    }
+
+    fn read(&mut self) -> u8 {
+        let mut eeprom = self.lock();
+        let pointer: usize = eeprom.pointer;
+        eeprom.pointer = (pointer + 1) % SIZE;
+        eeprom.memory[pointer]
+    }
+
+    fn stop(&mut self, now: Duration) {
+        let mut eeprom = self.lock();
+        if eeprom.page.is_empty() {
+            return;
+        }
+        for (pointer, byte) in std::mem::take(&mut eeprom.page) {
+            eeprom.memory[pointer] = byte;
+        }
+        eeprom.busy_until = now + eeprom.write_time;
+    }
+}
@@ -0,0 +1,212 @@
+use super::SpiTarget;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Status register write enable latch bit.
+const WEL: u8 = 1 << 1;
+
+/// Page size in bytes.
+const PAGE: usize = 256;
+/// Sector size in bytes.
+const SECTOR: usize = 4 * 1024;
+/// Block size in bytes.
+const BLOCK: usize = 64 * 1024;
+
+/// Command being executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// Waiting for an opcode.
+    Opcode,
+    /// Reading the JEDEC ID, index of the next byte.
+    JedecId(usize),
+    /// Reading the status register.
+    Status,
+    /// Receiving the address of a command, opcode and number of address
+    /// bytes received.
+    Address(u8, usize),
+    /// Reading data.
+    Read,
+    /// Programming a page.
+    Program,
+    /// Erase of `len` bytes at the address, executed on deselect.
+    Erase(usize),
+    /// Chip erase, executed on deselect.
+    ChipErase,
+    /// Ignoring bytes until deselect.
+    Ignore,
+}
+
+/// Flash state.
+#[derive(Debug)]
+struct Flash {
+    jedec_id: [u8; 3],
+    memory: Vec<u8>,
+    status: u8,
+    command: Command,
+    address: usize,
+    /// Bytes latched by a page program.
+    page: Vec<(usize, u8)>,
+}
+
+/// 25-series SPI NOR flash.
+///
+/// Supports the JEDEC ID (`0x9F`), read status (`0x05`), write
+/// enable/disable (`0x06`/`0x04`), read (`0x03`), page program (`0x02`),
+/// sector erase (`0x20`), block erase (`0xD8`) and chip erase
+/// (`0xC7`/`0x60`) commands with 24-bit addresses.
+///
+/// Program and erase operations complete when the chip select is
+/// deasserted, the write in progress status bit is never set.
+///
+/// This is a handle, clones refer to the same flash.
+///
+/// # Example
+///
+/// ```
+/// use ftdi_embedded_hal::Pin;
+/// use ftdi_embedded_hal::sim::{SimDevice, SpiFlash, SpiPeripheral};
+///
+/// // Winbond W25Q80
+/// let flash = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+///
+/// let mut device = SimDevice::new();
+/// device.attach(SpiPeripheral::new(
+///     Pin::Lower(3),
+///     eh1::spi::MODE_0,
+///     flash.clone(),
+/// ));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpiFlash {
+    inner: Arc<Mutex<Flash>>,
+}
+
+impl SpiFlash {
+    /// Create a new erased flash of `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is not a power of two.
+    pub fn new(jedec_id: [u8; 3], size: usize) -> SpiFlash {
+        assert!(size.is_power_of_two(), "size must be a power of two");
+        SpiFlash {
+            inner: Arc::new(Mutex::new(Flash {
+                jedec_id,
+                memory: vec![0xFF; size],
+                status: 0,
+                command: Command::Opcode,
+                address: 0,
+                page: Vec::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Flash> {
+        self.inner.lock().expect("Failed to aquire flash mutex")
+    }
+
+    /// Get the memory contents.
+    pub fn memory(&self) -> Vec<u8> {
+        self.lock().memory.clone()
+    }
+
+    /// Get the status register.
+    pub fn status(&self) -> u8 {
+        self.lock().status
+    }
+}
+
+impl SpiTarget for SpiFlash {
+    fn select(&mut self) {
+        let mut flash = self.lock();
+        flash.command = Command::Opcode;
+        flash.page.clear();
+    }
+
+    fn deselect(&mut self) {
+        let mut flash = self.lock();
+        let size: usize = flash.memory.len();
+        let address: usize = flash.address;
+        match flash.command {
+            Command::Program => {
+                for (address, byte) in std::mem::take(&mut flash.page) {
+                    flash.memory[address] &= byte;
+                }
+                flash.status &= !WEL;
+            }
+            Command::Erase(len) => {
+                let start: usize = address & !(len - 1) & (size - 1);
+                let end: usize = (start + len).min(size);
+                flash.memory[start..end].fill(0xFF);
+                flash.status &= !WEL;
+            }
+            Command::ChipErase => {
+                flash.memory.fill(0xFF);
+                flash.status &= !WEL;
+            }
+            _ => {}
+        }
+        flash.command = Command::Opcode;
+    }
+
+    fn shift_out(&mut self) -> u8 {
+        let mut flash = self.lock();
+        match flash.command {
+            Command::JedecId(idx) => {
+                flash.command = Command::JedecId(idx + 1);
+                flash.jedec_id.get(idx).copied().unwrap_or(0x00)
+            }
+            Command::Status => flash.status,
+            Command::Read => {
+                let size: usize = flash.memory.len();
+                let address: usize = flash.address;
+                flash.address = (address + 1) & (size - 1);
+                flash.memory[address]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn shift_in(&mut self, byte: u8) {
+        let mut flash = self.lock();
+        flash.command = match flash.command {
+            Command::Opcode => match byte {
+                0x9F => Command::JedecId(0),
+                0x05 => Command::Status,
+                0x06 => {
+                    flash.status |= WEL;
+                    Command::Ignore
+                }
+                0x04 => {
+                    flash.status &= !WEL;
+                    Command::Ignore
+                }
+                0xC7 | 0x60 if flash.status & WEL != 0 => Command::ChipErase,
+                0x03 | 0x02 | 0x20 | 0xD8 => {
+                    flash.address = 0;
+                    Command::Address(byte, 0)
+                }
+                _ => Command::Ignore,
+            },
+            Command::Address(opcode, count) => {
+                let size: usize = flash.memory.len();
+                flash.address = ((flash.address << 8) | usize::from(byte)) & (size - 1);
+                match (count + 1, opcode) {
+                    (3, 0x03) => Command::Read,
+                    (3, _) if flash.status & WEL == 0 => Command::Ignore,
+                    (3, 0x02) => Command::Program,
+                    (3, 0x20) => Command::Erase(SECTOR),
+                    (3, _) => Command::Erase(BLOCK),
+                    (count, _) => Command::Address(opcode, count),
+                }
+            }
+            Command::Program => {
+                let address: usize = flash.address;
+                flash.page.push((address, byte));
+                flash.address = (address & !(PAGE - 1)) | ((address + 1) & (PAGE - 1));
+                Command::Program
+            }
+            Command::Erase(_) | Command::ChipErase => Command::Ignore,
+            command => command,
+        };
+    }
+}
@@ -0,0 +1,450 @@
+use super::{Lines, Peripheral};
+use crate::gpio::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Behavioral model of an I2C target.
+///
+/// The methods are called by [`I2cPeripheral`] as the controller clocks
+/// bytes on the bus.
+pub trait I2cTarget: Send {
+    /// Address byte received after a start or repeated start condition.
+    ///
+    /// A 10-bit address starts with the reserved address `0b11110xx`
+    /// holding the two most significant address bits, followed by the low
+    /// address byte as a [`write`](I2cTarget::write) for writes, or a
+    /// repeated start with the same reserved address for reads.
+    ///
+    /// Return `true` to acknowledge the address.
+    /// A target that does not acknowledge ignores the bus until the next
+    /// start condition.
+    fn start(&mut self, now: Duration, address: u8, read: bool) -> bool;
+
+    /// Data byte written by the controller.
+    ///
+    /// Return `true` to acknowledge the byte.
+    fn write(&mut self, byte: u8) -> bool;
+
+    /// Data byte requested by the controller.
+    fn read(&mut self) -> u8;
+
+    /// Stop condition.
+    fn stop(&mut self, _now: Duration) {}
+
+    /// Time to hold SCL low for.
+    ///
+    /// This is called before the target acknowledges a byte it received,
+    /// and before it transmits a byte.
+    fn stretch(&mut self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Bus state of an [`I2cPeripheral`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for a start condition.
+    Idle,
+    /// Receiving the address byte.
+    Address,
+    /// Receiving data bytes.
+    Write,
+    /// Transmitting data bytes.
+    Read,
+}
+
+/// Adapter from the SCL and SDA lines to an [`I2cTarget`].
+///
+/// # Example
+///
+/// ```
+/// use ftdi_embedded_hal::Pin;
+/// use ftdi_embedded_hal::sim::{I2cPeripheral, I2cRegisters, SimDevice};
+///
+/// let target = I2cRegisters::new(0x20, 16);
+///
+/// let mut device = SimDevice::new();
+/// device.connect(Pin::Lower(1), Pin::Lower(2));
+/// device.attach(I2cPeripheral::new(target.clone()));
+/// ```
+#[derive(Debug)]
+pub struct I2cPeripheral<T> {
+    target: T,
+    scl: Pin,
+    sda: Pin,
+    /// Levels seen by the previous update.
+    prev_scl: bool,
+    prev_sda: bool,
+    /// SCL rose since the start condition or the previous falling edge.
+    clocked: bool,
+    state: State,
+    /// A start condition was seen since the last stop condition.
+    active: bool,
+    /// Number of SCL falling edges in the current byte, 0-9.
+    bit: u8,
+    /// Byte being shifted in or out.
+    byte: u8,
+    /// The address byte selected a read.
+    read: bool,
+    /// Acknowledge of the byte being transferred.
+    ack: bool,
+    /// The target pulls SDA low.
+    sda_low: bool,
+    /// The target holds SCL low until this time.
+    stretch_until: Option<Duration>,
+}
+
+impl<T: I2cTarget> I2cPeripheral<T> {
+    /// Create a new I2C adapter on the [`FtHal::i2c`] pins, SCL on AD0 and
+    /// SDA on AD1.
+    ///
+    /// AD1 and AD2 still need to be connected with [`SimDevice::connect`].
+    ///
+    /// [`FtHal::i2c`]: crate::FtHal::i2c
+    /// [`SimDevice::connect`]: super::SimDevice::connect
+    pub fn new(target: T) -> I2cPeripheral<T> {
+        I2cPeripheral::with_pins(target, Pin::Lower(0), Pin::Lower(1))
+    }
+
+    /// Create a new I2C adapter on arbitrary pins.
+    pub fn with_pins(target: T, scl: Pin, sda: Pin) -> I2cPeripheral<T> {
+        I2cPeripheral {
+            target,
+            scl,
+            sda,
+            prev_scl: true,
+            prev_sda: true,
+            clocked: false,
+            state: State::Idle,
+            active: false,
+            bit: 0,
+            byte: 0,
+            read: false,
+            ack: false,
+            sda_low: false,
+            stretch_until: None,
+        }
+    }
+
+    /// SCL rising edge, SDA is sampled.
+    fn sample(&mut self, sda: bool) {
+        match self.state {
+            State::Address | State::Write if self.bit < 8 => {
+                self.byte = (self.byte << 1) | u8::from(sda);
+            }
+            State::Read if self.bit == 8 => {
+                // controller ACK
+                self.ack = !sda;
+            }
+            _ => {}
+        }
+    }
+
+    /// SCL falling edge, the target may change SDA.
+    fn shift(&mut self, now: Duration) {
+        self.bit += 1;
+        match (self.state, self.bit) {
+            (State::Address, 8) => {
+                self.read = self.byte & 1 != 0;
+                self.ack = self.target.start(now, self.byte >> 1, self.read);
+                self.sda_low = self.ack;
+                if self.ack {
+                    self.stretch(now);
+                }
+            }
+            (State::Write, 8) => {
+                self.ack = self.target.write(self.byte);
+                self.sda_low = self.ack;
+                self.stretch(now);
+            }
+            (State::Address | State::Write, 9) => {
+                self.sda_low = false;
+                self.bit = 0;
+                self.byte = 0;
+                if !self.ack {
+                    self.state = State::Idle;
+                } else if self.state == State::Address && self.read {
+                    self.state = State::Read;
+                    self.load(now);
+                } else {
+                    self.state = State::Write;
+                }
+            }
+            (State::Read, 1..=7) => self.sda_low = self.byte & (0x80 >> self.bit) == 0,
+            (State::Read, 8) => self.sda_low = false,
+            (State::Read, 9) => {
+                self.bit = 0;
+                if self.ack {
+                    self.load(now);
+                } else {
+                    self.state = State::Idle;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Fetch the next byte from the target and drive its MSB.
+    fn load(&mut self, now: Duration) {
+        self.byte = self.target.read();
+        self.sda_low = self.byte & 0x80 == 0;
+        self.stretch(now);
+    }
+
+    /// Hold SCL low for as long as the target asks.
+    fn stretch(&mut self, now: Duration) {
+        let stretch: Duration = self.target.stretch();
+        if !stretch.is_zero() {
+            self.stretch_until = Some(now.saturating_add(stretch));
+        }
+    }
+}
+
+impl<T: I2cTarget> Peripheral for I2cPeripheral<T> {
+    fn update(&mut self, now: Duration, levels: Lines) {
+        if let Some(until) = self.stretch_until
+            && now >= until
+        {
+            self.stretch_until = None;
+        }
+
+        let scl: bool = levels.contains(self.scl);
+        let sda: bool = levels.contains(self.sda);
+
+        if scl && self.prev_scl && sda != self.prev_sda {
+            self.clocked = false;
+            self.sda_low = false;
+            self.bit = 0;
+            self.byte = 0;
+            if sda {
+                if self.active {
+                    self.target.stop(now);
+                }
+                self.active = false;
+                self.state = State::Idle;
+            } else {
+                self.active = true;
+                self.state = State::Address;
+            }
+        } else if scl && !self.prev_scl {
+            self.clocked = true;
+            self.sample(sda);
+        } else if !scl && self.prev_scl && self.clocked {
+            self.clocked = false;
+            self.shift(now);
+        }
+
+        self.prev_scl = scl;
+        self.prev_sda = sda;
+    }
+
+    fn drive_low(&self) -> Lines {
+        let mut lines: Lines = Lines::NONE;
+        lines.set(self.sda, self.sda_low);
+        if self.stretch_until.is_some() {
+            lines.set(self.scl, true);
+        }
+        lines
+    }
+
+    fn next_event(&self) -> Option<Duration> {
+        // a saturated stretch never ends
+        self.stretch_until.filter(|&until| until != Duration::MAX)
+    }
+}
+
+/// 10-bit addressing state of [`I2cRegisters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TenBit {
+    /// Not addressed.
+    Idle,
+    /// Header matched, waiting for the low address byte.
+    Header,
+    /// Addressed by a write, reads with a repeated start header.
+    Selected,
+}
+
+/// Register map state of [`I2cRegisters`].
+#[derive(Debug)]
+struct Registers {
+    /// 7-bit address, or 10-bit address when `ten_bit` is `Some`.
+    address: u16,
+    ten_bit: Option<TenBit>,
+    map: Vec<u8>,
+    present: bool,
+    /// Clock stretch before each ACK and transmitted byte.
+    stretch: Duration,
+    /// Register pointer, `None` until written in the current transfer.
+    pointer: Option<usize>,
+    /// Pointer used by reads.
+    current: usize,
+}
+
+/// Generic I2C target with an 8-bit register pointer.
+///
+/// The first byte written after the address sets the register pointer,
+/// following bytes are written to consecutive registers.
+/// Reads return consecutive registers starting at the pointer.
+///
+/// Writes past the end of the register map are not acknowledged.
+/// The target responds to a 7-bit address, or to a 10-bit address when
+/// created with [`I2cRegisters::new_ten_bit`].
+///
+/// This is a handle, clones refer to the same target.
+#[derive(Debug, Clone)]
+pub struct I2cRegisters {
+    inner: Arc<Mutex<Registers>>,
+}
+
+impl I2cRegisters {
+    /// Create a new target with `len` registers cleared to zero.
+    pub fn new(address: u8, len: usize) -> I2cRegisters {
+        I2cRegisters::with_address(address.into(), None, len)
+    }
+
+    /// Create a new target with a 10-bit address and `len` registers
+    /// cleared to zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use eh1::i2c::I2c;
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::Pin;
+    /// use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice};
+    ///
+    /// let target = I2cRegisters::new_ten_bit(0x2A5, 16);
+    ///
+    /// let mut device = SimDevice::new();
+    /// device.connect(Pin::Lower(1), Pin::Lower(2));
+    /// device.attach(I2cPeripheral::new(target.clone()));
+    ///
+    /// let hal = hal::FtHal::init_freq(device, 100_000)?;
+    /// let mut i2c = hal.i2c()?;
+    /// i2c.write(0x2A5_u16, &[0x03, 0x42])?;
+    /// assert_eq!(target.register(3), 0x42);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new_ten_bit(address: u16, len: usize) -> I2cRegisters {
+        I2cRegisters::with_address(address & 0x3FF, Some(TenBit::Idle), len)
+    }
+
+    fn with_address(address: u16, ten_bit: Option<TenBit>, len: usize) -> I2cRegisters {
+        I2cRegisters {
+            inner: Arc::new(Mutex::new(Registers {
+                address,
+                ten_bit,
+                map: vec![0; len],
+                present: true,
+                stretch: Duration::ZERO,
+                pointer: None,
+                current: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Registers> {
+        self.inner
+            .lock()
+            .expect("Failed to aquire register map mutex")
+    }
+
+    /// Get the value of a register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `register` is out of range.
+    pub fn register(&self, register: usize) -> u8 {
+        self.lock().map[register]
+    }
+
+    /// Set the value of a register.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `register` is out of range.
+    pub fn set_register(&self, register: usize, value: u8) {
+        self.lock().map[register] = value;
+    }
+
+    /// Connect or disconnect the target from the bus.
+    ///
+    /// A disconnected target does not acknowledge its address.
+    pub fn set_present(&self, present: bool) {
+        self.lock().present = present;
+    }
+
+    /// Hold SCL low for `stretch` before acknowledging each received byte
+    /// and before transmitting each byte.
+    ///
+    /// [`Duration::MAX`] holds SCL low forever.
+    pub fn set_stretch(&self, stretch: Duration) {
+        self.lock().stretch = stretch;
+    }
+}
+
+impl I2cTarget for I2cRegisters {
+    fn start(&mut self, _now: Duration, address: u8, read: bool) -> bool {
+        let mut regs = self.lock();
+        regs.pointer = None;
+        let Some(ten_bit) = regs.ten_bit else {
+            return regs.present && u16::from(address) == regs.address;
+        };
+
+        let header: u8 = 0x78 | (regs.address >> 8) as u8;
+        let next: TenBit = match (address == header, read) {
+            (false, _) => TenBit::Idle,
+            (true, false) => TenBit::Header,
+            (true, true) if ten_bit == TenBit::Selected => TenBit::Selected,
+            (true, true) => TenBit::Idle,
+        };
+        regs.ten_bit = Some(next);
+        regs.present && next != TenBit::Idle
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        let mut regs = self.lock();
+        if regs.ten_bit == Some(TenBit::Header) {
+            let selected: bool = u16::from(byte) == regs.address & 0xFF;
+            regs.ten_bit = Some(if selected {
+                TenBit::Selected
+            } else {
+                TenBit::Idle
+            });
+            return selected;
+        }
+        match regs.pointer {
+            None => {
+                let pointer: usize = usize::from(byte);
+                regs.pointer = Some(pointer);
+                regs.current = pointer;
+                pointer < regs.map.len()
+            }
+            Some(pointer) if pointer < regs.map.len() => {
+                regs.map[pointer] = byte;
+                regs.pointer = Some(pointer + 1);
+                regs.current = pointer + 1;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let mut regs = self.lock();
+        let current: usize = regs.current;
+        regs.current += 1;
+        regs.map.get(current).copied().unwrap_or(0xFF)
+    }
+
+    fn stretch(&mut self) -> Duration {
+        self.lock().stretch
+    }
+
+    fn stop(&mut self, _now: Duration) {
+        let mut regs = self.lock();
+        if regs.ten_bit.is_some() {
+            regs.ten_bit = Some(TenBit::Idle);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::I2cTarget;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Sensor state.
+#[derive(Debug)]
+struct Sensor {
+    address: u8,
+    /// Temperature, configuration, hysteresis and overtemperature registers.
+    registers: [u16; 4],
+    /// Register pointer.
+    pointer: usize,
+    /// Number of bytes transferred since the start condition.
+    count: usize,
+}
+
+/// National Semiconductor LM75 I2C temperature sensor.
+///
+/// The temperature register is read-only and is set with
+/// [`Lm75::set_temperature`].
+/// Reads return the register selected by the pointer, repeating it for
+/// longer reads.
+///
+/// This is a handle, clones refer to the same sensor.
+///
+/// # Example
+///
+/// ```
+/// use ftdi_embedded_hal::sim::{I2cPeripheral, Lm75, SimDevice};
+///
+/// let sensor = Lm75::new(0x48);
+/// sensor.set_temperature(-25.0);
+///
+/// let mut device = SimDevice::new();
+/// device.attach(I2cPeripheral::new(sensor.clone()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lm75 {
+    inner: Arc<Mutex<Sensor>>,
+}
+
+impl Lm75 {
+    /// Register pointer value of the temperature register.
+    const TEMPERATURE: usize = 0;
+    /// Register pointer value of the configuration register.
+    const CONFIGURATION: usize = 1;
+
+    /// Create a new sensor reading 0 °C.
+    ///
+    /// `address` is the 7-bit device address, `0x48` when the A2-A0 pins
+    /// are tied low.
+    pub fn new(address: u8) -> Lm75 {
+        Lm75 {
+            inner: Arc::new(Mutex::new(Sensor {
+                address,
+                // power-on defaults, T_HYST 75 °C and T_OS 80 °C
+                registers: [0x0000, 0x0000, 0x4B00, 0x5000],
+                pointer: Lm75::TEMPERATURE,
+                count: 0,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Sensor> {
+        self.inner.lock().expect("Failed to aquire LM75 mutex")
+    }
+
+    /// Set the measured temperature in °C.
+    ///
+    /// The value is rounded to the 0.5 °C resolution of the LM75.
+    pub fn set_temperature(&self, celsius: f32) {
+        let half_degrees: i16 = (celsius * 2.0).round() as i16;
+        self.lock().registers[Lm75::TEMPERATURE] = (half_degrees << 7) as u16;
+    }
+
+    /// Get the configuration register.
+    pub fn configuration(&self) -> u8 {
+        self.lock().registers[Lm75::CONFIGURATION] as u8
+    }
+}
+
+impl I2cTarget for Lm75 {
+    fn start(&mut self, _now: Duration, address: u8, _read: bool) -> bool {
+        let mut sensor = self.lock();
+        sensor.count = 0;
+        address == sensor.address
+    }
+
+    fn write(&mut self, byte: u8) -> bool {
+        let mut sensor = self.lock();
+        let count: usize = sensor.count;
+        sensor.count += 1;
+        let pointer: usize = sensor.pointer;
+        match (count, pointer) {
+            (0, _) => {
+                sensor.pointer = usize::from(byte & 0x03);
+                true
+            }
+            (1, Lm75::CONFIGURATION) => {
+                sensor.registers[pointer] = u16::from(byte);
+                true
+            }
+            (1, Lm75::TEMPERATURE) => false,
+            (1, _) => {
+                sensor.registers[pointer] = u16::from(byte) << 8;
+                true
+            }
+            (2, _) if pointer > Lm75::CONFIGURATION => {
+                // only the MSB of the LSB is implemented
+                sensor.registers[pointer] |= u16::from(byte & 0x80);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn read(&mut self) -> u8 {
+        let mut sensor = self.lock();
+        let count: usize = sensor.count;
+        sensor.count += 1;
+        let value: u16 = sensor.registers[sensor.pointer];
+        if sensor.pointer == Lm75::CONFIGURATION {
+            value as u8
+        } else if count.is_multiple_of(2) {
+            (value >> 8) as u8
+        } else {
+            value as u8
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use super::{Lines, Peripheral};
+use crate::gpio::Pin;
+use eh1::spi::{Mode, Phase, Polarity};
+use std::time::Duration;
+
+/// Behavioral model of an SPI device.
+///
+/// The methods are called by [`SpiPeripheral`] while its chip select is
+/// asserted.
+/// Bytes are exchanged MSB first.
+pub trait SpiTarget: Send {
+    /// Chip select asserted.
+    fn select(&mut self) {}
+
+    /// Chip select deasserted.
+    fn deselect(&mut self) {}
+
+    /// Byte to shift out on SDI (MISO) during the next 8 clocks.
+    fn shift_out(&mut self) -> u8;
+
+    /// Byte shifted in from SDO (MOSI).
+    fn shift_in(&mut self, byte: u8);
+}
+
+/// Adapter from the SPI lines to a [`SpiTarget`].
+///
+/// SCK is AD0, SDO (MOSI) is AD1 and SDI (MISO) is AD2, the pins used by
+/// [`FtHal::spi`].
+/// The chip select is active low.
+///
+/// # Example
+///
+/// ```
+/// use ftdi_embedded_hal::Pin;
+/// use ftdi_embedded_hal::sim::{SimDevice, SpiFlash, SpiPeripheral};
+///
+/// let flash = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+///
+/// let mut device = SimDevice::new();
+/// device.attach(SpiPeripheral::new(
+///     Pin::Lower(3),
+///     eh1::spi::MODE_0,
+///     flash.clone(),
+/// ));
+/// ```
+///
+/// [`FtHal::spi`]: crate::FtHal::spi
+#[derive(Debug)]
+pub struct SpiPeripheral<T> {
+    target: T,
+    cs: Pin,
+    mode: Mode,
+    /// Chip select is asserted.
+    selected: bool,
+    /// SCK level seen by the previous update.
+    prev_sck: bool,
+    /// Number of bits shifted in the current byte.
+    bit: u8,
+    /// Byte being shifted in.
+    rx: u8,
+    /// Byte being shifted out.
+    tx: u8,
+    /// Level driven on SDI.
+    sdi: bool,
+}
+
+/// SCK line.
+const SCK: Pin = Pin::Lower(0);
+/// SDO (MOSI) line.
+const SDO: Pin = Pin::Lower(1);
+/// SDI (MISO) line.
+const SDI: Pin = Pin::Lower(2);
+
+impl<T: SpiTarget> SpiPeripheral<T> {
+    /// Create a new SPI adapter with the chip select on `cs`.
+    pub fn new(cs: Pin, mode: Mode, target: T) -> SpiPeripheral<T> {
+        SpiPeripheral {
+            target,
+            cs,
+            mode,
+            selected: false,
+            prev_sck: mode.polarity == Polarity::IdleHigh,
+            bit: 0,
+            rx: 0,
+            tx: 0,
+            sdi: true,
+        }
+    }
+
+    /// Drive the next bit of the outgoing byte.
+    fn drive(&mut self) {
+        if self.bit == 0 {
+            self.tx = self.target.shift_out();
+        }
+        self.sdi = self.tx & (0x80 >> self.bit) != 0;
+    }
+
+    /// Sample the incoming bit.
+    fn sample(&mut self, sdo: bool) {
+        self.rx = (self.rx << 1) | u8::from(sdo);
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.target.shift_in(self.rx);
+        }
+    }
+}
+
+impl<T: SpiTarget> Peripheral for SpiPeripheral<T> {
+    fn update(&mut self, _now: Duration, levels: Lines) {
+        let selected: bool = !levels.contains(self.cs);
+        let sck: bool = levels.contains(SCK);
+        let idle: bool = self.mode.polarity == Polarity::IdleHigh;
+
+        if selected != self.selected {
+            self.selected = selected;
+            self.bit = 0;
+            if selected {
+                self.target.select();
+                if self.mode.phase == Phase::CaptureOnFirstTransition {
+                    self.drive();
+                }
+            } else {
+                self.target.deselect();
+            }
+        } else if selected && sck != self.prev_sck {
+            let leading: bool = sck != idle;
+            match (self.mode.phase, leading) {
+                (Phase::CaptureOnFirstTransition, true)
+                | (Phase::CaptureOnSecondTransition, false) => {
+                    self.sample(levels.contains(SDO));
+                }
+                _ => {}
+            }
+            match (self.mode.phase, leading) {
+                (Phase::CaptureOnFirstTransition, false)
+                | (Phase::CaptureOnSecondTransition, true) => self.drive(),
+                _ => {}
+            }
+        }
+
+        self.prev_sck = sck;
+    }
+
+    fn drive_low(&self) -> Lines {
+        if self.selected && !self.sdi {
+            Lines::from(SDI)
+        } else {
+            Lines::NONE
+        }
+    }
+
+    fn drive_high(&self) -> Lines {
+        if self.selected && self.sdi {
+            Lines::from(SDI)
+        } else {
+            Lines::NONE
+        }
+    }
+}
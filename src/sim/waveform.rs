@@ -0,0 +1,99 @@
+use super::{Lines, Peripheral};
+use crate::gpio::Pin;
+use std::time::Duration;
+
+/// Scripted signal driven on a single pin.
+///
+/// Edge times are relative to the moment the waveform is attached.
+///
+/// # Example
+///
+/// ```
+/// use eh1::digital::InputPin;
+/// use ftdi_embedded_hal as hal;
+/// use hal::Pin;
+/// use hal::sim::{SimDevice, Waveform};
+/// use std::time::Duration;
+///
+/// let mut device = SimDevice::new();
+/// device.attach(
+///     Waveform::new(Pin::Lower(4), false)
+///         .at(Duration::from_millis(10), true)
+///         .at(Duration::from_millis(20), false),
+/// );
+///
+/// let mut hal = hal::FtHal::init_default(device)?;
+/// let mut input = hal.adi4()?;
+/// assert!(input.is_low()?);
+/// hal.with_device(|sim| sim.advance(Duration::from_millis(15)));
+/// assert!(input.is_high()?);
+/// hal.with_device(|sim| sim.advance(Duration::from_millis(10)));
+/// assert!(input.is_low()?);
+/// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    pin: Pin,
+    level: bool,
+    /// Edges sorted by time.
+    edges: Vec<(Duration, bool)>,
+    /// Index of the next edge.
+    next: usize,
+    /// Time the waveform was attached.
+    start: Option<Duration>,
+}
+
+impl Waveform {
+    /// Create a new waveform with an initial level.
+    pub fn new(pin: Pin, high: bool) -> Waveform {
+        Waveform {
+            pin,
+            level: high,
+            edges: Vec::new(),
+            next: 0,
+            start: None,
+        }
+    }
+
+    /// Change the level at `offset` after the waveform is attached.
+    #[must_use]
+    pub fn at(mut self, offset: Duration, high: bool) -> Waveform {
+        let idx: usize = self.edges.partition_point(|&(time, _)| time <= offset);
+        self.edges.insert(idx, (offset, high));
+        self
+    }
+}
+
+impl Peripheral for Waveform {
+    fn update(&mut self, now: Duration, _levels: Lines) {
+        let start: Duration = *self.start.get_or_insert(now);
+        while let Some(&(offset, high)) = self.edges.get(self.next) {
+            if start + offset > now {
+                break;
+            }
+            self.level = high;
+            self.next += 1;
+        }
+    }
+
+    fn drive_low(&self) -> Lines {
+        if self.level {
+            Lines::NONE
+        } else {
+            Lines::from(self.pin)
+        }
+    }
+
+    fn drive_high(&self) -> Lines {
+        if self.level {
+            Lines::from(self.pin)
+        } else {
+            Lines::NONE
+        }
+    }
+
+    fn next_event(&self) -> Option<Duration> {
+        let start: Duration = self.start?;
+        self.edges.get(self.next).map(|&(offset, _)| start + offset)
+    }
+}
@@ -1,59 +1,543 @@
 use crate::error::Error;
-use crate::{FtInner, PinUse};
-use ftdi_mpsse::{ClockData, ClockDataOut, MpsseCmdBuilder, MpsseCmdExecutor};
+use crate::trace::Origin;
+use crate::{Capability, ErrorKind, FtInner, Pin, PinUse};
+use ftdi_mpsse::{
+    ClockBits, ClockBitsOut, ClockData, ClockDataOut, MpsseCmdBuilder, MpsseCmdExecutor,
+};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// SPI bit order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// Most significant bit first.
+    #[default]
+    MsbFirst,
+    /// Least significant bit first.
+    LsbFirst,
+}
+
+/// Byte order of SPI words wider than a byte.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Most significant byte first.
+    #[default]
+    BigEndian,
+    /// Least significant byte first.
+    LittleEndian,
+}
+
+/// MPSSE master clock frequency in MHz, a GPIO command takes at least one
+/// master clock period to execute.
+const MASTER_CLOCK_MHZ: u128 = 60;
+
+/// MPSSE command clocking 1 to 8 cycles without data.
+const CLOCK_BITS_NO_DATA: u8 = 0x8E;
+
+/// MPSSE command clocking 1 to 65536 times 8 cycles without data.
+const CLOCK_BYTES_NO_DATA: u8 = 0x8F;
+
+/// Highest MPSSE clock frequency in Hz, assumed when the clock frequency is
+/// not set by the HAL.
+const MAX_CLOCK: u32 = 30_000_000;
+
+/// Longest chip select setup or hold time of a [`SpiDeviceConfig`].
+const MAX_CS_HOLD: Duration = Duration::from_millis(1);
+
+/// Longest chip select idle time of a [`SpiDeviceConfig`].
+const MAX_CS_IDLE: Duration = Duration::from_millis(100);
+
+/// SPI device chip select configuration.
+///
+/// The setup and hold times are realized in the MPSSE command stream by
+/// repeating the GPIO command that drives the chip select pin, which keeps
+/// SCK at its idle level, they are minimums and the actual times may be
+/// longer.
+/// The idle time is realized by clocking without data at the clock
+/// frequency of the device once the chip select is deasserted, rounded up
+/// to whole clock cycles.
+///
+/// The setup and hold times are at most 1 ms, and the idle time at most
+/// 100 ms, since the MPSSE executes no other commands in the meantime.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpiDeviceConfig {
+    /// Chip select is asserted high instead of low.
+    pub cs_active_high: bool,
+    /// Minimum time from asserting chip select to the first data clock edge.
+    pub cs_setup: Duration,
+    /// Minimum time from the last data clock edge to deasserting chip select.
+    pub cs_hold: Duration,
+    /// Minimum time chip select stays deasserted after a transaction.
+    pub cs_idle_min: Duration,
+}
+
+/// Number of GPIO commands that take at least `duration`.
+fn cmd_count(duration: Duration) -> u128 {
+    (duration.as_nanos() * MASTER_CLOCK_MHZ).div_ceil(1000)
+}
+
+/// Append the commands clocking without data for `duration` to `cmd`.
+///
+/// The number of cycles is rounded up at `frequency`, or the highest clock
+/// frequency if it is not known.
+/// Whole bytes of 8 cycles are clocked in commands of at most 64 KiB, and
+/// the remaining cycles with a bit command.
+fn idle_cmd(
+    mut cmd: MpsseCmdBuilder,
+    duration: Duration,
+    frequency: Option<u32>,
+) -> MpsseCmdBuilder {
+    let frequency: u128 = frequency.unwrap_or(MAX_CLOCK).into();
+    let cycles: u128 = (duration.as_nanos() * frequency).div_ceil(1_000_000_000);
+
+    // ftdi-mpsse has no builder methods for these commands
+    let mut bytes: u128 = cycles / 8;
+    while bytes != 0 {
+        let len: u16 = (bytes.min(MAX_CLOCK_BYTES as u128) - 1) as u16;
+        cmd.0.push(CLOCK_BYTES_NO_DATA);
+        cmd.0.extend_from_slice(&len.to_le_bytes());
+        bytes -= u128::from(len) + 1;
+    }
+    let bits: u8 = (cycles % 8) as u8;
+    if bits != 0 {
+        cmd.0.extend_from_slice(&[CLOCK_BITS_NO_DATA, bits - 1]);
+    }
+    cmd
+}
+
+/// SPI word wider than a byte.
+trait Word: Copy {
+    /// Size of the word in bytes.
+    const BYTES: usize;
+
+    /// Append the bytes of the word to `bytes`.
+    fn extend(self, byte_order: ByteOrder, bytes: &mut Vec<u8>);
+
+    /// Create a word from its bytes.
+    fn from_bytes(byte_order: ByteOrder, bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_word {
+    ($($word:ty),*) => {$(
+        impl Word for $word {
+            const BYTES: usize = size_of::<$word>();
+
+            fn extend(self, byte_order: ByteOrder, bytes: &mut Vec<u8>) {
+                match byte_order {
+                    ByteOrder::BigEndian => bytes.extend_from_slice(&self.to_be_bytes()),
+                    ByteOrder::LittleEndian => bytes.extend_from_slice(&self.to_le_bytes()),
+                }
+            }
+
+            fn from_bytes(byte_order: ByteOrder, bytes: &[u8]) -> Self {
+                let bytes: [u8; size_of::<$word>()] =
+                    bytes.try_into().expect("word size mismatch");
+                match byte_order {
+                    ByteOrder::BigEndian => <$word>::from_be_bytes(bytes),
+                    ByteOrder::LittleEndian => <$word>::from_le_bytes(bytes),
+                }
+            }
+        }
+    )*};
+}
 
-/// FTDI SPI polarity.
+impl_word!(u8, u16, u32);
+
+/// FTDI SPI mode.
 ///
 /// This is a helper type to support multiple embedded-hal versions simultaneously.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Polarity {
-    /// MPSSE command used to clock data in and out simultaneously.
+pub struct Mode {
+    /// SCK level while the bus is idle.
+    idle_high: bool,
+    /// Data is sampled on the rising edge, and changes on the falling edge.
+    sample_rising: bool,
+    /// Bit order of the data.
     ///
-    /// This is set by [`Spi::set_clock_polarity`].
-    clk: ClockData,
-    /// MPSSE command used to clock data out.
+    /// This is set by [`Spi::set_bit_order`].
+    bit_order: BitOrder,
+    /// Byte order of words wider than a byte.
+    ///
+    /// This is set by [`Spi::set_byte_order`].
+    byte_order: ByteOrder,
+    /// Byte written while reading.
     ///
-    /// This is set by [`Spi::set_clock_polarity`].
-    clk_out: ClockDataOut,
+    /// This is set by [`Spi::set_fill_byte`].
+    fill: u8,
 }
 
-impl From<eh0::spi::Polarity> for Polarity {
-    fn from(cpol: eh0::spi::Polarity) -> Self {
-        match cpol {
-            eh0::spi::Polarity::IdleLow => Polarity {
-                clk: ClockData::MsbPosIn,
-                clk_out: ClockDataOut::MsbNeg,
-            },
-            eh0::spi::Polarity::IdleHigh => Polarity {
-                clk: ClockData::MsbNegIn,
-                clk_out: ClockDataOut::MsbPos,
-            },
+impl Mode {
+    /// Select the clock edges for a clock polarity and phase.
+    fn new(idle_high: bool, capture_on_second_transition: bool) -> Self {
+        Mode {
+            idle_high,
+            // data is sampled on the rising edge in mode 0 and 3
+            sample_rising: idle_high == capture_on_second_transition,
+            bit_order: BitOrder::MsbFirst,
+            byte_order: ByteOrder::BigEndian,
+            fill: 0x00,
+        }
+    }
+
+    /// Set the clock polarity and phase, keeping the other settings.
+    fn set(&mut self, mode: Mode) {
+        self.idle_high = mode.idle_high;
+        self.sample_rising = mode.sample_rising;
+    }
+
+    /// MPSSE command used to clock data in and out simultaneously.
+    fn clk(&self) -> ClockData {
+        match (self.bit_order, self.sample_rising) {
+            (BitOrder::MsbFirst, true) => ClockData::MsbPosIn,
+            (BitOrder::MsbFirst, false) => ClockData::MsbNegIn,
+            (BitOrder::LsbFirst, true) => ClockData::LsbPosIn,
+            (BitOrder::LsbFirst, false) => ClockData::LsbNegIn,
+        }
+    }
+
+    /// MPSSE command used to clock data out.
+    fn clk_out(&self) -> ClockDataOut {
+        match (self.bit_order, self.sample_rising) {
+            (BitOrder::MsbFirst, true) => ClockDataOut::MsbNeg,
+            (BitOrder::MsbFirst, false) => ClockDataOut::MsbPos,
+            (BitOrder::LsbFirst, true) => ClockDataOut::LsbNeg,
+            (BitOrder::LsbFirst, false) => ClockDataOut::LsbPos,
+        }
+    }
+
+    /// MPSSE command used to clock bits in and out simultaneously.
+    fn clk_bits(&self) -> ClockBits {
+        match (self.bit_order, self.sample_rising) {
+            (BitOrder::MsbFirst, true) => ClockBits::MsbPosIn,
+            (BitOrder::MsbFirst, false) => ClockBits::MsbNegIn,
+            (BitOrder::LsbFirst, true) => ClockBits::LsbPosIn,
+            (BitOrder::LsbFirst, false) => ClockBits::LsbNegIn,
+        }
+    }
+
+    /// MPSSE command used to clock bits out.
+    fn clk_bits_out(&self) -> ClockBitsOut {
+        match (self.bit_order, self.sample_rising) {
+            (BitOrder::MsbFirst, true) => ClockBitsOut::MsbNeg,
+            (BitOrder::MsbFirst, false) => ClockBitsOut::MsbPos,
+            (BitOrder::LsbFirst, true) => ClockBitsOut::LsbNeg,
+            (BitOrder::LsbFirst, false) => ClockBitsOut::LsbPos,
+        }
+    }
+
+    /// Encode words as bytes.
+    fn encode<W: Word>(&self, words: &[W]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(words.len() * W::BYTES);
+        for word in words {
+            word.extend(self.byte_order, &mut bytes);
+        }
+        bytes
+    }
+
+    /// Decode words from bytes.
+    fn decode<W: Word>(&self, bytes: &[u8], words: &mut [W]) {
+        for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(W::BYTES)) {
+            *word = W::from_bytes(self.byte_order, bytes);
+        }
+    }
+
+    /// Align the bits read into a trailing partial byte like the bits
+    /// written from it.
+    fn align_bits(&self, read: &mut [u8], bits: usize) {
+        let partial: usize = bits % 8;
+        if partial != 0 {
+            let last: &mut u8 = &mut read[bits / 8];
+            // bits are shifted in from the least significant bit when
+            // clocking MSB first, and from the most significant bit when
+            // clocking LSB first
+            *last = match self.bit_order {
+                BitOrder::MsbFirst => *last << (8 - partial),
+                BitOrder::LsbFirst => *last >> (8 - partial),
+            };
         }
     }
-}
 
-impl From<eh1::spi::Polarity> for Polarity {
-    fn from(cpol: eh1::spi::Polarity) -> Self {
-        match cpol {
-            eh1::spi::Polarity::IdleLow => Polarity {
-                clk: ClockData::MsbPosIn,
-                clk_out: ClockDataOut::MsbNeg,
-            },
-            eh1::spi::Polarity::IdleHigh => Polarity {
-                clk: ClockData::MsbNegIn,
-                clk_out: ClockDataOut::MsbPos,
-            },
+    /// Scatter the bytes read by [`Pipeline::operations`] into the read
+    /// buffers of the operations.
+    fn scatter<W: Word>(&self, operations: &mut [eh1::spi::Operation<'_, W>], mut data: &[u8]) {
+        for op in operations {
+            match op {
+                eh1::spi::Operation::Read(buffer)
+                | eh1::spi::Operation::TransferInPlace(buffer) => {
+                    let (bytes, rest) = data.split_at(buffer.len() * W::BYTES);
+                    self.decode(bytes, buffer);
+                    data = rest;
+                }
+                eh1::spi::Operation::Transfer(read, write) => {
+                    let (bytes, rest) = data.split_at(read.len().max(write.len()) * W::BYTES);
+                    self.decode(bytes, read);
+                    data = rest;
+                }
+                eh1::spi::Operation::Write(_) | eh1::spi::Operation::DelayNs(_) => {}
+            }
         }
     }
 }
 
-impl Default for Polarity {
+impl From<eh0::spi::Mode> for Mode {
+    fn from(mode: eh0::spi::Mode) -> Self {
+        Mode::new(
+            mode.polarity == eh0::spi::Polarity::IdleHigh,
+            mode.phase == eh0::spi::Phase::CaptureOnSecondTransition,
+        )
+    }
+}
+
+impl From<eh1::spi::Mode> for Mode {
+    fn from(mode: eh1::spi::Mode) -> Self {
+        Mode::new(
+            mode.polarity == eh1::spi::Polarity::IdleHigh,
+            mode.phase == eh1::spi::Phase::CaptureOnSecondTransition,
+        )
+    }
+}
+
+impl Default for Mode {
     fn default() -> Self {
-        Self {
-            clk: ClockData::MsbPosIn,
-            clk_out: ClockDataOut::MsbNeg,
+        Mode::new(false, false)
+    }
+}
+
+/// Largest number of bytes clocked by one MPSSE data shifting command.
+const MAX_CLOCK_BYTES: usize = 65536;
+
+/// MPSSE command stream of SPI transfers.
+///
+/// Data shifting commands are split to fit the 64 KiB length limit of the
+/// MPSSE, and the stream is sent in pieces reading at most the USB in
+/// transfer size.
+/// Each piece is sent before the data of the previous piece is received,
+/// keeping the MPSSE busy while the host reads.
+struct Pipeline<'a, Device: MpsseCmdExecutor> {
+    ft: &'a mut Device,
+    mode: Mode,
+    /// Commands not sent yet.
+    cmd: MpsseCmdBuilder,
+    /// Bytes read by `cmd`.
+    queued: usize,
+    /// Bytes read by the commands sent, not received yet.
+    pending: usize,
+    /// Most bytes read by a piece of the stream.
+    chunk: usize,
+    /// Data received.
+    data: Vec<u8>,
+}
+
+impl<'a, Device, E> Pipeline<'a, Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    /// Start a command stream with `cmd`.
+    fn new(lock: &'a mut FtInner<Device>, mode: Mode, cmd: MpsseCmdBuilder) -> Self {
+        Pipeline {
+            chunk: lock.in_transfer_size.clamp(1, MAX_CLOCK_BYTES),
+            ft: &mut lock.ft,
+            mode,
+            cmd,
+            queued: 0,
+            pending: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Clock out `write`, reading the bytes clocked in if `read` is set.
+    fn clock(&mut self, mut write: &[u8], read: bool) -> Result<(), Error<E>> {
+        while !write.is_empty() {
+            let len: usize = if read {
+                write.len().min(self.chunk - self.queued)
+            } else {
+                write.len().min(MAX_CLOCK_BYTES)
+            };
+            let (piece, rest) = write.split_at(len);
+            let cmd: MpsseCmdBuilder = std::mem::take(&mut self.cmd);
+            self.cmd = if read {
+                self.queued += len;
+                cmd.clock_data(self.mode.clk(), piece)
+            } else {
+                cmd.clock_data_out(self.mode.clk_out(), piece)
+            };
+            if self.queued == self.chunk || self.cmd.as_slice().len() >= MAX_CLOCK_BYTES {
+                self.send()?;
+            }
+            write = rest;
+        }
+        Ok(())
+    }
+
+    /// Clock out the first `bits` bits of `write`, reading the bits clocked
+    /// in if `read` is set.
+    ///
+    /// Whole bytes are clocked with byte commands, and the trailing bits of
+    /// a partial byte with a bit command, from the bits of the last byte
+    /// that are sent first in the bit order.
+    fn clock_bits(&mut self, write: &[u8], bits: usize, read: bool) -> Result<(), Error<E>> {
+        assert!(
+            bits <= write.len() * 8,
+            "{bits} bits do not fit in {} bytes",
+            write.len()
+        );
+        let (bytes, partial): (&[u8], u8) = (&write[..bits / 8], (bits % 8) as u8);
+        self.clock(bytes, read)?;
+        if partial != 0 {
+            let last: u8 = write[bytes.len()];
+            let cmd: MpsseCmdBuilder = std::mem::take(&mut self.cmd);
+            self.cmd = if read {
+                self.queued += 1;
+                cmd.clock_bits(self.mode.clk_bits(), last, partial)
+            } else {
+                cmd.clock_bits_out(self.mode.clk_bits_out(), last, partial)
+            };
+        }
+        Ok(())
+    }
+
+    /// Append the commands of embedded-hal SPI operations.
+    ///
+    /// Delays repeat `hold`, a command that leaves the pins unchanged.
+    fn operations<W: Word>(
+        &mut self,
+        operations: &[eh1::spi::Operation<'_, W>],
+        hold: &MpsseCmdBuilder,
+    ) -> Result<(), Error<E>> {
+        for op in operations {
+            match op {
+                eh1::spi::Operation::Read(buffer) => {
+                    self.clock(&vec![self.mode.fill; buffer.len() * W::BYTES], true)?;
+                }
+                eh1::spi::Operation::Write(buffer) => {
+                    self.clock(&self.mode.encode(buffer), false)?;
+                }
+                eh1::spi::Operation::Transfer(read, write) => {
+                    // clock the longer of the buffers, writing the fill byte
+                    // past the end of `write`
+                    let mut data_out: Vec<u8> = self.mode.encode(write);
+                    data_out.resize(read.len().max(write.len()) * W::BYTES, self.mode.fill);
+                    self.clock(&data_out, true)?;
+                }
+                eh1::spi::Operation::TransferInPlace(buffer) => {
+                    self.clock(&self.mode.encode(buffer), true)?;
+                }
+                eh1::spi::Operation::DelayNs(ns) => {
+                    self.repeat(hold, cmd_count(Duration::from_nanos((*ns).into())))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Clock out `write` in a stream of its own, returning the data read if
+    /// `read` is set.
+    fn exchange(
+        lock: &'a mut FtInner<Device>,
+        mode: Mode,
+        write: &[u8],
+        read: bool,
+    ) -> Result<Vec<u8>, Error<E>> {
+        let mut pipeline: Pipeline<Device> = Pipeline::new(lock, mode, MpsseCmdBuilder::new());
+        pipeline.clock(write, read)?;
+        pipeline.finish(MpsseCmdBuilder::new())
+    }
+
+    /// Clock out the fill byte in a stream of its own, reading into `read`.
+    fn read(lock: &'a mut FtInner<Device>, mode: Mode, read: &mut [u8]) -> Result<(), Error<E>> {
+        let data: Vec<u8> = Pipeline::exchange(lock, mode, &vec![mode.fill; read.len()], true)?;
+        read.copy_from_slice(&data);
+        Ok(())
+    }
+
+    /// Clock out `write` in a stream of its own, reading into `read`.
+    ///
+    /// The longer of the buffers is clocked, `write` is padded with the fill
+    /// byte and the bytes read past the end of `read` are discarded.
+    fn transfer(
+        lock: &'a mut FtInner<Device>,
+        mode: Mode,
+        read: &mut [u8],
+        write: &[u8],
+    ) -> Result<(), Error<E>> {
+        let data: Vec<u8> = if read.len() > write.len() {
+            let mut data_out: Vec<u8> = write.to_vec();
+            data_out.resize(read.len(), mode.fill);
+            Pipeline::exchange(lock, mode, &data_out, true)?
+        } else {
+            Pipeline::exchange(lock, mode, write, true)?
+        };
+        read.copy_from_slice(&data[..read.len()]);
+        Ok(())
+    }
+
+    /// Clock out the first `bits` bits of `write` in a stream of its own,
+    /// reading them into `read` if it is given.
+    fn exchange_bits(
+        lock: &'a mut FtInner<Device>,
+        mode: Mode,
+        write: &[u8],
+        bits: usize,
+        read: Option<&mut [u8]>,
+    ) -> Result<(), Error<E>> {
+        let mut pipeline: Pipeline<Device> = Pipeline::new(lock, mode, MpsseCmdBuilder::new());
+        pipeline.clock_bits(write, bits, read.is_some())?;
+        let data: Vec<u8> = pipeline.finish(MpsseCmdBuilder::new())?;
+        if let Some(read) = read {
+            read.copy_from_slice(&data);
+            mode.align_bits(read, bits);
+        }
+        Ok(())
+    }
+
+    /// Append `count` copies of `cmd`.
+    fn repeat(&mut self, cmd: &MpsseCmdBuilder, count: u128) -> Result<(), Error<E>> {
+        for _ in 0..count {
+            self.cmd.0.extend_from_slice(cmd.as_slice());
+            if self.cmd.as_slice().len() >= MAX_CLOCK_BYTES {
+                self.send()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send the queued commands, then receive the data of the previous piece.
+    fn send(&mut self) -> Result<(), Error<E>> {
+        let mut cmd: MpsseCmdBuilder = std::mem::take(&mut self.cmd);
+        if self.queued != 0 {
+            cmd = cmd.send_immediate();
         }
+        self.ft.send(cmd.as_slice())?;
+        self.receive()?;
+        self.pending = self.queued;
+        self.queued = 0;
+        Ok(())
+    }
+
+    /// Receive the data of the commands sent.
+    fn receive(&mut self) -> Result<(), Error<E>> {
+        if self.pending != 0 {
+            let start: usize = self.data.len();
+            self.data.resize(start + self.pending, 0);
+            self.ft.recv(&mut self.data[start..])?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    /// Send the rest of the stream followed by `end`, and return the data
+    /// read since the last call.
+    fn finish(&mut self, end: MpsseCmdBuilder) -> Result<Vec<u8>, Error<E>> {
+        self.cmd.0.extend_from_slice(end.as_slice());
+        if self.queued == 0 {
+            self.cmd = std::mem::take(&mut self.cmd).send_immediate();
+        }
+        self.send()?;
+        self.receive()?;
+        Ok(std::mem::take(&mut self.data))
     }
 }
 
@@ -68,8 +552,10 @@ impl Default for Polarity {
 pub struct Spi<Device: MpsseCmdExecutor> {
     /// Parent FTDI device.
     mtx: Arc<Mutex<FtInner<Device>>>,
-    /// SPI polarity
-    pol: Polarity,
+    /// SPI mode
+    mode: Mode,
+    /// Bytes received by full duplex writes, not read yet.
+    rx: VecDeque<u8>,
 }
 
 impl<Device, E> Spi<Device>
@@ -81,9 +567,13 @@ where
     pub(crate) fn new(mtx: Arc<Mutex<FtInner<Device>>>) -> Result<Spi<Device>, Error<E>> {
         {
             let mut lock = mtx.lock().expect("Failed to aquire FTDI mutex");
-            lock.allocate_pin(0, PinUse::Spi);
-            lock.allocate_pin(1, PinUse::Spi);
-            lock.allocate_pin(2, PinUse::Spi);
+            let _origin = Origin::Spi.enter();
+            lock.allocate_pins(&[
+                (Pin::Lower(0), PinUse::Spi),
+                (Pin::Lower(1), PinUse::Spi),
+                (Pin::Lower(2), PinUse::Spi),
+            ])
+            .map_err(Error::Hal)?;
 
             // clear direction of first 3 pins
             lock.direction &= !0x07;
@@ -94,23 +584,50 @@ where
             let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
                 .set_gpio_lower(lock.value, lock.direction)
                 .send_immediate();
-            lock.ft.send(cmd.as_slice())?;
+            lock.send_or_release(
+                cmd.as_slice(),
+                &[Pin::Lower(0), Pin::Lower(1), Pin::Lower(2)],
+            )?;
         }
 
         Ok(Spi {
             mtx,
-            pol: Default::default(),
+            mode: Default::default(),
+            rx: VecDeque::new(),
         })
     }
 
-    /// Set the SPI clock polarity.
+    /// Set the SPI clock frequency in Hz.
+    ///
+    /// Returns the actual frequency, see [`FtHal::set_clock_frequency`].
+    ///
+    /// The MPSSE clock is shared, this changes the clock frequency of all
+    /// peripherals.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi()?;
+    /// assert_eq!(spi.set_frequency(10_000_000)?, 10_000_000);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
     ///
-    /// FTD2XX devices only supports [SPI mode] 0 and 2, clock phase is fixed.
+    /// [`FtHal::set_clock_frequency`]: crate::FtHal::set_clock_frequency
+    pub fn set_frequency(&mut self, frequency: u32) -> Result<u32, Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::Spi.enter();
+        lock.set_clock(frequency)
+    }
+
+    /// Set the [SPI mode].
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use eh1::spi::Polarity;
     /// use ftdi_embedded_hal as hal;
     ///
     /// # #[cfg(feature = "libftd2xx")]
@@ -118,22 +635,20 @@ where
     /// let device = libftd2xx::Ft2232h::with_description("Dual RS232-HS A")?;
     /// let hal = hal::FtHal::init_freq(device, 3_000_000)?;
     /// let mut spi = hal.spi()?;
-    /// spi.set_clock_polarity(Polarity::IdleLow)?;
+    /// spi.set_mode(eh1::spi::MODE_3)?;
     /// # }
     /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
     /// ```
     ///
     /// [SPI mode]: https://en.wikipedia.org/wiki/Serial_Peripheral_Interface#Mode_numbers
-    pub fn set_clock_polarity<P: Into<Polarity>>(&mut self, cpol: P) -> Result<(), Error<E>> {
-        self.pol = cpol.into();
-        let mut lock = self.mtx.lock().unwrap();
-        match self.pol.clk {
-            ClockData::MsbNegIn | ClockData::LsbNegIn => {
-                lock.lower.value |= 1;
-            }
-            ClockData::MsbPosIn | ClockData::LsbPosIn => {
-                lock.lower.value &= !1;
-            }
+    pub fn set_mode<M: Into<Mode>>(&mut self, mode: M) -> Result<(), Error<E>> {
+        self.mode.set(mode.into());
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::Spi.enter();
+        if self.mode.idle_high {
+            lock.lower.value |= 1;
+        } else {
+            lock.lower.value &= !1;
         }
         let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
             .set_gpio_lower(lock.value, lock.direction)
@@ -141,6 +656,135 @@ where
         lock.ft.send(cmd.as_slice())?;
         Ok(())
     }
+
+    /// Set the bit order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::BitOrder;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi()?;
+    /// spi.set_bit_order(BitOrder::LsbFirst);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_bit_order(&mut self, bit_order: BitOrder) {
+        self.mode.bit_order = bit_order;
+    }
+
+    /// Set the byte order of words wider than a byte.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::ByteOrder;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi()?;
+    /// spi.set_byte_order(ByteOrder::LittleEndian);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        self.mode.byte_order = byte_order;
+    }
+
+    /// Set the byte written while reading.
+    ///
+    /// This is written by reads, and past the end of the write buffer when
+    /// a transfer reads more than it writes.
+    /// The default is `0x00`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi()?;
+    /// spi.set_fill_byte(0xFF);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_fill_byte(&mut self, fill: u8) {
+        self.mode.fill = fill;
+    }
+
+    /// Write the first `bits` bits of `words`.
+    ///
+    /// Bits are packed into bytes in the bit order, see [`Spi::set_bit_order`].
+    /// The bits of a trailing partial byte are the ones sent first, the most
+    /// significant bits when clocking MSB first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi()?;
+    /// // 9-bit frame 0x1A5
+    /// spi.write_bits(&[0xD2, 0x80], 9)?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` holds fewer than `bits` bits.
+    pub fn write_bits(&mut self, words: &[u8], bits: usize) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::Spi.enter();
+        Pipeline::exchange_bits(&mut lock, self.mode, words, bits, None)
+    }
+
+    /// Read `bits` bits into `words`.
+    ///
+    /// Bits are packed like in [`write_bits`](Self::write_bits), the unused
+    /// bits of a trailing partial byte are zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` holds fewer than `bits` bits.
+    pub fn read_bits(&mut self, words: &mut [u8], bits: usize) -> Result<(), Error<E>> {
+        let write: Vec<u8> = vec![self.mode.fill; bits.div_ceil(8)];
+        self.transfer_bits(words, &write, bits)
+    }
+
+    /// Write the first `bits` bits of `write` while reading `bits` bits
+    /// into `read`.
+    ///
+    /// Bits are packed like in [`write_bits`](Self::write_bits), the unused
+    /// bits of a trailing partial byte are zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read` or `write` hold fewer than `bits` bits.
+    pub fn transfer_bits(
+        &mut self,
+        read: &mut [u8],
+        write: &[u8],
+        bits: usize,
+    ) -> Result<(), Error<E>> {
+        let read: &mut [u8] = &mut read[..bits.div_ceil(8)];
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::Spi.enter();
+        Pipeline::exchange_bits(&mut lock, self.mode, write, bits, Some(read))
+    }
+}
+
+impl<Device: MpsseCmdExecutor> Drop for Spi<Device> {
+    fn drop(&mut self) {
+        // a poisoned mutex has nothing left worth releasing
+        if let Ok(mut lock) = self.mtx.lock() {
+            let _origin = Origin::Spi.enter();
+            let _ = lock.release_pins(&[Pin::Lower(0), Pin::Lower(1), Pin::Lower(2)]);
+        }
+    }
 }
 
 impl<Device, E> eh0::blocking::spi::Write<u8> for Spi<Device>
@@ -152,12 +796,9 @@ where
     type Error = Error<E>;
 
     fn write(&mut self, words: &[u8]) -> Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data_out(self.pol.clk_out, words)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        lock.ft.send(cmd.as_slice())?;
+        let _origin = Origin::Spi.enter();
+        Pipeline::exchange(&mut lock, self.mode, words, false)?;
 
         Ok(())
     }
@@ -172,18 +813,40 @@ where
     type Error = Error<E>;
 
     fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, words)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        lock.ft.send(cmd.as_slice())?;
-        lock.ft.recv(words)?;
+        let _origin = Origin::Spi.enter();
+        let data: Vec<u8> = Pipeline::exchange(&mut lock, self.mode, words, true)?;
+        words.copy_from_slice(&data);
 
         Ok(words)
     }
 }
 
+impl<Device, E> Spi<Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    /// Clock out a full duplex byte, keeping the byte clocked in for
+    /// [`Spi::read_full_duplex`].
+    fn write_full_duplex(&mut self, byte: u8) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::Spi.enter();
+        let data: Vec<u8> = Pipeline::exchange(&mut lock, self.mode, &[byte], true)?;
+        self.rx.extend(data);
+        Ok(())
+    }
+
+    /// Read the oldest byte clocked in by a full duplex write.
+    fn read_full_duplex(&mut self) -> nb::Result<u8, Error<E>> {
+        self.rx.pop_front().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+/// Bytes are clocked by `send`, `read` returns the byte clocked in by the
+/// oldest `send` not read yet, or [`nb::Error::WouldBlock`] if every byte
+/// has been read.
 impl<Device, E> eh0::spi::FullDuplex<u8> for Spi<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
@@ -193,28 +856,11 @@ where
     type Error = Error<E>;
 
     fn read(&mut self) -> nb::Result<u8, Error<E>> {
-        let mut buf: [u8; 1] = [0];
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, &buf)
-            .send_immediate();
-
-        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        match lock.ft.xfer(cmd.as_slice(), &mut buf) {
-            Ok(()) => Ok(buf[0]),
-            Err(e) => Err(nb::Error::Other(Error::from(e))),
-        }
+        self.read_full_duplex()
     }
 
     fn send(&mut self, byte: u8) -> nb::Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data_out(self.pol.clk_out, &[byte])
-            .send_immediate();
-
-        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        match lock.ft.send(cmd.as_slice()) {
-            Ok(()) => Ok(()),
-            Err(e) => Err(nb::Error::Other(Error::from(e))),
-        }
+        self.write_full_duplex(byte).map_err(nb::Error::Other)
     }
 }
 
@@ -244,25 +890,15 @@ where
     Error<E>: From<E>,
 {
     fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        let data_out: Vec<u8> = vec![0; words.len()];
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, &data_out)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        lock.ft.send(cmd.as_slice())?;
-        lock.ft.recv(words)?;
-
-        Ok(())
+        let _origin = Origin::Spi.enter();
+        Pipeline::read(&mut lock, self.mode, words)
     }
 
     fn write(&mut self, words: &[u8]) -> Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data_out(self.pol.clk_out, words)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        lock.ft.send(cmd.as_slice())?;
+        let _origin = Origin::Spi.enter();
+        Pipeline::exchange(&mut lock, self.mode, words, false)?;
 
         Ok(())
     }
@@ -272,37 +908,24 @@ where
     }
 
     fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, words)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-
-        lock.ft.send(cmd.as_slice())?;
-        lock.ft.recv(words)?;
+        let _origin = Origin::Spi.enter();
+        let data: Vec<u8> = Pipeline::exchange(&mut lock, self.mode, words, true)?;
+        words.copy_from_slice(&data);
 
         Ok(())
     }
 
     fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, write)
-            .send_immediate();
-
         let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        lock.ft.send(cmd.as_slice())?;
-        lock.ft.recv(read)?;
-
-        let remain: usize = write.len().saturating_sub(read.len());
-        if remain != 0 {
-            let mut remain_buf: Vec<u8> = vec![0; remain];
-            lock.ft.recv(&mut remain_buf)?;
-        }
-
-        Ok(())
+        let _origin = Origin::Spi.enter();
+        Pipeline::transfer(&mut lock, self.mode, read, write)
     }
 }
 
+/// Bytes are clocked by `write`, `read` returns the byte clocked in by the
+/// oldest `write` not read yet, or [`nb::Error::WouldBlock`] if every byte
+/// has been read.
 impl<Device, E> ehnb1::spi::FullDuplex<u8> for Spi<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
@@ -310,34 +933,73 @@ where
     Error<E>: From<E>,
 {
     fn read(&mut self) -> nb::Result<u8, Error<E>> {
-        let mut buf: [u8; 1] = [0];
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data(self.pol.clk, &buf)
-            .send_immediate();
-
-        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        match lock.ft.xfer(cmd.as_slice(), &mut buf) {
-            Ok(()) => Ok(buf[0]),
-            Err(e) => Err(nb::Error::Other(Error::from(e))),
-        }
+        self.read_full_duplex()
     }
 
     fn write(&mut self, byte: u8) -> nb::Result<(), Error<E>> {
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .clock_data_out(self.pol.clk_out, &[byte])
-            .send_immediate();
-
-        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        match lock.ft.send(cmd.as_slice()) {
-            Ok(()) => Ok(()),
-            Err(e) => Err(nb::Error::Other(Error::from(e))),
-        }
+        self.write_full_duplex(byte).map_err(nb::Error::Other)
     }
 }
 
+/// FTDI SPI bus locked by a [`SpiDevice`] with its chip select asserted.
+///
+/// This is passed to the closure of [`SpiDevice::transaction_with`].
+#[derive(Debug)]
 pub struct SpiDeviceBus<'a, Device: MpsseCmdExecutor> {
     lock: MutexGuard<'a, FtInner<Device>>,
-    pol: Polarity,
+    mode: Mode,
+}
+
+impl<Device, E> SpiDeviceBus<'_, Device>
+where
+    Device: MpsseCmdExecutor<Error = E>,
+    E: std::error::Error,
+    Error<E>: From<E>,
+{
+    /// Write the first `bits` bits of `words`.
+    ///
+    /// Bits are packed into bytes in the bit order, see [`SpiDevice::set_bit_order`].
+    /// The bits of a trailing partial byte are the ones sent first, the most
+    /// significant bits when clocking MSB first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` holds fewer than `bits` bits.
+    pub fn write_bits(&mut self, words: &[u8], bits: usize) -> Result<(), Error<E>> {
+        Pipeline::exchange_bits(&mut self.lock, self.mode, words, bits, None)
+    }
+
+    /// Read `bits` bits into `words`.
+    ///
+    /// Bits are packed like in [`write_bits`](Self::write_bits), the unused
+    /// bits of a trailing partial byte are zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `words` holds fewer than `bits` bits.
+    pub fn read_bits(&mut self, words: &mut [u8], bits: usize) -> Result<(), Error<E>> {
+        let write: Vec<u8> = vec![self.mode.fill; bits.div_ceil(8)];
+        self.transfer_bits(words, &write, bits)
+    }
+
+    /// Write the first `bits` bits of `write` while reading `bits` bits
+    /// into `read`.
+    ///
+    /// Bits are packed like in [`write_bits`](Self::write_bits), the unused
+    /// bits of a trailing partial byte are zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read` or `write` hold fewer than `bits` bits.
+    pub fn transfer_bits(
+        &mut self,
+        read: &mut [u8],
+        write: &[u8],
+        bits: usize,
+    ) -> Result<(), Error<E>> {
+        let read: &mut [u8] = &mut read[..bits.div_ceil(8)];
+        Pipeline::exchange_bits(&mut self.lock, self.mode, write, bits, Some(read))
+    }
 }
 
 impl<Device, E> eh1::spi::ErrorType for SpiDeviceBus<'_, Device>
@@ -356,23 +1018,11 @@ where
     Error<E>: From<E>,
 {
     fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.lock.ft.xfer(
-            MpsseCmdBuilder::new()
-                .clock_data(self.pol.clk, words)
-                .send_immediate()
-                .as_slice(),
-            words,
-        )?;
-        Ok(())
+        Pipeline::read(&mut self.lock, self.mode, words)
     }
 
     fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
-        self.lock.ft.send(
-            MpsseCmdBuilder::new()
-                .clock_data_out(self.pol.clk_out, words)
-                .send_immediate()
-                .as_slice(),
-        )?;
+        Pipeline::exchange(&mut self.lock, self.mode, words, false)?;
         Ok(())
     }
 
@@ -381,43 +1031,83 @@ where
     }
 
     fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        self.lock.ft.xfer(
-            MpsseCmdBuilder::new()
-                .clock_data(self.pol.clk, write)
-                .send_immediate()
-                .as_slice(),
-            read,
-        )?;
-        Ok(())
+        Pipeline::transfer(&mut self.lock, self.mode, read, write)
     }
 
     fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
-        self.lock.ft.xfer(
-            MpsseCmdBuilder::new()
-                .clock_data(self.pol.clk, words)
-                .send_immediate()
-                .as_slice(),
-            words,
-        )?;
+        let data: Vec<u8> = Pipeline::exchange(&mut self.lock, self.mode, words, true)?;
+        words.copy_from_slice(&data);
         Ok(())
     }
 }
 
+/// Implement `SpiBus` for words wider than a byte on top of `SpiBus<u8>`.
+macro_rules! impl_spi_bus_words {
+    ($bus:ty; $($word:ty),*) => {$(
+        impl<Device, E> eh1::spi::SpiBus<$word> for $bus
+        where
+            Device: MpsseCmdExecutor<Error = E>,
+            E: std::error::Error,
+            Error<E>: From<E>,
+        {
+            fn read(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                let mut bytes: Vec<u8> = vec![0; words.len() * <$word as Word>::BYTES];
+                eh1::spi::SpiBus::<u8>::read(self, &mut bytes)?;
+                self.mode.decode(&bytes, words);
+                Ok(())
+            }
+
+            fn write(&mut self, words: &[$word]) -> Result<(), Self::Error> {
+                let bytes: Vec<u8> = self.mode.encode(words);
+                eh1::spi::SpiBus::<u8>::write(self, &bytes)
+            }
+
+            fn flush(&mut self) -> Result<(), Self::Error> {
+                eh1::spi::SpiBus::<u8>::flush(self)
+            }
+
+            fn transfer(&mut self, read: &mut [$word], write: &[$word]) -> Result<(), Self::Error> {
+                let mut bytes: Vec<u8> = vec![0; read.len() * <$word as Word>::BYTES];
+                let write: Vec<u8> = self.mode.encode(write);
+                eh1::spi::SpiBus::<u8>::transfer(self, &mut bytes, &write)?;
+                self.mode.decode(&bytes, read);
+                Ok(())
+            }
+
+            fn transfer_in_place(&mut self, words: &mut [$word]) -> Result<(), Self::Error> {
+                let mut bytes: Vec<u8> = self.mode.encode(words);
+                eh1::spi::SpiBus::<u8>::transfer_in_place(self, &mut bytes)?;
+                self.mode.decode(&bytes, words);
+                Ok(())
+            }
+        }
+    )*};
+}
+
+impl_spi_bus_words!(Spi<Device>; u16, u32);
+impl_spi_bus_words!(SpiDeviceBus<'_, Device>; u16, u32);
+
 /// FTDI SPI device, a SPI bus with chip select pin.
 ///
-/// This is created by calling [`FtHal::spi_device`].
+/// This is created by calling [`FtHal::spi_device`] or
+/// [`FtHal::spi_device_pin`].
 ///
 /// This is specific to embedded-hal version 1.
 ///
 /// [`FtHal::spi_device`]: crate::FtHal::spi_device
+/// [`FtHal::spi_device_pin`]: crate::FtHal::spi_device_pin
 #[derive(Debug)]
 pub struct SpiDevice<Device: MpsseCmdExecutor> {
     /// Parent FTDI device.
     mtx: Arc<Mutex<FtInner<Device>>>,
-    /// SPI polarity
-    pol: Polarity,
-    /// Chip select pin index.  0-7 for the FT232H.
-    cs_idx: u8,
+    /// SPI mode
+    mode: Mode,
+    /// Clock frequency, `None` to use the clock frequency of the bus.
+    frequency: Option<u32>,
+    /// Chip select pin.
+    cs: Pin,
+    /// Chip select configuration.
+    config: SpiDeviceConfig,
 }
 
 impl<Device, E> SpiDevice<Device>
@@ -428,50 +1118,171 @@ where
 {
     pub(crate) fn new(
         mtx: Arc<Mutex<FtInner<Device>>>,
-        cs_idx: u8,
+        cs: Pin,
     ) -> Result<SpiDevice<Device>, Error<E>> {
         {
             let mut lock = mtx.lock().expect("Failed to aquire FTDI mutex");
-            lock.allocate_pin(0, PinUse::Spi);
-            lock.allocate_pin(1, PinUse::Spi);
-            lock.allocate_pin(2, PinUse::Spi);
-            lock.allocate_pin(cs_idx, PinUse::Output);
+            let _origin = Origin::SpiDevice.enter();
+            // the bus pins are shared by all SPI devices
+            if lock.spi_devices == 0 {
+                lock.allocate_pins(&[
+                    (Pin::Lower(0), PinUse::Spi),
+                    (Pin::Lower(1), PinUse::Spi),
+                    (Pin::Lower(2), PinUse::Spi),
+                    (cs, PinUse::Output),
+                ])
+            } else {
+                lock.allocate_pin_any(cs, PinUse::Output)
+            }
+            .map_err(Error::Hal)?;
 
-            let cs_mask: u8 = 1 << cs_idx;
+            // clear direction of first 3 pins
+            lock.direction &= !0x07;
+            // set SCK (AD0) and MOSI (AD1) as output pins
+            lock.direction |= 0x03;
 
-            // clear direction of first 3 pins and CS
-            lock.direction &= !(0x07 | cs_mask);
-            // set SCK (AD0) and MOSI (AD1), and CS as output pins
-            lock.direction |= 0x03 | cs_mask;
-            // deassert CS
-            lock.value |= cs_mask;
+            // set CS as output pin and deassert it
+            let (byte, idx) = match cs {
+                Pin::Lower(idx) => (&mut lock.lower, idx),
+                Pin::Upper(idx) => (&mut lock.upper, idx),
+            };
+            byte.direction |= 1 << idx;
+            byte.value |= 1 << idx;
 
             // set GPIO pins to new state
-            let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-                .set_gpio_lower(lock.value, lock.direction)
-                .send_immediate();
-            lock.ft.send(cmd.as_slice())?;
+            let mut cmd: MpsseCmdBuilder =
+                MpsseCmdBuilder::new().set_gpio_lower(lock.lower.value, lock.lower.direction);
+            if let Pin::Upper(_) = cs {
+                cmd = cmd.set_gpio_upper(lock.upper.value, lock.upper.direction);
+            }
+            if lock.spi_devices == 0 {
+                lock.send_or_release(
+                    cmd.send_immediate().as_slice(),
+                    &[Pin::Lower(0), Pin::Lower(1), Pin::Lower(2), cs],
+                )
+            } else {
+                lock.send_or_release(cmd.send_immediate().as_slice(), &[cs])
+            }?;
+            lock.spi_devices += 1;
         }
 
         Ok(Self {
             mtx,
-            pol: Default::default(),
-            cs_idx,
+            mode: Default::default(),
+            frequency: None,
+            cs,
+            config: Default::default(),
         })
     }
 
+    /// Convert the chip select pin index to a pin mask
     pub(crate) fn cs_mask(&self) -> u8 {
-        1 << self.cs_idx
+        let idx = match self.cs {
+            Pin::Lower(idx) => idx,
+            Pin::Upper(idx) => idx,
+        };
+        1 << idx
     }
 
-    /// Set the SPI clock polarity.
+    /// Append `count` commands driving the chip select pin to `cmd`.
     ///
-    /// FTD2XX devices only supports [SPI mode] 0 and 2, clock phase is fixed.
+    /// Repeating the command keeps the pin at the same level for at least
+    /// `count` GPIO command times, with SCK at its idle level.
+    fn cs_cmd(
+        &self,
+        lock: &FtInner<Device>,
+        mut cmd: MpsseCmdBuilder,
+        asserted: bool,
+        count: u128,
+    ) -> MpsseCmdBuilder {
+        let byte = match self.cs {
+            Pin::Lower(_) => &lock.lower,
+            Pin::Upper(_) => &lock.upper,
+        };
+        let value: u8 = if asserted == self.config.cs_active_high {
+            byte.value | self.cs_mask()
+        } else {
+            byte.value & !self.cs_mask()
+        };
+        for _ in 0..count {
+            cmd = match self.cs {
+                Pin::Lower(_) => cmd.set_gpio_lower(value, byte.direction),
+                Pin::Upper(_) => cmd.set_gpio_upper(value, byte.direction),
+            };
+        }
+        cmd
+    }
+
+    /// Set the chip select configuration.
+    ///
+    /// The chip select pin is driven to its new inactive level immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::ChipSelectTime`] if the setup or hold time is
+    /// longer than 1 ms or the idle time is longer than 100 ms, or
+    /// [`ErrorKind::Unsupported`] for an idle time other than zero if the
+    /// chip does not support clocking without data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::SpiDeviceConfig;
+    /// use hal::sim::SimDevice;
+    /// use std::time::Duration;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi_device(3)?;
+    /// spi.set_config(SpiDeviceConfig {
+    ///     cs_active_high: true,
+    ///     cs_setup: Duration::from_nanos(100),
+    ///     cs_hold: Duration::from_nanos(50),
+    ///     cs_idle_min: Duration::from_micros(1),
+    /// })?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_config(&mut self, config: SpiDeviceConfig) -> Result<(), Error<E>> {
+        let mut lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::SpiDevice.enter();
+        let times: [(Duration, Duration); 3] = [
+            (config.cs_setup, MAX_CS_HOLD),
+            (config.cs_hold, MAX_CS_HOLD),
+            (config.cs_idle_min, MAX_CS_IDLE),
+        ];
+        if let Some(&(time, _)) = times.iter().find(|&&(time, max)| time > max) {
+            return Err(Error::Hal(ErrorKind::ChipSelectTime(time)));
+        }
+        if !config.cs_idle_min.is_zero() {
+            lock.require(Capability::ClockWithoutData)
+                .map_err(Error::Hal)?;
+        }
+        self.config = config;
+
+        // the pin value kept in the lock is the inactive level
+        let mask: u8 = self.cs_mask();
+        let byte = match self.cs {
+            Pin::Lower(_) => &mut lock.lower,
+            Pin::Upper(_) => &mut lock.upper,
+        };
+        if config.cs_active_high {
+            byte.value &= !mask;
+        } else {
+            byte.value |= mask;
+        }
+        let cmd: MpsseCmdBuilder = self.cs_cmd(&lock, MpsseCmdBuilder::new(), false, 1);
+        lock.ft.send(cmd.send_immediate().as_slice())?;
+        Ok(())
+    }
+
+    /// Set the [SPI mode].
+    ///
+    /// The idle clock level is driven at the start of the next transaction,
+    /// before the chip select is asserted.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use eh1::spi::Polarity;
     /// use ftdi_embedded_hal as hal;
     ///
     /// # #[cfg(feature = "libftd2xx")]
@@ -479,29 +1290,132 @@ where
     /// let device = libftd2xx::Ft2232h::with_description("Dual RS232-HS A")?;
     /// let hal = hal::FtHal::init_freq(device, 3_000_000)?;
     /// let mut spi = hal.spi_device(3)?;
-    /// spi.set_clock_polarity(Polarity::IdleLow)?;
+    /// spi.set_mode(eh1::spi::MODE_3)?;
     /// # }
     /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
     /// ```
     ///
     /// [SPI mode]: https://en.wikipedia.org/wiki/Serial_Peripheral_Interface#Mode_numbers
-    pub fn set_clock_polarity<P: Into<Polarity>>(&mut self, cpol: P) -> Result<(), Error<E>> {
-        self.pol = cpol.into();
-        let mut lock = self.mtx.lock().unwrap();
-        match self.pol.clk {
-            ClockData::MsbNegIn | ClockData::LsbNegIn => {
-                lock.lower.value |= 1;
-            }
-            ClockData::MsbPosIn | ClockData::LsbPosIn => {
-                lock.lower.value &= !1;
-            }
-        }
-        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
-            .set_gpio_lower(lock.value, lock.direction)
-            .send_immediate();
-        lock.ft.send(cmd.as_slice())?;
+    pub fn set_mode<M: Into<Mode>>(&mut self, mode: M) -> Result<(), Error<E>> {
+        self.mode.set(mode.into());
         Ok(())
     }
+
+    /// Set the bit order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::BitOrder;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi_device(3)?;
+    /// spi.set_bit_order(BitOrder::LsbFirst);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_bit_order(&mut self, bit_order: BitOrder) {
+        self.mode.bit_order = bit_order;
+    }
+
+    /// Set the byte order of words wider than a byte.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::ByteOrder;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi_device(3)?;
+    /// spi.set_byte_order(ByteOrder::LittleEndian);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        self.mode.byte_order = byte_order;
+    }
+
+    /// Set the byte written while reading.
+    ///
+    /// This is written by reads, and past the end of the write buffer when
+    /// a transfer reads more than it writes.
+    /// The default is `0x00`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut spi = hal.spi_device(3)?;
+    /// spi.set_fill_byte(0xFF);
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_fill_byte(&mut self, fill: u8) {
+        self.mode.fill = fill;
+    }
+
+    /// Set the clock frequency of this device in Hz.
+    ///
+    /// The clock divisor is programmed at the start of each transaction,
+    /// if the bus was last used at a different frequency.
+    /// Without a frequency the device uses the clock frequency of the bus,
+    /// see [`FtHal::set_clock_frequency`].
+    ///
+    /// Returns the actual frequency, which is the closest frequency the
+    /// clock divisor can produce that does not exceed `frequency`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ErrorKind::ClockFrequency`] if the frequency is out of
+    /// range, or [`ErrorKind::Unsupported`] if the chip does not support the
+    /// frequency.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ftdi_embedded_hal as hal;
+    ///
+    /// # #[cfg(feature = "libftd2xx")]
+    /// # {
+    /// let device = libftd2xx::Ft2232h::with_description("Dual RS232-HS A")?;
+    /// let hal = hal::FtHal::init_default(device)?;
+    /// let mut flash = hal.spi_device(3)?;
+    /// flash.set_frequency(20_000_000)?;
+    /// let mut adc = hal.spi_device(4)?;
+    /// adc.set_frequency(1_000_000)?;
+    /// # }
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// [`FtHal::set_clock_frequency`]: crate::FtHal::set_clock_frequency
+    /// [`ErrorKind::ClockFrequency`]: crate::ErrorKind::ClockFrequency
+    /// [`ErrorKind::Unsupported`]: crate::ErrorKind::Unsupported
+    pub fn set_frequency(&mut self, frequency: u32) -> Result<u32, Error<E>> {
+        let lock = self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::SpiDevice.enter();
+        let (_, actual) = lock.clock_cmd(frequency).map_err(Error::Hal)?;
+        self.frequency = Some(actual);
+        Ok(actual)
+    }
+}
+
+impl<Device: MpsseCmdExecutor> Drop for SpiDevice<Device> {
+    fn drop(&mut self) {
+        // a poisoned mutex has nothing left worth releasing
+        if let Ok(mut lock) = self.mtx.lock() {
+            let _origin = Origin::SpiDevice.enter();
+            lock.spi_devices -= 1;
+            let _ = if lock.spi_devices == 0 {
+                lock.release_pins(&[Pin::Lower(0), Pin::Lower(1), Pin::Lower(2), self.cs])
+            } else {
+                lock.release_pins(&[self.cs])
+            };
+        }
+    }
 }
 
 impl<Device, E> eh1::spi::ErrorType for SpiDevice<Device>
@@ -513,97 +1427,176 @@ where
     type Error = Error<E>;
 }
 
-impl<Device, E> eh1::spi::SpiDevice for SpiDevice<Device>
+impl<Device, E> SpiDevice<Device>
 where
     Device: MpsseCmdExecutor<Error = E>,
     E: std::error::Error,
     Error<E>: From<E>,
 {
-    fn transaction(
+    /// Run a transaction with the chip select asserted.
+    ///
+    /// This is the same as the embedded-hal [`transaction`], with access to
+    /// the bus methods that have no [`Operation`], such as
+    /// [`SpiDeviceBus::write_bits`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ftdi_embedded_hal as hal;
+    /// use hal::sim::SimDevice;
+    ///
+    /// let hal = hal::FtHal::init_default(SimDevice::new())?;
+    /// let mut lcd = hal.spi_device(3)?;
+    /// // 9-bit command 0x02C
+    /// lcd.transaction_with(|bus| bus.write_bits(&[0x16, 0x00], 9))?;
+    /// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// [`transaction`]: eh1::spi::SpiDevice::transaction
+    /// [`Operation`]: eh1::spi::Operation
+    pub fn transaction_with<R>(
         &mut self,
-        operations: &mut [eh1::spi::Operation<'_, u8>],
-    ) -> Result<(), Self::Error> {
+        f: impl FnOnce(&mut SpiDeviceBus<'_, Device>) -> Result<R, Error<E>>,
+    ) -> Result<R, Error<E>> {
         // lock the bus
         let mut lock: MutexGuard<FtInner<Device>> =
             self.mtx.lock().expect("Failed to aquire FTDI mutex");
-        let direction: u8 = lock.direction;
+        let _origin = Origin::SpiDevice.enter();
 
-        // assert the chip select pin
-        let mut value_cs_asserted: u8 = lock.value & !self.cs_mask();
-
-        // drive pin 0, the clock pin according to the CPOL setting
-        // Reference: https://github.com/ftdi-rs/ftdi-embedded-hal/pull/69
-        match self.pol.clk {
-            ClockData::MsbNegIn | ClockData::LsbNegIn => {
-                value_cs_asserted |= 1;
-            }
-            ClockData::MsbPosIn | ClockData::LsbPosIn => {
-                value_cs_asserted &= !1;
-            }
+        let clock: Option<u32> = self.clock_change(&lock);
+        let cmd: MpsseCmdBuilder = self.select(&mut lock, clock)?;
+        lock.ft.send(cmd.send_immediate().as_slice())?;
+        if clock.is_some() {
+            lock.clock = clock;
         }
 
-        lock.ft.send(
-            MpsseCmdBuilder::new()
-                .set_gpio_lower(value_cs_asserted, direction)
-                .send_immediate()
-                .as_slice(),
-        )?;
-
         let mut bus: SpiDeviceBus<Device> = SpiDeviceBus {
             lock,
-            pol: self.pol,
+            mode: self.mode,
         };
 
-        for op in operations {
-            match op {
-                eh1::spi::Operation::Read(buffer) => {
-                    eh1::spi::SpiBus::read(&mut bus, buffer)?;
-                }
-                eh1::spi::Operation::Write(buffer) => {
-                    eh1::spi::SpiBus::write(&mut bus, buffer)?;
-                }
-                eh1::spi::Operation::Transfer(read, write) => {
-                    eh1::spi::SpiBus::transfer(&mut bus, read, write)?;
-                }
-                eh1::spi::Operation::TransferInPlace(buffer) => {
-                    eh1::spi::SpiBus::transfer_in_place(&mut bus, buffer)?;
-                }
-                eh1::spi::Operation::DelayNs(micros) => {
-                    std::thread::sleep(std::time::Duration::from_nanos((*micros).into()));
-                }
-            }
-        }
+        let ret: R = f(&mut bus)?;
 
         // flush the bus
-        {
-            use eh1::spi::SpiBus;
-            bus.flush()?;
-        }
+        eh1::spi::SpiBus::<u8>::flush(&mut bus)?;
 
         let mut lock: MutexGuard<FtInner<Device>> = bus.lock;
+        let cmd: MpsseCmdBuilder = self.deselect(&lock, MpsseCmdBuilder::new(), lock.clock);
+        lock.ft.send(cmd.send_immediate().as_slice())?;
 
-        // deassert the chip select pin
-        let mut value_cs_deasserted: u8 = lock.value | self.cs_mask();
+        // unlocking the bus is implicit via Drop
+        Ok(ret)
+    }
+
+    /// Clock frequency to program before a transaction, `None` if the bus
+    /// already runs at the frequency of this device.
+    ///
+    /// The previous transaction may have been with another device, a device
+    /// without a frequency of its own returns the bus to the clock frequency
+    /// of the bus.
+    fn clock_change(&self, lock: &FtInner<Device>) -> Option<u32> {
+        self.frequency
+            .or(lock.bus_clock)
+            .filter(|&frequency| lock.clock != Some(frequency))
+    }
+
+    /// Build the commands that configure the bus for this device and
+    /// assert the chip select pin.
+    ///
+    /// `clock` is the result of [`SpiDevice::clock_change`], the caller
+    /// records it once the commands are sent.
+    fn select(
+        &self,
+        lock: &mut FtInner<Device>,
+        clock: Option<u32>,
+    ) -> Result<MpsseCmdBuilder, Error<E>> {
+        let direction: u8 = lock.direction;
+
+        // reprogram the clock divisor and idle clock level if they differ
+        let mut cmd: MpsseCmdBuilder = MpsseCmdBuilder::new();
+        if let Some(frequency) = clock {
+            (cmd, _) = lock.clock_cmd(frequency).map_err(Error::Hal)?;
+        }
 
         // drive pin 0, the clock pin according to the CPOL setting
         // Reference: https://github.com/ftdi-rs/ftdi-embedded-hal/pull/69
-        match self.pol.clk {
-            ClockData::MsbNegIn | ClockData::LsbNegIn => {
-                value_cs_deasserted |= 1;
-            }
-            ClockData::MsbPosIn | ClockData::LsbPosIn => {
-                value_cs_deasserted &= !1;
-            }
+        let mut value: u8 = lock.value;
+        if self.mode.idle_high {
+            value |= 1;
+        } else {
+            value &= !1;
+        }
+        if value != lock.value {
+            lock.value = value;
+            cmd = cmd.set_gpio_lower(value, direction);
         }
 
-        lock.ft.send(
-            MpsseCmdBuilder::new()
-                .set_gpio_lower(value_cs_deasserted, direction)
-                .send_immediate()
-                .as_slice(),
-        )?;
+        // assert the chip select pin, held for the setup time
+        Ok(self.cs_cmd(lock, cmd, true, 1 + cmd_count(self.config.cs_setup)))
+    }
 
-        // unlocking the bus is implicit via Drop
+    /// Append the commands deasserting the chip select pin to `cmd`, with
+    /// the idle time clocked at `frequency`.
+    fn deselect(
+        &self,
+        lock: &FtInner<Device>,
+        cmd: MpsseCmdBuilder,
+        frequency: Option<u32>,
+    ) -> MpsseCmdBuilder {
+        let cmd: MpsseCmdBuilder = self.cs_cmd(lock, cmd, true, cmd_count(self.config.cs_hold));
+        let cmd: MpsseCmdBuilder = self.cs_cmd(lock, cmd, false, 1);
+        idle_cmd(cmd, self.config.cs_idle_min, frequency)
+    }
+
+    /// Run embedded-hal SPI operations.
+    ///
+    /// The operations are sent as a single command stream with the chip
+    /// select commands, split into pieces for large transfers.
+    /// The data read is scattered into the read buffers.
+    ///
+    /// Delays repeat the command asserting the chip select, clocking
+    /// without data would clock the device.
+    fn transaction_ops<W: Word>(
+        &mut self,
+        operations: &mut [eh1::spi::Operation<'_, W>],
+    ) -> Result<(), Error<E>> {
+        // lock the bus
+        let mut lock: MutexGuard<FtInner<Device>> =
+            self.mtx.lock().expect("Failed to aquire FTDI mutex");
+        let _origin = Origin::SpiDevice.enter();
+
+        let clock: Option<u32> = self.clock_change(&lock);
+        let frequency: Option<u32> = clock.or(lock.clock);
+        let cmd: MpsseCmdBuilder = self.select(&mut lock, clock)?;
+        let hold: MpsseCmdBuilder = self.cs_cmd(&lock, MpsseCmdBuilder::new(), true, 1);
+        let end: MpsseCmdBuilder = self.deselect(&lock, MpsseCmdBuilder::new(), frequency);
+        let mut pipeline: Pipeline<Device> = Pipeline::new(&mut lock, self.mode, cmd);
+        pipeline.operations(operations, &hold)?;
+        let data: Vec<u8> = pipeline.finish(end)?;
+        if clock.is_some() {
+            lock.clock = clock;
+        }
+        self.mode.scatter(operations, &data);
         Ok(())
     }
 }
+
+macro_rules! impl_spi_device_words {
+    ($($word:ty),*) => {$(
+        impl<Device, E> eh1::spi::SpiDevice<$word> for SpiDevice<Device>
+        where
+            Device: MpsseCmdExecutor<Error = E>,
+            E: std::error::Error,
+            Error<E>: From<E>,
+        {
+            fn transaction(
+                &mut self,
+                operations: &mut [eh1::spi::Operation<'_, $word>],
+            ) -> Result<(), Self::Error> {
+                self.transaction_ops(operations)
+            }
+        }
+    )*};
+}
+
+impl_spi_device_words!(u8, u16, u32);
@@ -0,0 +1,476 @@
+//! Recording of MPSSE traffic.
+//!
+//! [`TracingExecutor`] wraps a device and records every transfer made
+//! through it, along with the HAL object that made the transfer.
+//! The resulting [`Trace`] can be saved to a file and attached to a bug
+//! report.
+//!
+//! [`ReplayExecutor`] drives the HAL from a recorded trace, which turns a
+//! recording made on real hardware into a regression test.
+//!
+//! # Example
+//!
+//! ```
+//! use eh1::digital::OutputPin;
+//! use ftdi_embedded_hal as hal;
+//! use hal::Pin;
+//! use hal::sim::SimDevice;
+//! use hal::trace::{Direction, Origin, Trace, TracingExecutor};
+//!
+//! let device = TracingExecutor::new(SimDevice::new());
+//! let mut hal = hal::FtHal::init_default(device)?;
+//! let mut pin = hal.ad3()?;
+//! pin.set_high()?;
+//!
+//! let trace: Trace = hal.with_device(|d| d.take_trace());
+//! let record = trace.records().last().unwrap();
+//! assert_eq!(record.origin, Origin::OutputPin(Pin::Lower(3)));
+//! assert_eq!(record.direction, Direction::Send);
+//!
+//! // save the trace to a file, or anything else implementing io::Write
+//! let mut file: Vec<u8> = Vec::new();
+//! trace.write(&mut file)?;
+//! assert_eq!(Trace::read(file.as_slice())?, trace);
+//! # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # File format
+//!
+//! Traces are stored as UTF-8 text.
+//! The first line is the header `ftdi-embedded-hal trace 1`, followed by one
+//! line per record with space separated fields:
+//!
+//! ```text
+//! <timestamp> <duration> <origin> <direction> <tx> <rx> [error: <message>]
+//! ```
+//!
+//! * `timestamp` is the start of the transfer in nanoseconds since tracing
+//!   started, and `duration` the duration of the transfer in nanoseconds.
+//! * `origin` is `hal`, `spi`, `spi-device`, `i2c`, `output:<pin>`,
+//!   `input:<pin>` or `unknown`, where `<pin>` is a pin name such as `AD3`.
+//! * `direction` is `send`, `recv` or `xfer`.
+//! * `tx` and `rx` are the bytes written and read as lowercase hex, or `-`
+//!   when empty.
+//! * A failed transfer ends with `error: ` followed by the error message.
+//!
+//! Empty lines and lines starting with `#` are ignored.
+use crate::gpio::Pin;
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use std::cell::Cell;
+use std::fmt::{self, Write as _};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+mod replay;
+
+pub use replay::{ReplayError, ReplayExecutor};
+
+/// First line of a trace file.
+const HEADER: &str = "ftdi-embedded-hal trace 1";
+
+thread_local! {
+    /// HAL object currently accessing the device on this thread.
+    static ORIGIN: Cell<Origin> = const { Cell::new(Origin::Unknown) };
+}
+
+/// HAL object that made a transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Origin {
+    /// Transfer made outside of the HAL, for example with
+    /// [`FtHal::with_device`].
+    ///
+    /// [`FtHal::with_device`]: crate::FtHal::with_device
+    Unknown,
+    /// [`FtHal`](crate::FtHal).
+    FtHal,
+    /// [`Spi`](crate::Spi).
+    Spi,
+    /// [`SpiDevice`](crate::SpiDevice).
+    SpiDevice,
+    /// [`I2c`](crate::I2c).
+    I2c,
+    /// [`OutputPin`](crate::OutputPin).
+    OutputPin(Pin),
+    /// [`InputPin`](crate::InputPin).
+    InputPin(Pin),
+}
+
+impl Origin {
+    /// Set the origin of transfers made on this thread until the returned
+    /// guard is dropped.
+    pub(crate) fn enter(self) -> OriginGuard {
+        OriginGuard(ORIGIN.replace(self))
+    }
+
+    fn current() -> Origin {
+        ORIGIN.get()
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Unknown => f.write_str("unknown"),
+            Origin::FtHal => f.write_str("hal"),
+            Origin::Spi => f.write_str("spi"),
+            Origin::SpiDevice => f.write_str("spi-device"),
+            Origin::I2c => f.write_str("i2c"),
+            Origin::OutputPin(pin) => write!(f, "output:{pin}"),
+            Origin::InputPin(pin) => write!(f, "input:{pin}"),
+        }
+    }
+}
+
+/// Restores the previous origin when dropped.
+#[derive(Debug)]
+pub(crate) struct OriginGuard(Origin);
+
+impl Drop for OriginGuard {
+    fn drop(&mut self) {
+        ORIGIN.set(self.0);
+    }
+}
+
+/// Transfer direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// [`MpsseCmdExecutor::send`].
+    Send,
+    /// [`MpsseCmdExecutor::recv`].
+    Recv,
+    /// [`MpsseCmdExecutor::xfer`].
+    Xfer,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Direction::Send => "send",
+            Direction::Recv => "recv",
+            Direction::Xfer => "xfer",
+        })
+    }
+}
+
+/// Recorded transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Start of the transfer, relative to the start of tracing.
+    pub timestamp: Duration,
+    /// Duration of the transfer.
+    pub duration: Duration,
+    /// HAL object that made the transfer.
+    pub origin: Origin,
+    /// Transfer direction.
+    pub direction: Direction,
+    /// Bytes sent to the device, empty for [`Direction::Recv`].
+    pub tx: Vec<u8>,
+    /// Bytes received from the device, empty for [`Direction::Send`].
+    pub rx: Vec<u8>,
+    /// Error message if the transfer failed.
+    pub error: Option<String>,
+}
+
+/// Hex encode bytes, `-` if empty.
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return String::from("-");
+    }
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, byte| {
+            let _ = write!(s, "{byte:02x}");
+            s
+        })
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.timestamp.as_nanos(),
+            self.duration.as_nanos(),
+            self.origin,
+            self.direction,
+            hex(&self.tx),
+            hex(&self.rx)
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, " error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    s.parse().ok().map(Duration::from_nanos)
+}
+
+fn parse_pin(s: &str) -> Option<Pin> {
+    let (byte, idx) = s.split_at_checked(2)?;
+    let idx: u8 = idx.parse().ok()?;
+    match byte {
+        "AD" => Some(Pin::Lower(idx)),
+        "AC" => Some(Pin::Upper(idx)),
+        _ => None,
+    }
+}
+
+fn parse_origin(s: &str) -> Option<Origin> {
+    match s {
+        "unknown" => Some(Origin::Unknown),
+        "hal" => Some(Origin::FtHal),
+        "spi" => Some(Origin::Spi),
+        "spi-device" => Some(Origin::SpiDevice),
+        "i2c" => Some(Origin::I2c),
+        _ => {
+            if let Some(pin) = s.strip_prefix("output:") {
+                parse_pin(pin).map(Origin::OutputPin)
+            } else if let Some(pin) = s.strip_prefix("input:") {
+                parse_pin(pin).map(Origin::InputPin)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+fn parse_direction(s: &str) -> Option<Direction> {
+    match s {
+        "send" => Some(Direction::Send),
+        "recv" => Some(Direction::Recv),
+        "xfer" => Some(Direction::Xfer),
+        _ => None,
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s == "-" {
+        return Some(Vec::new());
+    }
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&s[idx..idx + 2], 16).ok())
+        .collect()
+}
+
+impl Record {
+    fn parse(line: &str) -> Option<Record> {
+        let mut fields = line.splitn(7, ' ');
+        let record = Record {
+            timestamp: parse_duration(fields.next()?)?,
+            duration: parse_duration(fields.next()?)?,
+            origin: parse_origin(fields.next()?)?,
+            direction: parse_direction(fields.next()?)?,
+            tx: parse_hex(fields.next()?)?,
+            rx: parse_hex(fields.next()?)?,
+            error: match fields.next() {
+                Some(error) => Some(error.strip_prefix("error: ")?.to_string()),
+                None => None,
+            },
+        };
+        Some(record)
+    }
+}
+
+/// Recorded MPSSE traffic.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    records: Vec<Record>,
+}
+
+impl Trace {
+    /// Create a new empty trace.
+    pub fn new() -> Trace {
+        Trace::default()
+    }
+
+    /// Get the recorded transfers in chronological order.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Append a transfer.
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record)
+    }
+
+    /// Write the trace in the [file format](self#file-format).
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "{self}")?;
+        writer.flush()
+    }
+
+    /// Read a trace in the [file format](self#file-format).
+    ///
+    /// Malformed input returns an error of kind
+    /// [`io::ErrorKind::InvalidData`].
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Trace> {
+        let mut lines = reader.lines();
+        match lines.next().transpose()? {
+            Some(header) if header.trim_end() == HEADER => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "missing trace header",
+                ));
+            }
+        }
+
+        let mut trace = Trace::new();
+        for (idx, line) in lines.enumerate() {
+            let line: String = line?;
+            let line: &str = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let record: Record = Record::parse(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed trace record on line {}", idx + 2),
+                )
+            })?;
+            trace.push(record);
+        }
+        Ok(trace)
+    }
+
+    /// Save the trace to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    /// Load a trace from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Trace> {
+        Trace::read(io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for record in &self.records {
+            writeln!(f, "{record}")?;
+        }
+        Ok(())
+    }
+}
+
+/// MPSSE executor that records all transfers.
+///
+/// Transfers are forwarded to the wrapped device and recorded with the HAL
+/// object that made them.
+/// Calls to [`MpsseCmdExecutor::init`] are forwarded, but not recorded.
+///
+/// The trace is retrieved with [`FtHal::with_device`] once the device is
+/// owned by the HAL, see the [module documentation](self) for an example.
+///
+/// [`FtHal::with_device`]: crate::FtHal::with_device
+#[derive(Debug)]
+pub struct TracingExecutor<D> {
+    device: D,
+    start: Instant,
+    trace: Trace,
+}
+
+impl<D> TracingExecutor<D> {
+    /// Wrap a device, the trace starts now.
+    pub fn new(device: D) -> TracingExecutor<D> {
+        TracingExecutor {
+            device,
+            start: Instant::now(),
+            trace: Trace::new(),
+        }
+    }
+
+    /// Get the transfers recorded so far.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Take the transfers recorded so far, leaving an empty trace.
+    pub fn take_trace(&mut self) -> Trace {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Get a reference to the wrapped device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Get a mutable reference to the wrapped device.
+    ///
+    /// Transfers made directly on the device are not recorded.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Unwrap the device, discarding the trace.
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+}
+
+impl<D> TracingExecutor<D>
+where
+    D: MpsseCmdExecutor,
+    D::Error: fmt::Display,
+{
+    /// Run a transfer on the device and record it.
+    fn record<F>(&mut self, direction: Direction, tx: &[u8], f: F) -> Result<(), D::Error>
+    where
+        F: FnOnce(&mut D) -> (Result<(), D::Error>, Vec<u8>),
+    {
+        let origin: Origin = Origin::current();
+        let start: Instant = Instant::now();
+        let (result, rx) = f(&mut self.device);
+        self.trace.push(Record {
+            timestamp: start.saturating_duration_since(self.start),
+            duration: start.elapsed(),
+            origin,
+            direction,
+            tx: tx.to_vec(),
+            rx,
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+        result
+    }
+}
+
+impl<D> MpsseCmdExecutor for TracingExecutor<D>
+where
+    D: MpsseCmdExecutor,
+    D::Error: fmt::Display,
+{
+    type Error = D::Error;
+
+    fn init(&mut self, settings: &MpsseSettings) -> Result<(), Self::Error> {
+        self.device.init(settings)
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.record(Direction::Send, data, |device| {
+            (device.send(data), Vec::new())
+        })
+    }
+
+    fn recv(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+        self.record(Direction::Recv, &[], |device| {
+            let result = device.recv(data);
+            (result, data.to_vec())
+        })
+    }
+
+    fn xfer(&mut self, txdata: &[u8], rxdata: &mut [u8]) -> Result<(), Self::Error> {
+        self.record(Direction::Xfer, txdata, |device| {
+            let result = device.xfer(txdata, rxdata);
+            (result, rxdata.to_vec())
+        })
+    }
+}
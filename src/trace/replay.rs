@@ -0,0 +1,360 @@
+use super::{Direction, Origin, Record, Trace};
+use crate::disasm::{Disassembly, MpsseOp, disassemble};
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use std::fmt;
+
+/// Maximum number of differing commands shown for each side of a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Replay error.
+///
+/// `record` fields are indices into [`Trace::records`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplayError {
+    /// The HAL sent commands that differ from the recording.
+    Mismatch {
+        /// Recorded transfer that differs.
+        record: usize,
+        /// HAL object that made the recorded transfer.
+        origin: Origin,
+        /// Offset of the first differing byte in the recorded commands.
+        offset: usize,
+        /// Recorded commands.
+        expected: Vec<u8>,
+        /// Commands sent by the HAL, aligned with `expected`.
+        actual: Vec<u8>,
+    },
+    /// The HAL sent commands where the recording reads a response, or
+    /// after the end of the recording.
+    UnexpectedSend {
+        /// Recorded read, `None` at the end of the recording.
+        record: Option<usize>,
+        /// Commands sent by the HAL.
+        data: Vec<u8>,
+    },
+    /// The HAL read a response where the recording sends commands, or
+    /// after the end of the recording.
+    UnexpectedRecv {
+        /// Recorded send, `None` at the end of the recording.
+        record: Option<usize>,
+        /// Recorded commands not yet sent by the HAL.
+        expected: Vec<u8>,
+        /// Number of bytes the HAL attempted to read.
+        len: usize,
+    },
+    /// The recorded transfer failed.
+    Recorded {
+        /// Failed transfer.
+        record: usize,
+        /// Recorded error message.
+        message: String,
+    },
+    /// Returned by [`ReplayExecutor::finish`] if the recording was not
+    /// replayed in full.
+    Unfinished {
+        /// First transfer that was not replayed.
+        record: usize,
+    },
+}
+
+/// Write up to [`DIFF_CONTEXT`] lines of a listing, starting at line `start`.
+fn write_lines(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    disassembly: &Disassembly,
+    start: usize,
+) -> fmt::Result {
+    let listing: String = disassembly.to_string();
+    for line in listing.lines().skip(start).take(DIFF_CONTEXT) {
+        writeln!(f)?;
+        write!(f, "{prefix}{line}")?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Mismatch {
+                record,
+                origin,
+                offset,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Replay diverged from record {record} ({origin}) at byte {offset}"
+                )?;
+                if let (Some(e), Some(a)) = (expected.get(*offset), actual.get(*offset)) {
+                    write!(f, ", expected 0x{e:02x} found 0x{a:02x}")?;
+                }
+
+                let expected: Disassembly = disassemble(expected);
+                let actual: Disassembly = disassemble(actual);
+                let expected_ops: Vec<(usize, &MpsseOp)> = expected.ops_with_offsets().collect();
+                let actual_ops: Vec<(usize, &MpsseOp)> = actual.ops_with_offsets().collect();
+                let common: usize = expected_ops
+                    .iter()
+                    .zip(actual_ops.iter())
+                    .take_while(|(e, a)| e == a)
+                    .count();
+                if let Some(previous) = common.checked_sub(1) {
+                    write_lines(f, "  ", &expected, previous)?;
+                }
+                write_lines(f, "- ", &expected, common)?;
+                write_lines(f, "+ ", &actual, common)
+            }
+            ReplayError::UnexpectedSend { record, data } => {
+                match record {
+                    Some(record) => write!(
+                        f,
+                        "Replay diverged from record {record}, expected a read but commands were sent"
+                    )?,
+                    None => write!(f, "Commands sent after the end of the recording")?,
+                }
+                write_lines(f, "+ ", &disassemble(data), 0)
+            }
+            ReplayError::UnexpectedRecv {
+                record,
+                expected,
+                len,
+            } => {
+                match record {
+                    Some(record) => write!(
+                        f,
+                        "Replay diverged from record {record}, expected commands but {len} bytes were read"
+                    )?,
+                    None => write!(f, "{len} bytes read after the end of the recording")?,
+                }
+                write_lines(f, "- ", &disassemble(expected), 0)
+            }
+            ReplayError::Recorded { record, message } => {
+                write!(f, "Recorded transfer {record} failed: {message}")
+            }
+            ReplayError::Unfinished { record } => {
+                write!(f, "Recording was not replayed past record {record}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Half of a recorded transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Send,
+    Recv,
+}
+
+/// Send or receive part of a recorded transfer.
+#[derive(Debug)]
+struct Event {
+    /// Index of the record.
+    record: usize,
+    kind: Kind,
+    data: Vec<u8>,
+    /// Recorded error message, on the last event of a failed record.
+    error: Option<String>,
+}
+
+/// MPSSE executor that replays a recorded [`Trace`].
+///
+/// Commands sent by the HAL are compared with the recording, and reads are
+/// served with the recorded responses.
+/// Any difference fails the transfer with a [`ReplayError`] describing the
+/// difference.
+///
+/// The recording is treated as a stream, commands may be split over
+/// [`send`], [`recv`] and [`xfer`] calls differently than when they were
+/// recorded.
+/// Timestamps and the [`MpsseCmdExecutor::init`] settings are not
+/// checked.
+///
+/// [`send`]: MpsseCmdExecutor::send
+/// [`recv`]: MpsseCmdExecutor::recv
+/// [`xfer`]: MpsseCmdExecutor::xfer
+///
+/// # Example
+///
+/// ```
+/// use eh1::i2c::I2c;
+/// use ftdi_embedded_hal as hal;
+/// use hal::sim::{I2cPeripheral, Lm75, SimDevice};
+/// use hal::trace::{ReplayExecutor, Trace, TracingExecutor};
+///
+/// // record reading an LM75 on the simulator, or real hardware
+/// let sensor = Lm75::new(0x48);
+/// sensor.set_temperature(21.5);
+/// let mut device = SimDevice::new();
+/// device.connect(hal::Pin::Lower(1), hal::Pin::Lower(2));
+/// device.attach(I2cPeripheral::new(sensor));
+/// let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 400_000)?;
+/// let mut buf = [0; 2];
+/// hal.i2c()?.write_read(0x48_u8, &[0x00], &mut buf)?;
+/// let trace: Trace = hal.with_device(|d| d.take_trace());
+///
+/// // replay the recording
+/// let mut hal = hal::FtHal::init_freq(ReplayExecutor::new(&trace), 400_000)?;
+/// let mut buf = [0; 2];
+/// hal.i2c()?.write_read(0x48_u8, &[0x00], &mut buf)?;
+/// assert_eq!(buf, [21, 0x80]);
+/// hal.with_device(|d| d.finish())?;
+///
+/// // the register pointer differs from the recording
+/// let mut hal = hal::FtHal::init_freq(ReplayExecutor::new(&trace), 400_000)?;
+/// assert!(hal.i2c()?.write_read(0x48_u8, &[0x01], &mut buf).is_err());
+/// # Ok::<(), std::boxed::Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ReplayExecutor {
+    events: Vec<Event>,
+    /// Origins of the records.
+    origins: Vec<Origin>,
+    /// Index of the current event.
+    idx: usize,
+    /// Offset in the current event.
+    offset: usize,
+}
+
+impl ReplayExecutor {
+    /// Create a new executor replaying a trace.
+    pub fn new(trace: &Trace) -> ReplayExecutor {
+        let mut events: Vec<Event> = Vec::new();
+        for (idx, record) in trace.records().iter().enumerate() {
+            let Record {
+                direction, tx, rx, ..
+            } = record;
+            if matches!(direction, Direction::Send | Direction::Xfer) {
+                events.push(Event {
+                    record: idx,
+                    kind: Kind::Send,
+                    data: tx.clone(),
+                    error: None,
+                });
+            }
+            if matches!(direction, Direction::Recv | Direction::Xfer) {
+                events.push(Event {
+                    record: idx,
+                    kind: Kind::Recv,
+                    data: rx.clone(),
+                    error: None,
+                });
+            }
+            if let Some(event) = events.last_mut()
+                && event.record == idx
+            {
+                event.error.clone_from(&record.error);
+            }
+        }
+        // empty transfers cannot be matched
+        events.retain(|event| !event.data.is_empty());
+
+        ReplayExecutor {
+            events,
+            origins: trace.records().iter().map(|record| record.origin).collect(),
+            idx: 0,
+            offset: 0,
+        }
+    }
+
+    /// Check that the recording was replayed in full.
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        match self.events.get(self.idx) {
+            Some(event) => Err(ReplayError::Unfinished {
+                record: event.record,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Advance by `len` bytes within the current event.
+    fn advance(&mut self, len: usize) -> Result<(), ReplayError> {
+        let event: &Event = &self.events[self.idx];
+        self.offset += len;
+        if self.offset == event.data.len() {
+            self.idx += 1;
+            self.offset = 0;
+            if let Some(message) = &event.error {
+                return Err(ReplayError::Recorded {
+                    record: event.record,
+                    message: message.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MpsseCmdExecutor for ReplayExecutor {
+    type Error = ReplayError;
+
+    fn init(&mut self, _settings: &MpsseSettings) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        let mut pos: usize = 0;
+        while pos < data.len() {
+            let Some(event) = self.events.get(self.idx) else {
+                return Err(ReplayError::UnexpectedSend {
+                    record: None,
+                    data: data[pos..].to_vec(),
+                });
+            };
+            if event.kind != Kind::Send {
+                return Err(ReplayError::UnexpectedSend {
+                    record: Some(event.record),
+                    data: data[pos..].to_vec(),
+                });
+            }
+
+            let expected: &[u8] = &event.data[self.offset..];
+            let actual: &[u8] = &data[pos..];
+            let len: usize = expected.len().min(actual.len());
+            if let Some(diff) = expected.iter().zip(actual).position(|(e, a)| e != a) {
+                let mut aligned: Vec<u8> = event.data[..self.offset].to_vec();
+                aligned.extend_from_slice(actual);
+                return Err(ReplayError::Mismatch {
+                    record: event.record,
+                    origin: self.origins[event.record],
+                    offset: self.offset + diff,
+                    expected: event.data.clone(),
+                    actual: aligned,
+                });
+            }
+            self.advance(len)?;
+            pos += len;
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self, data: &mut [u8]) -> Result<(), Self::Error> {
+        let mut pos: usize = 0;
+        while pos < data.len() {
+            let Some(event) = self.events.get(self.idx) else {
+                return Err(ReplayError::UnexpectedRecv {
+                    record: None,
+                    expected: Vec::new(),
+                    len: data.len() - pos,
+                });
+            };
+            if event.kind != Kind::Recv {
+                return Err(ReplayError::UnexpectedRecv {
+                    record: Some(event.record),
+                    expected: event.data[self.offset..].to_vec(),
+                    len: data.len() - pos,
+                });
+            }
+
+            let available: &[u8] = &event.data[self.offset..];
+            let len: usize = available.len().min(data.len() - pos);
+            data[pos..pos + len].copy_from_slice(&available[..len]);
+            self.advance(len)?;
+            pos += len;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Simulated version of `examples/at24c04.rs`.
+
+use eeprom24x::Eeprom24x;
+use eeprom24x::SlaveAddr;
+use ftdi_embedded_hal::{self as hal, Pin};
+use hal::sim::{At24c04, I2cPeripheral, SimDevice};
+use std::time::Duration;
+
+#[test]
+fn at24c04() {
+    let model = At24c04::new(0x50);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model.clone()));
+
+    let mut hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut eeprom = Eeprom24x::new_24x04(i2c, SlaveAddr::default());
+    let delay = Duration::from_millis(5);
+
+    // check high memory addresses: 1 bit passed as a part of i2c addr
+    let addrs1: [u32; 4] = [0x100, 0x10F, 0x1F0, 0x1EE];
+    let byte_w1 = 0xe5;
+    let addrs2: [u32; 4] = [0x00, 0x0F, 0xF0, 0xEE];
+    let byte_w2 = 0xaa;
+
+    // write bytes
+
+    for addr in addrs1.iter() {
+        eeprom.write_byte(*addr, byte_w1).unwrap();
+        hal.with_device(|sim| sim.advance(delay));
+    }
+
+    for addr in addrs2.iter() {
+        eeprom.write_byte(*addr, byte_w2).unwrap();
+        hal.with_device(|sim| sim.advance(delay));
+    }
+
+    // read bytes and check
+
+    for addr in addrs1.iter() {
+        let byte_r = eeprom.read_byte(*addr).unwrap();
+        assert_eq!(byte_w1, byte_r);
+    }
+
+    for addr in addrs2.iter() {
+        let byte_r = eeprom.read_byte(*addr).unwrap();
+        assert_eq!(byte_w2, byte_r);
+    }
+
+    let memory: Vec<u8> = model.memory();
+    for addr in addrs1.iter() {
+        assert_eq!(memory[*addr as usize], byte_w1);
+    }
+    for addr in addrs2.iter() {
+        assert_eq!(memory[*addr as usize], byte_w2);
+    }
+}
+
+#[test]
+fn write_cycle() {
+    let model = At24c04::new(0x50);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model.clone()));
+
+    let mut hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut eeprom = Eeprom24x::new_24x04(i2c, SlaveAddr::default());
+
+    eeprom.write_byte(0x42, 0x24).unwrap();
+    // the EEPROM does not respond during the write cycle
+    assert!(eeprom.read_byte(0x42).is_err());
+    hal.with_device(|sim| sim.advance(Duration::from_millis(5)));
+    assert_eq!(eeprom.read_byte(0x42).unwrap(), 0x24);
+}
+
+#[test]
+fn page_write() {
+    let model = At24c04::new(0x50);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model.clone()));
+
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut eeprom = Eeprom24x::new_24x04(i2c, SlaveAddr::default());
+
+    let data: [u8; 4] = [0x10, 0x20, 0x30, 0x40];
+    eeprom.write_page(0x1F0, &data).unwrap();
+    assert_eq!(model.memory()[0x1F0..0x1F4], data);
+}
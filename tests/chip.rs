@@ -0,0 +1,120 @@
+use ftdi_embedded_hal::{self as hal, Capability, Chip, ErrorKind, Pin};
+use hal::sim::{SimDevice, SimError};
+
+fn hal_kind<T: std::fmt::Debug>(result: Result<T, hal::Error<SimError>>) -> ErrorKind {
+    match result.unwrap_err() {
+        hal::Error::Hal(kind) => kind,
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[test]
+fn capabilities() {
+    assert!(Chip::FT232H.supports(Capability::Pin(Pin::Upper(7))));
+    assert!(!Chip::FT4232H.supports(Capability::Pin(Pin::Upper(0))));
+    assert!(Chip::FT2232D.supports(Capability::Pin(Pin::Upper(3))));
+    assert!(!Chip::FT2232D.supports(Capability::Pin(Pin::Upper(4))));
+    assert!(!Chip::FT2232H.supports(Capability::Pin(Pin::Lower(8))));
+
+    assert!(Chip::FT2232H.supports(Capability::ClockFrequency(30_000_000)));
+    assert!(!Chip::FT2232H.supports(Capability::ClockFrequency(30_000_001)));
+    assert!(Chip::FT2232D.supports(Capability::ClockFrequency(6_000_000)));
+    assert!(!Chip::FT2232D.supports(Capability::ClockFrequency(10_000_000)));
+    assert!(!Chip::FT232H.supports(Capability::ClockFrequency(50)));
+
+    assert!(Chip::FT232H.supports(Capability::OpenDrain));
+    assert!(!Chip::FT2232H.supports(Capability::OpenDrain));
+    assert!(!Chip::FT2232D.supports(Capability::ThreePhaseClocking));
+    assert!(!Chip::FT2232D.supports(Capability::AdaptiveClocking));
+    assert!(Chip::FT4232H.supports(Capability::AdaptiveClocking));
+    assert!(!Chip::FT2232D.supports(Capability::ClockWithoutData));
+    assert!(Chip::FT2232H.supports(Capability::ClockWithoutData));
+}
+
+#[test]
+fn detect() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    assert_eq!(hal.chip(), None);
+    assert_eq!(hal.detect_chip().unwrap(), Some(Chip::FT232H));
+    assert_eq!(hal.chip(), Some(Chip::FT232H));
+    hal.c7().unwrap();
+}
+
+#[test]
+fn ft4232h() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    hal.set_chip(Chip::FT4232H).unwrap();
+
+    assert_eq!(
+        hal_kind(hal.c0()),
+        ErrorKind::Unsupported {
+            chip: Chip::FT4232H,
+            capability: Capability::Pin(Pin::Upper(0)),
+        }
+    );
+    let err = hal.ci4().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "A regular error occurred \"The FT4232H does not support pin AC4\""
+    );
+    hal.ad7().unwrap();
+    hal.i2c().unwrap();
+}
+
+#[test]
+fn ft2232d() {
+    let hal = hal::FtHal::init_freq(SimDevice::new(), 1_000_000).unwrap();
+    hal.set_chip(Chip::FT2232D).unwrap();
+
+    assert_eq!(
+        hal_kind(hal.i2c()),
+        ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::ThreePhaseClocking,
+        }
+    );
+    hal.c3().unwrap();
+    assert_eq!(
+        hal_kind(hal.c4()),
+        ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::Pin(Pin::Upper(4)),
+        }
+    );
+    hal.spi().unwrap();
+}
+
+#[test]
+fn set_chip_checks() {
+    let hal = hal::FtHal::init_freq(SimDevice::new(), 10_000_000).unwrap();
+    assert_eq!(
+        hal_kind(hal.set_chip(Chip::FT2232D)),
+        ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::ClockFrequency(10_000_000),
+        }
+    );
+    assert_eq!(hal.chip(), None);
+
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let output = hal.c5().unwrap();
+    assert_eq!(
+        hal_kind(hal.set_chip(Chip::FT4232H)),
+        ErrorKind::Unsupported {
+            chip: Chip::FT4232H,
+            capability: Capability::Pin(Pin::Upper(5)),
+        }
+    );
+    drop(output);
+    hal.set_chip(Chip::FT4232H).unwrap();
+
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let _i2c = hal.i2c().unwrap();
+    assert_eq!(
+        hal_kind(hal.set_chip(Chip::FT2232D)),
+        ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::ThreePhaseClocking,
+        }
+    );
+}
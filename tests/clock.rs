@@ -0,0 +1,132 @@
+use eh1::i2c::I2c;
+use eh1::spi::{SpiBus, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Capability, Chip, ErrorKind, Pin};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice};
+use hal::trace::{Trace, TracingExecutor};
+
+fn hal_kind<T: std::fmt::Debug>(result: Result<T, hal::Error<hal::sim::SimError>>) -> ErrorKind {
+    match result.unwrap_err() {
+        hal::Error::Hal(kind) => kind,
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[test]
+fn set_clock_frequency() {
+    let mut hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 100_000);
+
+    for (requested, actual) in [
+        (1_000_000, 1_000_000),
+        (400_000, 400_000),
+        (30_000_000, 30_000_000),
+        (20_000_000, 15_000_000),
+        (7_000_000, 6_000_000),
+        (6_000_000, 6_000_000),
+        // 30 MHz divided by 6, 8 and 10 beats the 6 MHz base
+        (5_000_000, 5_000_000),
+        (4_000_000, 3_750_000),
+        (3_750_000, 3_750_000),
+        (92, 91),
+        (1_234_567, 1_200_000),
+    ] {
+        assert_eq!(hal.set_clock_frequency(requested).unwrap(), actual);
+        assert_eq!(hal.with_device(|sim| sim.clock_frequency()), actual);
+    }
+
+    assert_eq!(
+        hal_kind(hal.set_clock_frequency(0)),
+        ErrorKind::ClockFrequency(0)
+    );
+    assert_eq!(
+        hal_kind(hal.set_clock_frequency(91)),
+        ErrorKind::ClockFrequency(91)
+    );
+    assert_eq!(
+        hal_kind(hal.set_clock_frequency(30_000_001)),
+        ErrorKind::ClockFrequency(30_000_001)
+    );
+    // failures keep the previous frequency
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 1_200_000);
+}
+
+#[test]
+fn chip_limits() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    hal.set_chip(Chip::FT2232D).unwrap();
+    assert_eq!(hal.set_clock_frequency(6_000_000).unwrap(), 6_000_000);
+    assert_eq!(hal.set_clock_frequency(250_000).unwrap(), 250_000);
+    assert_eq!(
+        hal_kind(hal.set_clock_frequency(10_000_000)),
+        ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::ClockFrequency(10_000_000),
+        }
+    );
+}
+
+#[test]
+fn spi_frequency() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let mut hal = hal::FtHal::init_default(device).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    assert_eq!(spi.set_frequency(10_000_000).unwrap(), 10_000_000);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 10_000_000);
+
+    // 16 clock cycles at 10 MHz
+    let start = hal.with_device(|sim| sim.elapsed());
+    let mut buf: [u8; 2] = [0; 2];
+    spi.transfer(&mut buf, &[0x5A, 0xA5]).unwrap();
+    assert_eq!(buf, [0x5A, 0xA5]);
+    let elapsed = hal.with_device(|sim| sim.elapsed()) - start;
+    assert!(elapsed.as_nanos() >= 1600, "{elapsed:?}");
+    assert!(elapsed.as_nanos() < 1800, "{elapsed:?}");
+}
+
+#[test]
+fn i2c_frequency() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    let mut hal = hal::FtHal::init_default(device).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    // 3-phase clocking, the MPSSE clock is 3/2 of SCL
+    assert_eq!(i2c.set_frequency(400_000).unwrap(), 400_000);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 600_000);
+    assert_eq!(i2c.set_frequency(100_000).unwrap(), 100_000);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 150_000);
+    assert_eq!(i2c.set_frequency(333_333).unwrap(), 327_868);
+    // an MPSSE clock of 30 MHz / 6
+    assert_eq!(i2c.set_frequency(3_400_000).unwrap(), 3_333_333);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 5_000_000);
+
+    assert_eq!(i2c.set_frequency(1_000_000).unwrap(), 1_000_000);
+    i2c.write(0x20_u8, &[0x00, 0x12, 0x34]).unwrap();
+    let mut buf: [u8; 2] = [0; 2];
+    i2c.write_read(0x20_u8, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x12, 0x34]);
+}
+
+#[test]
+fn init_frequency() {
+    // the HAL programs the divisor that does not exceed the request
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(SimDevice::new()), 4_000_000).unwrap();
+    assert_eq!(hal.with_device(|d| d.device().clock_frequency()), 3_750_000);
+
+    // the bus already runs at the actual frequency
+    let mut spi = hal.spi_device(3).unwrap();
+    assert_eq!(spi.set_frequency(3_750_000).unwrap(), 3_750_000);
+    hal.with_device(|d| d.take_trace());
+    spi.write(&[0x00_u8]).unwrap();
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert!(trace.records().iter().all(|r| {
+        disassemble(&r.tx)
+            .ops()
+            .all(|op| !matches!(op, MpsseOp::SetClockDivisor(_)))
+    }));
+}
@@ -0,0 +1,164 @@
+use ftdi_embedded_hal::disasm::{MpsseOp, disassemble};
+use ftdi_mpsse::{
+    ClockBits, ClockBitsIn, ClockBitsOut, ClockData, ClockDataIn, ClockDataOut, ClockTMS,
+    ClockTMSOut, MpsseCmdBuilder,
+};
+
+#[test]
+fn builder_roundtrip() {
+    let cmd = MpsseCmdBuilder::new()
+        .set_clock(29, Some(false))
+        .enable_3phase_data_clocking()
+        .disable_3phase_data_clocking()
+        .enable_loopback()
+        .disable_loopback()
+        .enable_adaptive_data_clocking()
+        .disable_adaptive_data_clocking()
+        .set_gpio_lower(0x0B, 0x0F)
+        .set_gpio_upper(0x80, 0xC0)
+        .gpio_lower()
+        .gpio_upper()
+        .wait_on_io_high()
+        .wait_on_io_low()
+        .clock_data_out(ClockDataOut::LsbNeg, &[0x12, 0x34])
+        .clock_data_in(ClockDataIn::MsbNeg, 3)
+        .clock_data(ClockData::MsbPosIn, &[0xAB])
+        .clock_bits_out(ClockBitsOut::MsbNeg, 0xA0, 3)
+        .clock_bits_in(ClockBitsIn::LsbPos, 1)
+        .clock_bits(ClockBits::LsbNegIn, 0x55, 8)
+        .clock_tms_out(ClockTMSOut::NegEdge, 0x03, true, 2)
+        .clock_tms(ClockTMS::PosTMSNegTDO, 0x01, false, 1)
+        .send_immediate();
+
+    let ops: Vec<MpsseOp> = disassemble(cmd.as_slice()).into();
+    assert_eq!(
+        ops,
+        [
+            MpsseOp::DisableClockDivide,
+            MpsseOp::SetClockDivisor(29),
+            MpsseOp::Enable3Phase,
+            MpsseOp::Disable3Phase,
+            MpsseOp::EnableLoopback,
+            MpsseOp::DisableLoopback,
+            MpsseOp::EnableAdaptive,
+            MpsseOp::DisableAdaptive,
+            MpsseOp::SetGpioLower {
+                value: 0x0B,
+                direction: 0x0F
+            },
+            MpsseOp::SetGpioUpper {
+                value: 0x80,
+                direction: 0xC0
+            },
+            MpsseOp::GpioLower,
+            MpsseOp::GpioUpper,
+            MpsseOp::WaitOnIoHigh,
+            MpsseOp::WaitOnIoLow,
+            MpsseOp::ClockDataOut {
+                mode: ClockDataOut::LsbNeg,
+                data: vec![0x12, 0x34]
+            },
+            MpsseOp::ClockDataIn {
+                mode: ClockDataIn::MsbNeg,
+                len: 3
+            },
+            MpsseOp::ClockData {
+                mode: ClockData::MsbPosIn,
+                data: vec![0xAB]
+            },
+            MpsseOp::ClockBitsOut {
+                mode: ClockBitsOut::MsbNeg,
+                bits: 0xA0,
+                len: 3
+            },
+            MpsseOp::ClockBitsIn {
+                mode: ClockBitsIn::LsbPos,
+                len: 1
+            },
+            MpsseOp::ClockBits {
+                mode: ClockBits::LsbNegIn,
+                bits: 0x55,
+                len: 8
+            },
+            MpsseOp::ClockTmsOut {
+                mode: ClockTMSOut::NegEdge,
+                bits: 0x83,
+                len: 2
+            },
+            MpsseOp::ClockTms {
+                mode: ClockTMS::PosTMSNegTDO,
+                bits: 0x01,
+                len: 1
+            },
+            MpsseOp::SendImmediate,
+        ]
+    );
+}
+
+#[test]
+fn response_len() {
+    let cmd = MpsseCmdBuilder::new()
+        .set_gpio_lower(0x00, 0x03)
+        .gpio_lower()
+        .clock_data_in(ClockDataIn::MsbPos, 10)
+        .clock_data(ClockData::MsbNegIn, &[0; 4])
+        .clock_bits_in(ClockBitsIn::MsbPos, 1)
+        .clock_data_out(ClockDataOut::MsbNeg, &[0; 8])
+        .send_immediate();
+    assert_eq!(disassemble(cmd.as_slice()).response_len(), 1 + 10 + 4 + 1);
+}
+
+#[test]
+fn raw_commands() {
+    let disassembly = disassemble(&[0x8E, 0x07, 0x8F, 0x01, 0x00, 0x9E, 0x03, 0x00, 0xAA, 0x30]);
+    let ops: Vec<&MpsseOp> = disassembly.ops().collect();
+    assert_eq!(
+        ops,
+        [
+            &MpsseOp::ClockBitsNoData(8),
+            &MpsseOp::ClockBytesNoData(2),
+            &MpsseOp::DriveOnlyZero {
+                lower: 0x03,
+                upper: 0x00
+            },
+            &MpsseOp::Invalid(0xAA),
+        ]
+    );
+    assert_eq!(disassembly.incomplete(), [0x30]);
+    assert_eq!(
+        disassembly.to_string(),
+        "0000: clock_bits_no_data(8)\n\
+         0002: clock_bytes_no_data(2)\n\
+         0005: drive_only_zero(0x03, 0x00)\n\
+         0008: invalid(0xaa)\n\
+         0009: incomplete([30])\n"
+    );
+}
+
+#[test]
+fn shift_fallback() {
+    // data in and out on the positive edge has no ftdi-mpsse mode
+    let disassembly = disassemble(&[0x30, 0x01, 0x00, 0xDE, 0xAD]);
+    assert_eq!(
+        disassembly.ops().collect::<Vec<_>>(),
+        [&MpsseOp::Shift {
+            opcode: 0x30,
+            len: 2,
+            data: vec![0xDE, 0xAD]
+        }]
+    );
+    assert_eq!(disassembly.response_len(), 2);
+    assert_eq!(
+        disassembly.to_string(),
+        "0000: shift(0x30, 2 bytes, [de ad])\n"
+    );
+}
+
+#[test]
+fn long_data() {
+    let cmd = MpsseCmdBuilder::new().clock_data_out(ClockDataOut::MsbNeg, &[0x5A; 1000]);
+    assert_eq!(
+        disassemble(cmd.as_slice()).to_string(),
+        "0000: clock_data_out(MsbNeg, [5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a 5a ... 1000 bytes])\n"
+    );
+}
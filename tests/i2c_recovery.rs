@@ -0,0 +1,130 @@
+use eh1::i2c::{Error as _, ErrorKind, I2c, NoAcknowledgeSource};
+use ftdi_embedded_hal::{self as hal, Chip, Pin};
+use ftdi_mpsse::{ClockBitsIn, ClockBitsOut, MpsseCmdBuilder, MpsseCmdExecutor};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice, Waveform};
+use hal::trace::{Direction, Trace, TracingExecutor};
+
+const SCL: u8 = 1 << 0;
+const SDA: u8 = 1 << 1;
+
+fn i2c_device(target: &I2cRegisters) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    device
+}
+
+/// Interrupt a read from the target after 3 bits of a `0x00` data byte,
+/// leaving the target holding SDA low.
+fn interrupt_read(hal: &mut hal::FtHal<SimDevice>, address: u8) {
+    hal.with_device(|sim| {
+        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new()
+            .set_gpio_lower(SCL | SDA, SCL | SDA)
+            .set_gpio_lower(SCL, SCL | SDA)
+            .set_gpio_lower(0, SCL | SDA)
+            .clock_bits_out(ClockBitsOut::MsbNeg, (address << 1) | 1, 8)
+            .set_gpio_lower(0, SCL)
+            .clock_bits_in(ClockBitsIn::MsbPos, 1)
+            .clock_bits_in(ClockBitsIn::MsbPos, 3)
+            // the host goes away
+            .set_gpio_lower(0, 0)
+            .send_immediate();
+        sim.send(cmd.as_slice()).unwrap();
+        let mut rx: [u8; 2] = [0; 2];
+        sim.recv(&mut rx).unwrap();
+        assert_eq!(rx[0] & 0b1, 0, "target did not acknowledge");
+        assert!(!sim.level(Pin::Lower(1)), "target released SDA");
+    });
+}
+
+#[test]
+fn recover_bus() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    // the stop condition cannot drive SDA high over the target
+    i2c.set_open_drain(true).unwrap();
+
+    interrupt_read(&mut hal, 0x20);
+    assert!(i2c.recover_bus().unwrap());
+    hal.with_device(|sim| assert!(sim.level(Pin::Lower(0)) && sim.level(Pin::Lower(1))));
+
+    i2c.write(0x20_u8, &[0x01, 0xAB]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+
+    // an idle bus only gets a stop condition
+    assert!(i2c.recover_bus().unwrap());
+    i2c.write(0x20_u8, &[0x02, 0xCD]).unwrap();
+    assert_eq!(target.register(2), 0xCD);
+}
+
+#[test]
+fn recover_bus_stuck() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(Waveform::new(Pin::Lower(1), false));
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    assert!(!i2c.recover_bus().unwrap());
+}
+
+/// Returns `true` if the I2C bus was recovered since the last call.
+fn recovered(hal: &mut hal::FtHal<TracingExecutor<SimDevice>>) -> bool {
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    trace.records().iter().any(|r| {
+        r.direction == Direction::Send
+            && disassemble(&r.tx)
+                .ops()
+                .any(|op| matches!(op, MpsseOp::GpioLower))
+    })
+}
+
+#[test]
+fn after_nak() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut hal =
+        hal::FtHal::init_freq(TracingExecutor::new(i2c_device(&target)), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    assert!(i2c.write(0x21_u8, &[0x00]).is_err());
+    assert!(!recovered(&mut hal));
+
+    hal.set_i2c_recovery(true);
+    for fast in [false, true] {
+        i2c.set_fast(fast);
+
+        // the NAK is still reported
+        let err = i2c.write(0x21_u8, &[0x00]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+        );
+        assert!(recovered(&mut hal));
+
+        i2c.write(0x20_u8, &[0x01, 0xAB]).unwrap();
+        assert!(!recovered(&mut hal));
+
+        // the embedded-hal 0.2 traits
+        assert!(eh0::blocking::i2c::Write::write(&mut i2c, 0x21_u8, &[0x00]).is_err());
+        assert!(recovered(&mut hal));
+    }
+}
+
+#[test]
+fn at_creation() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    drop(hal.i2c().unwrap());
+
+    hal.with_device(|sim| {
+        let cmd: MpsseCmdBuilder = MpsseCmdBuilder::new().enable_3phase_data_clocking();
+        sim.send(cmd.as_slice()).unwrap();
+    });
+    interrupt_read(&mut hal, 0x20);
+
+    hal.set_i2c_recovery(true);
+    let _i2c = hal.i2c().unwrap();
+    hal.with_device(|sim| assert!(sim.level(Pin::Lower(0)) && sim.level(Pin::Lower(1))));
+}
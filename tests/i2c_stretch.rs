@@ -0,0 +1,267 @@
+use eh1::i2c::{Error as _, ErrorKind, I2c};
+use ftdi_embedded_hal::{self as hal, Chip, ClockStretching, Pin};
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use hal::sim::{I2cPeripheral, I2cRegisters, Lines, Peripheral, SimDevice, SimError};
+use std::time::Duration;
+
+/// Series resistor from the SCL output on AD0 to the SCL line on AD7.
+///
+/// A target holding SCL low wins over the FTDI output.
+#[derive(Debug, Default)]
+struct SeriesResistor {
+    high: bool,
+}
+
+impl Peripheral for SeriesResistor {
+    fn update(&mut self, _now: Duration, levels: Lines) {
+        self.high = levels.contains(Pin::Lower(0));
+    }
+
+    fn drive_low(&self) -> Lines {
+        if self.high {
+            Lines::NONE
+        } else {
+            Lines::from(Pin::Lower(7))
+        }
+    }
+
+    fn drive_high(&self) -> Lines {
+        if self.high {
+            Lines::from(Pin::Lower(7))
+        } else {
+            Lines::NONE
+        }
+    }
+}
+
+/// Target with SCL behind a series resistor, sensed on AD7.
+fn adaptive_device(target: &I2cRegisters) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(SeriesResistor::default());
+    device.attach(I2cPeripheral::with_pins(
+        target.clone(),
+        Pin::Lower(7),
+        Pin::Lower(1),
+    ));
+    device
+}
+
+/// Target directly on SCL and SDA, with SCL also sensed on AD7.
+fn wired_device(target: &I2cRegisters) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.connect(Pin::Lower(0), Pin::Lower(7));
+    device.attach(I2cPeripheral::new(target.clone()));
+    device
+}
+
+/// Target directly on SCL and SDA.
+fn polling_device(target: &I2cRegisters) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    device
+}
+
+/// Write and read back registers.
+///
+/// The target stretches SCL 10 times.
+fn exercise(
+    i2c: &mut hal::I2c<SimDevice>,
+    target: &I2cRegisters,
+) -> Result<(), hal::Error<SimError>> {
+    i2c.write(0x20_u8, &[0x01, 0xAB, 0xCD])?;
+    assert_eq!(target.register(1), 0xAB);
+    assert_eq!(target.register(2), 0xCD);
+
+    let mut buf: [u8; 3] = [0; 3];
+    i2c.write_read(0x20_u8, &[0x00], &mut buf)?;
+    assert_eq!(buf, [0x00, 0xAB, 0xCD]);
+    Ok(())
+}
+
+#[test]
+fn adaptive() {
+    let stretch: Duration = Duration::from_micros(100);
+    let target = I2cRegisters::new(0x20, 4);
+    target.set_stretch(stretch);
+    let mut hal = hal::FtHal::init_freq(adaptive_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_clock_stretching(ClockStretching::Adaptive).unwrap();
+    hal.with_device(|sim| assert!(sim.is_adaptive()));
+
+    let start: Duration = hal.with_device(|sim| sim.elapsed());
+    exercise(&mut i2c, &target).unwrap();
+    let elapsed: Duration = hal.with_device(|sim| sim.elapsed()) - start;
+    assert!(elapsed > 10 * stretch, "{elapsed:?}");
+
+    // AD7 is in use
+    assert!(hal.adi7().is_err());
+    i2c.set_clock_stretching(ClockStretching::Disabled).unwrap();
+    hal.with_device(|sim| assert!(!sim.is_adaptive()));
+    drop(hal.adi7().unwrap());
+
+    i2c.set_clock_stretching(ClockStretching::Adaptive).unwrap();
+    drop(i2c);
+    hal.with_device(|sim| assert!(!sim.is_adaptive()));
+    drop(hal.adi7().unwrap());
+}
+
+#[test]
+fn adaptive_stuck() {
+    let target = I2cRegisters::new(0x20, 4);
+    let hal = hal::FtHal::init_freq(adaptive_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_clock_stretching(ClockStretching::Adaptive).unwrap();
+
+    // the MPSSE waits for SCL forever
+    target.set_stretch(Duration::MAX);
+    assert!(matches!(
+        i2c.write(0x20_u8, &[0x00]),
+        Err(hal::Error::Backend(SimError::Stalled))
+    ));
+}
+
+#[test]
+fn adaptive_pin_in_use() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let input = hal.adi7().unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    let err = i2c
+        .set_clock_stretching(ClockStretching::Adaptive)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        hal::Error::Hal(hal::ErrorKind::PinInUse {
+            pin: Pin::Lower(7),
+            ..
+        })
+    ));
+
+    drop(input);
+    i2c.set_clock_stretching(ClockStretching::Adaptive).unwrap();
+}
+
+#[test]
+fn adaptive_open_drain() {
+    let stretch: Duration = Duration::from_millis(1);
+    let mut elapsed: Vec<Duration> = Vec::new();
+    for open_drain in [false, true] {
+        let target = I2cRegisters::new(0x20, 4);
+        target.set_stretch(stretch);
+        let mut hal = hal::FtHal::init_freq(wired_device(&target), 400_000).unwrap();
+        hal.set_chip(Chip::FT232H).unwrap();
+        let mut i2c = hal.i2c().unwrap();
+        i2c.set_clock_stretching(ClockStretching::Adaptive).unwrap();
+        i2c.set_open_drain(open_drain).unwrap();
+
+        let start: Duration = hal.with_device(|sim| sim.elapsed());
+        let _ = i2c.write(0x20_u8, &[0x01, 0xAB]);
+        elapsed.push(hal.with_device(|sim| sim.elapsed()) - start);
+    }
+
+    // the push-pull SCL output overrides the target holding SCL low, so the
+    // MPSSE never waits for it
+    assert!(elapsed[0] < stretch, "{:?}", elapsed[0]);
+    // the target stretches SCL before each of the 3 ACKs
+    assert!(elapsed[1] > 3 * stretch, "{:?}", elapsed[1]);
+}
+
+#[test]
+fn adaptive_failed_setup() {
+    let mut hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    // the device stops responding
+    hal.with_device(|sim| *sim = SimDevice::new());
+    assert!(matches!(
+        i2c.set_clock_stretching(ClockStretching::Adaptive),
+        Err(hal::Error::Backend(_))
+    ));
+
+    // AD7 is free, and adaptive clocking is not enabled when the I2C is dropped
+    hal.with_device(|sim| sim.init(&MpsseSettings::default()).unwrap());
+    drop(hal.adi7().unwrap());
+    drop(i2c);
+    hal.with_device(|sim| assert!(!sim.is_adaptive()));
+    drop(hal.adi7().unwrap());
+}
+
+#[test]
+fn disabled() {
+    // without waiting for SCL the target misses clock edges
+    let target = I2cRegisters::new(0x20, 4);
+    target.set_stretch(Duration::from_micros(100));
+    let hal = hal::FtHal::init_freq(adaptive_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    let result = i2c.write(0x20_u8, &[0x01, 0xAB]);
+    assert!(result.is_err() || target.register(1) != 0xAB);
+}
+
+#[test]
+fn polling() {
+    let stretch: Duration = Duration::from_micros(10);
+    let target = I2cRegisters::new(0x20, 4);
+    target.set_stretch(stretch);
+    let mut hal = hal::FtHal::init_freq(polling_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_clock_stretching(ClockStretching::Polling).unwrap();
+    i2c.set_stretch_timeout(Duration::from_secs(10));
+
+    for fast in [false, true] {
+        i2c.set_fast(fast);
+        let start: Duration = hal.with_device(|sim| sim.elapsed());
+        exercise(&mut i2c, &target).unwrap();
+        let elapsed: Duration = hal.with_device(|sim| sim.elapsed()) - start;
+        assert!(elapsed > 10 * stretch, "{elapsed:?}");
+
+        // the embedded-hal 0.2 traits
+        target.set_register(3, 0x00);
+        eh0::blocking::i2c::Write::write(&mut i2c, 0x20_u8, &[0x03, 0x5A]).unwrap();
+        assert_eq!(target.register(3), 0x5A);
+        let mut buf: [u8; 2] = [0; 2];
+        eh0::blocking::i2c::WriteRead::write_read(&mut i2c, 0x20_u8, &[0x02], &mut buf).unwrap();
+        assert_eq!(buf, [0xCD, 0x5A]);
+    }
+
+    // NAKs are still reported
+    target.set_present(false);
+    let err = i2c.write(0x20_u8, &[0x00]).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::NoAcknowledge(_)));
+}
+
+#[test]
+fn polling_ten_bit() {
+    let target = I2cRegisters::new_ten_bit(0x2A5, 4);
+    target.set_stretch(Duration::from_micros(10));
+    let hal = hal::FtHal::init_freq(polling_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_clock_stretching(ClockStretching::Polling).unwrap();
+    i2c.set_stretch_timeout(Duration::from_secs(10));
+
+    i2c.write(0x2A5_u16, &[0x01, 0xAB]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+    let mut buf: [u8; 2] = [0; 2];
+    i2c.write_read(0x2A5_u16, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB]);
+    i2c.read(0x2A5_u16, &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0x00]);
+}
+
+#[test]
+fn polling_timeout() {
+    let target = I2cRegisters::new(0x20, 4);
+    let hal = hal::FtHal::init_freq(polling_device(&target), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_clock_stretching(ClockStretching::Polling).unwrap();
+    i2c.set_stretch_timeout(Duration::from_millis(1));
+
+    target.set_stretch(Duration::MAX);
+    let err = i2c.write(0x20_u8, &[0x00]).unwrap_err();
+    assert!(matches!(
+        err,
+        hal::Error::Hal(hal::ErrorKind::I2cStretchTimeout)
+    ));
+    assert_eq!(err.kind(), ErrorKind::Bus);
+}
@@ -0,0 +1,219 @@
+use eh1::i2c::{Error as _, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+use ftdi_embedded_hal::{self as hal, Pin};
+use hal::sim::{I2cPeripheral, I2cRegisters, I2cTarget, SimDevice};
+use hal::trace::{Direction, Trace, TracingExecutor};
+use std::time::Duration;
+
+fn traced_hal(target: &I2cRegisters) -> hal::FtHal<TracingExecutor<SimDevice>> {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    hal::FtHal::init_freq(TracingExecutor::new(device), 400_000).unwrap()
+}
+
+/// Number of sends and receives.
+fn round_trips(trace: &Trace) -> (usize, usize) {
+    let count = |direction: Direction| {
+        trace
+            .records()
+            .iter()
+            .filter(|r| r.direction == direction)
+            .count()
+    };
+    (count(Direction::Send), count(Direction::Recv))
+}
+
+#[test]
+fn batched() {
+    let target = I2cRegisters::new(0x50, 64);
+    let mut hal = traced_hal(&target);
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_fast(true);
+    hal.with_device(|d| d.take_trace());
+
+    // a 32 byte page write
+    let page: Vec<u8> = (0..32).collect();
+    i2c.transaction(
+        0x50_u8,
+        &mut [Operation::Write(&[0x10]), Operation::Write(&page)],
+    )
+    .unwrap();
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (1, 1));
+    assert_eq!(target.register(0x10), 0);
+    assert_eq!(target.register(0x2F), 31);
+
+    // repeated starts
+    let mut first: [u8; 2] = [0; 2];
+    let mut second: [u8; 3] = [0; 3];
+    i2c.transaction(
+        0x50_u8,
+        &mut [
+            Operation::Write(&[0x11]),
+            Operation::Read(&mut first),
+            Operation::Write(&[0x20, 0xAA]),
+            Operation::Read(&mut second),
+        ],
+    )
+    .unwrap();
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (1, 1));
+    assert_eq!(first, [1, 2]);
+    assert_eq!(target.register(0x20), 0xAA);
+    assert_eq!(second, [17, 18, 19]);
+
+    // the default methods are transactions
+    let mut buf: [u8; 4] = [0; 4];
+    i2c.write_read(0x50_u8, &[0x2C], &mut buf).unwrap();
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (1, 1));
+    assert_eq!(buf, [28, 29, 30, 31]);
+}
+
+#[test]
+fn unbatched() {
+    let target = I2cRegisters::new(0x50, 64);
+    let mut hal = traced_hal(&target);
+    let mut i2c = hal.i2c().unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    let page: Vec<u8> = (0..32).collect();
+    i2c.transaction(
+        0x50_u8,
+        &mut [Operation::Write(&[0x10]), Operation::Write(&page)],
+    )
+    .unwrap();
+    let (_, recvs) = round_trips(&hal.with_device(|d| d.take_trace()));
+    assert_eq!(recvs, 34);
+    assert_eq!(target.register(0x2F), 31);
+}
+
+#[test]
+fn no_ack_source() {
+    for fast in [false, true] {
+        let target = I2cRegisters::new(0x50, 4);
+        let hal = traced_hal(&target);
+        let mut i2c = hal.i2c().unwrap();
+        i2c.set_fast(fast);
+
+        let err = i2c.write(0x51_u8, &[0x00]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            "fast: {fast}"
+        );
+
+        // the register pointer runs past the last register
+        let err = i2c.write(0x50_u8, &[0x02, 0x11, 0x22, 0x33]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+            "fast: {fast}"
+        );
+        assert_eq!(target.register(3), 0x22);
+
+        // the read address after a repeated start
+        target.set_present(false);
+        let mut buf: [u8; 1] = [0; 1];
+        let err = i2c.write_read(0x50_u8, &[0x00], &mut buf).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            "fast: {fast}"
+        );
+
+        // the bus is usable afterwards
+        target.set_present(true);
+        i2c.write_read(0x50_u8, &[0x03], &mut buf).unwrap();
+        assert_eq!(buf, [0x22]);
+    }
+}
+
+/// Target that acknowledges writes but not reads.
+struct WriteOnly;
+
+impl I2cTarget for WriteOnly {
+    fn start(&mut self, _now: Duration, address: u8, read: bool) -> bool {
+        address == 0x50 && !read
+    }
+
+    fn write(&mut self, _byte: u8) -> bool {
+        true
+    }
+
+    fn read(&mut self) -> u8 {
+        0xFF
+    }
+}
+
+#[test]
+fn read_address_no_ack() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(WriteOnly));
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    // the write is acknowledged, the read address after the repeated start is not
+    for fast in [false, true] {
+        i2c.set_fast(fast);
+        let mut buf: [u8; 1] = [0; 1];
+        let err = i2c.write_read(0x50_u8, &[0x00], &mut buf).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            "fast: {fast}"
+        );
+    }
+
+    i2c.set_fast(false);
+    let mut buf: [u8; 1] = [0; 1];
+    let err: hal::Error<hal::sim::SimError> =
+        eh0::blocking::i2c::WriteRead::write_read(&mut i2c, 0x50_u8, &[0x00], &mut buf)
+            .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+}
+
+#[test]
+fn ten_bit() {
+    let target = I2cRegisters::new_ten_bit(0x2A5, 4);
+    let mut hal = traced_hal(&target);
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_fast(true);
+    hal.with_device(|d| d.take_trace());
+
+    let mut first: [u8; 1] = [0; 1];
+    let mut second: [u8; 1] = [0; 1];
+    i2c.transaction(
+        0x2A5_u16,
+        &mut [
+            Operation::Write(&[0x01, 0xAB]),
+            Operation::Read(&mut first),
+            Operation::Write(&[0x01]),
+            Operation::Read(&mut second),
+        ],
+    )
+    .unwrap();
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (1, 1));
+    assert_eq!(first, [0x00]);
+    assert_eq!(second, [0xAB]);
+
+    let err = i2c.write(0x1A5_u16, &[0x00]).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+    // the upper 6 bits of a 10-bit address are not sent
+    hal.with_device(|d| d.take_trace());
+    let err = i2c.write(0x6A5_u16, &[0x00]).unwrap_err();
+    assert!(matches!(
+        err,
+        hal::Error::Hal(hal::ErrorKind::I2cAddress(0x6A5))
+    ));
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (0, 0));
+    let mut buf: [u8; 1] = [0; 1];
+    assert!(
+        eh0::blocking::i2c::WriteRead::write_read(&mut i2c, 0xFFFF_u16, &[0x01], &mut buf).is_err()
+    );
+    assert_eq!(round_trips(&hal.with_device(|d| d.take_trace())), (0, 0));
+}
@@ -0,0 +1,51 @@
+//! Simulated version of `examples/lm75.rs`.
+
+use ftdi_embedded_hal::{self as hal, Pin};
+use hal::sim::{I2cPeripheral, Lm75, SimDevice};
+use lm75::Address;
+
+#[test]
+fn lm75() {
+    let model = Lm75::new(0x48);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model.clone()));
+
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut sensor = lm75::Lm75::new(i2c, Address::default());
+
+    for temperature in [25.0, 0.5, -0.5, -55.0, 125.0] {
+        model.set_temperature(temperature);
+        assert_eq!(sensor.read_temperature().unwrap(), temperature);
+    }
+}
+
+#[test]
+fn configuration() {
+    let model = Lm75::new(0x48);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model.clone()));
+
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut sensor = lm75::Lm75::new(i2c, Address::default());
+
+    sensor.disable().unwrap();
+    assert_eq!(model.configuration() & 0x01, 0x01);
+    sensor.enable().unwrap();
+    assert_eq!(model.configuration() & 0x01, 0x00);
+}
+
+#[test]
+fn wrong_address() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(Lm75::new(0x49)));
+
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let i2c = hal.i2c().unwrap();
+    let mut sensor = lm75::Lm75::new(i2c, Address::default());
+    assert!(sensor.read_temperature().is_err());
+}
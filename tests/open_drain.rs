@@ -0,0 +1,236 @@
+use eh1::digital::OutputPin;
+use eh1::i2c::{Error as _, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+use ftdi_embedded_hal::{self as hal, Capability, Chip, ClockStretching, Pin};
+use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice, Waveform};
+use hal::trace::{Trace, TracingExecutor};
+use std::time::Duration;
+
+fn hal_kind<T: std::fmt::Debug, E: std::error::Error>(
+    result: Result<T, hal::Error<E>>,
+) -> hal::ErrorKind {
+    match result.unwrap_err() {
+        hal::Error::Hal(kind) => kind,
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+fn i2c_device(target: &I2cRegisters) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    device
+}
+
+#[test]
+fn i2c() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_open_drain(true).unwrap();
+    hal.with_device(|sim| assert_eq!(sim.lower_drive_zero(), 0b011));
+
+    i2c.write(0x20_u8, &[0x01, 0xAB, 0xCD]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+    assert_eq!(target.register(2), 0xCD);
+
+    let mut buf: [u8; 3] = [0; 3];
+    i2c.write_read(0x20_u8, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB, 0xCD]);
+
+    let mut first: [u8; 2] = [0; 2];
+    let mut second: [u8; 1] = [0; 1];
+    i2c.transaction(
+        0x20_u8,
+        &mut [
+            Operation::Write(&[0x01]),
+            Operation::Read(&mut first),
+            Operation::Write(&[0x02, 0x5A]),
+            Operation::Read(&mut second),
+        ],
+    )
+    .unwrap();
+    assert_eq!(first, [0xAB, 0xCD]);
+    assert_eq!(target.register(2), 0x5A);
+    assert_eq!(second, [0x00]);
+
+    // the embedded-hal 0.2 traits
+    let mut buf: [u8; 2] = [0; 2];
+    eh0::blocking::i2c::WriteRead::write_read(&mut i2c, 0x20_u8, &[0x01], &mut buf).unwrap();
+    assert_eq!(buf, [0xAB, 0x5A]);
+
+    // SCL and SDA are released again when dropped
+    drop(i2c);
+    hal.with_device(|sim| assert_eq!(sim.lower_drive_zero(), 0));
+}
+
+#[test]
+fn i2c_ten_bit() {
+    let target = I2cRegisters::new_ten_bit(0x2A5, 4);
+    let hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_open_drain(true).unwrap();
+
+    i2c.write(0x2A5_u16, &[0x01, 0xAB]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+    let mut buf: [u8; 2] = [0; 2];
+    i2c.write_read(0x2A5_u16, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB]);
+}
+
+#[test]
+fn i2c_no_ack() {
+    let target = I2cRegisters::new(0x20, 2);
+    let hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_open_drain(true).unwrap();
+
+    let err = i2c.write(0x21_u8, &[0x00]).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+
+    // the register pointer runs past the last register
+    let err = i2c.write(0x20_u8, &[0x01, 0x11, 0x22]).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)
+    );
+    assert_eq!(target.register(1), 0x11);
+
+    // the bus is usable afterwards
+    i2c.write(0x20_u8, &[0x00, 0x33]).unwrap();
+    assert_eq!(target.register(0), 0x33);
+}
+
+#[test]
+fn i2c_shorter() {
+    let target = I2cRegisters::new(0x20, 16);
+    let mut hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_fast(true);
+    let bytes: [u8; 16] = [0; 16];
+
+    let mut elapsed: Vec<Duration> = Vec::new();
+    for open_drain in [false, true] {
+        i2c.set_open_drain(open_drain).unwrap();
+        let start: Duration = hal.with_device(|sim| sim.elapsed());
+        i2c.write(0x20_u8, &bytes).unwrap();
+        elapsed.push(hal.with_device(|sim| sim.elapsed()) - start);
+    }
+    let (emulated, open_drain): (Duration, Duration) = (elapsed[0], elapsed[1]);
+    assert!(open_drain < emulated, "{open_drain:?} >= {emulated:?}");
+}
+
+#[test]
+fn i2c_polling() {
+    let target = I2cRegisters::new(0x20, 4);
+    target.set_stretch(Duration::from_micros(10));
+    let hal = hal::FtHal::init_freq(i2c_device(&target), 400_000).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    i2c.set_open_drain(true).unwrap();
+    i2c.set_clock_stretching(ClockStretching::Polling).unwrap();
+    i2c.set_stretch_timeout(Duration::from_secs(10));
+
+    i2c.write(0x20_u8, &[0x01, 0xAB]).unwrap();
+    let mut buf: [u8; 2] = [0; 2];
+    i2c.write_read(0x20_u8, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB]);
+}
+
+#[test]
+fn i2c_unsupported() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    hal.set_chip(Chip::FT2232H).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    assert_eq!(
+        hal_kind(i2c.set_open_drain(true)),
+        hal::ErrorKind::Unsupported {
+            chip: Chip::FT2232H,
+            capability: Capability::OpenDrain,
+        }
+    );
+    i2c.set_open_drain(false).unwrap();
+}
+
+#[test]
+fn unknown_chip() {
+    let mut hal = hal::FtHal::init_default(TracingExecutor::new(SimDevice::new())).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    // drive only zero is an FT232H command
+    assert_eq!(
+        hal_kind(i2c.set_open_drain(true)),
+        hal::ErrorKind::UnknownChip(Capability::OpenDrain)
+    );
+    assert_eq!(
+        hal_kind(hal.open_drain_pin(Pin::Upper(3))),
+        hal::ErrorKind::UnknownChip(Capability::OpenDrain)
+    );
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert!(trace.records().is_empty());
+    hal.with_device(|d| assert_eq!(d.device().lower_drive_zero(), 0));
+
+    // the pin was not allocated
+    hal.c3().unwrap();
+}
+
+#[test]
+fn output_pin() {
+    let mut device = SimDevice::new();
+    // another device holds the line low for 1 ms
+    device.attach(Waveform::new(Pin::Upper(3), false).at(Duration::from_millis(1), true));
+    let mut hal = hal::FtHal::init_default(device).unwrap();
+    hal.set_chip(Chip::FT232H).unwrap();
+
+    let mut pin = hal.open_drain_pin(Pin::Upper(3)).unwrap();
+    hal.with_device(|sim| assert_eq!(sim.upper_drive_zero(), 1 << 3));
+    pin.set_high().unwrap();
+    hal.with_device(|sim| assert!(!sim.level(Pin::Upper(3))));
+
+    hal.with_device(|sim| sim.advance(Duration::from_millis(2)));
+    hal.with_device(|sim| assert!(sim.level(Pin::Upper(3))));
+    pin.set_low().unwrap();
+    hal.with_device(|sim| assert!(!sim.level(Pin::Upper(3))));
+
+    // open-drain outputs are only supported by the FT232H
+    assert_eq!(
+        hal_kind(hal.set_chip(Chip::FT2232H)),
+        hal::ErrorKind::Unsupported {
+            chip: Chip::FT2232H,
+            capability: Capability::OpenDrain,
+        }
+    );
+
+    drop(pin);
+    hal.with_device(|sim| assert_eq!(sim.upper_drive_zero(), 0));
+    hal.set_chip(Chip::FT2232H).unwrap();
+    assert_eq!(
+        hal_kind(hal.open_drain_pin(Pin::Upper(3))),
+        hal::ErrorKind::Unsupported {
+            chip: Chip::FT2232H,
+            capability: Capability::OpenDrain,
+        }
+    );
+    // the pin was not allocated
+    hal.c3().unwrap();
+}
+
+#[test]
+fn output_pin_push_pull() {
+    let mut device = SimDevice::new();
+    device.attach(Waveform::new(Pin::Upper(3), false));
+    let mut hal = hal::FtHal::init_default(device).unwrap();
+
+    // a push-pull output overrides the other device
+    let mut pin = hal.c3().unwrap();
+    pin.set_high().unwrap();
+    hal.with_device(|sim| assert!(sim.level(Pin::Upper(3))));
+    hal.with_device(|sim| assert_eq!(sim.upper_drive_zero(), 0));
+}
@@ -0,0 +1,148 @@
+use ftdi_embedded_hal::{self as hal, ErrorKind, Pin, PinUse};
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use hal::sim::{SimDevice, SimError};
+
+fn hal_kind<T: std::fmt::Debug>(result: Result<T, hal::Error<SimError>>) -> ErrorKind {
+    match result.unwrap_err() {
+        hal::Error::Hal(kind) => kind,
+        err => panic!("unexpected error {err:?}"),
+    }
+}
+
+#[test]
+fn in_use() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let _i2c = hal.i2c().unwrap();
+
+    assert_eq!(
+        hal_kind(hal.spi()),
+        ErrorKind::PinInUse {
+            pin: Pin::Lower(0),
+            requested: PinUse::Spi,
+            current: PinUse::I2c,
+        }
+    );
+    assert_eq!(
+        hal_kind(hal.adi2()),
+        ErrorKind::PinInUse {
+            pin: Pin::Lower(2),
+            requested: PinUse::Input,
+            current: PinUse::I2c,
+        }
+    );
+
+    let _output = hal.c3().unwrap();
+    let err = hal.c3().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "A regular error occurred \"Unable to allocate pin AC3 for OUTPUT, pin is already allocated for OUTPUT\""
+    );
+    assert_eq!(
+        hal_kind(hal.ci3()),
+        ErrorKind::PinInUse {
+            pin: Pin::Upper(3),
+            requested: PinUse::Input,
+            current: PinUse::Output,
+        }
+    );
+
+    // the HAL is still usable
+    hal.ad3().unwrap();
+}
+
+#[test]
+fn spi_device_cs() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+
+    assert_eq!(
+        hal_kind(hal.spi_device(2)),
+        ErrorKind::PinInUse {
+            pin: Pin::Lower(2),
+            requested: PinUse::Output,
+            current: PinUse::Spi,
+        }
+    );
+    assert_eq!(
+        hal_kind(hal.spi_device(8)),
+        ErrorKind::PinOutOfRange(Pin::Lower(8))
+    );
+
+    // failed allocations do not claim any pins
+    let _sck = hal.ad0().unwrap();
+    let _cs = hal.ad3().unwrap();
+}
+
+#[test]
+fn release() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let mut hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+
+    let i2c = hal.i2c().unwrap();
+    assert!(hal.with_device(|sim| sim.is_3phase()));
+    drop(i2c);
+    assert!(!hal.with_device(|sim| sim.is_3phase()));
+
+    let mut spi = hal.spi().unwrap();
+    let mut buf: [u8; 2] = [0; 2];
+    eh1::spi::SpiBus::transfer(&mut spi, &mut buf, &[0x12, 0x34]).unwrap();
+    assert_eq!(buf, [0x12, 0x34]);
+    drop(spi);
+
+    let spi = hal.spi_device(3).unwrap();
+    drop(spi);
+    // pins keep their configuration by default
+    assert_eq!(hal.with_device(|sim| sim.lower_direction()), 0x0B);
+
+    let output = hal.c5().unwrap();
+    drop(output);
+    let _input = hal.ci5().unwrap();
+    let _i2c = hal.i2c().unwrap();
+}
+
+#[test]
+fn failed_setup() {
+    let mut hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let _spi_device = hal.spi_device(3).unwrap();
+
+    // the device stops responding
+    hal.with_device(|sim| *sim = SimDevice::new());
+    let failed = |err: hal::Error<SimError>| matches!(err, hal::Error::Backend(_));
+    assert!(failed(hal.spi_device(4).unwrap_err()));
+    assert!(failed(hal.c5().unwrap_err()));
+    assert!(failed(hal.ci6().unwrap_err()));
+    drop(_spi_device);
+    assert!(failed(hal.spi().unwrap_err()));
+    assert!(failed(hal.i2c().unwrap_err()));
+    assert!(failed(hal.spi_device(3).unwrap_err()));
+
+    // the pins of the failed peripherals are free
+    hal.with_device(|sim| sim.init(&MpsseSettings::default()).unwrap());
+    drop(hal.i2c().unwrap());
+    drop(hal.spi().unwrap());
+    let _spi_device = hal.spi_device(3).unwrap();
+    let _spi_device = hal.spi_device(4).unwrap();
+    let _output = hal.c5().unwrap();
+    let _input = hal.ci6().unwrap();
+}
+
+#[test]
+fn tristate_on_release() {
+    let mut hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    hal.set_tristate_on_release(true);
+
+    let spi = hal.spi_device(3).unwrap();
+    let output = hal.c5().unwrap();
+    let _output = hal.c6().unwrap();
+    hal.with_device(|sim| {
+        assert_eq!(sim.lower_direction(), 0x0B);
+        assert_eq!(sim.upper_direction(), 0x60);
+    });
+
+    drop(spi);
+    drop(output);
+    hal.with_device(|sim| {
+        assert_eq!(sim.lower_direction(), 0x00);
+        assert_eq!(sim.upper_direction(), 0x40);
+    });
+}
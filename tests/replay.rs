@@ -0,0 +1,209 @@
+use eh1::i2c::I2c;
+use eh1::spi::{Operation, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Pin};
+use ftdi_mpsse::MpsseCmdExecutor;
+use hal::sim::{I2cPeripheral, Lm75, SimDevice, SpiFlash, SpiPeripheral};
+use hal::trace::{Direction, Origin, Record, ReplayError, ReplayExecutor, Trace, TracingExecutor};
+use lm75::Address;
+use std::time::Duration;
+
+/// Record reading the temperature of an LM75.
+fn record_lm75() -> Trace {
+    let model = Lm75::new(0x48);
+    model.set_temperature(-12.5);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(model));
+
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 400_000).unwrap();
+    let mut sensor = lm75::Lm75::new(hal.i2c().unwrap(), Address::default());
+    assert_eq!(sensor.read_temperature().unwrap(), -12.5);
+    drop(sensor);
+
+    // the file format must not lose anything replay depends on
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    Trace::read(trace.to_string().as_bytes()).unwrap()
+}
+
+#[test]
+fn lm75() {
+    let trace: Trace = record_lm75();
+
+    let mut hal = hal::FtHal::init_freq(ReplayExecutor::new(&trace), 400_000).unwrap();
+    let mut sensor = lm75::Lm75::new(hal.i2c().unwrap(), Address::default());
+    assert_eq!(sensor.read_temperature().unwrap(), -12.5);
+    drop(sensor);
+    hal.with_device(|d| d.finish()).unwrap();
+}
+
+#[test]
+fn mismatch() {
+    let trace: Trace = record_lm75();
+
+    let hal = hal::FtHal::init_freq(ReplayExecutor::new(&trace), 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+    let mut buf: [u8; 2] = [0; 2];
+    let err = match i2c.write_read(0x48_u8, &[0x03], &mut buf).unwrap_err() {
+        hal::Error::Backend(err) => err,
+        err => panic!("unexpected error {err:?}"),
+    };
+    let ReplayError::Mismatch {
+        record,
+        origin,
+        offset,
+        ref expected,
+        ref actual,
+    } = err
+    else {
+        panic!("unexpected error {err:?}");
+    };
+    assert_eq!(origin, Origin::I2c);
+    assert_eq!(trace.records()[record].tx, *expected);
+    assert_eq!(expected[..offset], actual[..offset]);
+    assert_eq!(expected[offset], 0x00);
+    assert_eq!(actual[offset], 0x03);
+
+    let message: String = err.to_string();
+    let mut lines = message.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        format!(
+            "Replay diverged from record {record} (i2c) at byte {offset}, expected 0x00 found 0x03"
+        )
+    );
+    let diff: Vec<&str> = lines.collect();
+    let expected_line: &str = diff.iter().find(|l| l.starts_with("- ")).unwrap();
+    let actual_line: &str = diff.iter().find(|l| l.starts_with("+ ")).unwrap();
+    assert!(expected_line.contains("clock_bits_out(MsbNeg, 0x00, 8)"));
+    assert!(actual_line.contains("clock_bits_out(MsbNeg, 0x03, 8)"));
+}
+
+#[test]
+fn spi_flash() {
+    let flash = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(Pin::Lower(3), eh1::spi::MODE_0, flash));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 3_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    let mut id: [u8; 3] = [0; 3];
+    spi.transaction(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut id)])
+        .unwrap();
+    assert_eq!(id, [0xEF, 0x40, 0x14]);
+    drop(spi);
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+
+    let mut hal = hal::FtHal::init_freq(ReplayExecutor::new(&trace), 3_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    let mut replayed: [u8; 3] = [0; 3];
+    spi.transaction(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut replayed)])
+        .unwrap();
+    assert_eq!(replayed, id);
+    hal.with_device(|d| d.finish()).unwrap();
+
+    // an extra transaction
+    assert!(matches!(
+        spi.write(&[0x05_u8]),
+        Err(hal::Error::Backend(ReplayError::UnexpectedSend {
+            record: None,
+            ..
+        }))
+    ));
+}
+
+fn record(direction: Direction, tx: &[u8], rx: &[u8]) -> Record {
+    Record {
+        timestamp: Duration::ZERO,
+        duration: Duration::ZERO,
+        origin: Origin::Unknown,
+        direction,
+        tx: tx.to_vec(),
+        rx: rx.to_vec(),
+        error: None,
+    }
+}
+
+#[test]
+fn stream() {
+    let mut trace = Trace::new();
+    trace.push(record(Direction::Xfer, &[0x81, 0x83, 0x87], &[0x12, 0x34]));
+    trace.push(record(Direction::Send, &[0x80, 0x00, 0x01], &[]));
+
+    // calls are split differently than when recorded
+    let mut replay = ReplayExecutor::new(&trace);
+    replay.send(&[0x81]).unwrap();
+    replay.send(&[0x83]).unwrap();
+    let mut buf: [u8; 1] = [0];
+    replay.xfer(&[0x87], &mut buf).unwrap();
+    assert_eq!(buf, [0x12]);
+    assert_eq!(replay.finish(), Err(ReplayError::Unfinished { record: 0 }));
+    replay.recv(&mut buf).unwrap();
+    assert_eq!(buf, [0x34]);
+    replay.send(&[0x80, 0x00, 0x01]).unwrap();
+    replay.finish().unwrap();
+
+    // reading before the recorded commands are sent
+    let mut replay = ReplayExecutor::new(&trace);
+    replay.send(&[0x81]).unwrap();
+    let err = replay.recv(&mut buf).unwrap_err();
+    assert_eq!(
+        err,
+        ReplayError::UnexpectedRecv {
+            record: Some(0),
+            expected: vec![0x83, 0x87],
+            len: 1,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Replay diverged from record 0, expected commands but 1 bytes were read\n\
+         - 0000: gpio_upper()\n\
+         - 0001: send_immediate()"
+    );
+
+    // sending before the recorded response is read
+    let mut replay = ReplayExecutor::new(&trace);
+    replay.send(&[0x81, 0x83, 0x87]).unwrap();
+    let err = replay.send(&[0x80, 0x00, 0x01]).unwrap_err();
+    assert_eq!(
+        err,
+        ReplayError::UnexpectedSend {
+            record: Some(0),
+            data: vec![0x80, 0x00, 0x01],
+        }
+    );
+
+    // reading past the end
+    let mut replay = ReplayExecutor::new(&Trace::new());
+    assert_eq!(
+        replay.recv(&mut buf),
+        Err(ReplayError::UnexpectedRecv {
+            record: None,
+            expected: Vec::new(),
+            len: 1,
+        })
+    );
+}
+
+#[test]
+fn recorded_error() {
+    let mut trace = Trace::new();
+    trace.push(record(Direction::Send, &[0x81, 0x87], &[]));
+    trace.push(Record {
+        error: Some(String::from("read timeout")),
+        ..record(Direction::Recv, &[], &[0x00])
+    });
+
+    let mut replay = ReplayExecutor::new(&trace);
+    replay.send(&[0x81, 0x87]).unwrap();
+    let mut buf: [u8; 1] = [0];
+    let err = replay.recv(&mut buf).unwrap_err();
+    assert_eq!(
+        err,
+        ReplayError::Recorded {
+            record: 1,
+            message: String::from("read timeout"),
+        }
+    );
+    assert_eq!(err.to_string(), "Recorded transfer 1 failed: read timeout");
+    replay.finish().unwrap();
+}
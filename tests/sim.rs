@@ -0,0 +1,343 @@
+use eh1::digital::{InputPin, OutputPin};
+use eh1::i2c::{ErrorKind, I2c, NoAcknowledgeSource};
+use eh1::spi::{Operation, SpiBus, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Pin};
+use ftdi_mpsse::{MpsseCmdBuilder, MpsseCmdExecutor, MpsseSettings};
+use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice, SpiFlash, SpiPeripheral};
+
+#[test]
+fn gpio() {
+    let mut hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let mut output = hal.ad6().unwrap();
+    let mut input = hal.adi7().unwrap();
+
+    output.set_low().unwrap();
+    hal.with_device(|sim| {
+        assert!(!sim.level(Pin::Lower(6)));
+        assert_eq!(sim.lower_direction() & 0xC0, 0x40);
+    });
+    output.set_high().unwrap();
+    hal.with_device(|sim| assert!(sim.level(Pin::Lower(6))));
+
+    // undriven inputs are pulled high
+    assert!(input.is_high().unwrap());
+    hal.with_device(|sim| sim.set_level(Pin::Lower(7), false));
+    assert!(input.is_low().unwrap());
+}
+
+#[test]
+fn gpio_connected() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Upper(0), Pin::Upper(1));
+    let hal = hal::FtHal::init_default(device).unwrap();
+    let mut output = hal.c0().unwrap();
+    let mut input = hal.ci1().unwrap();
+
+    output.set_low().unwrap();
+    assert!(input.is_low().unwrap());
+    output.set_high().unwrap();
+    assert!(input.is_high().unwrap());
+}
+
+#[test]
+fn spi_loopback() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    let write: [u8; 5] = [0x00, 0x01, 0x5A, 0xA5, 0xFF];
+    let mut read: [u8; 5] = [0; 5];
+    spi.transfer(&mut read, &write).unwrap();
+    assert_eq!(read, write);
+
+    let mut buf: [u8; 3] = [0x12, 0x34, 0x56];
+    spi.transfer_in_place(&mut buf).unwrap();
+    assert_eq!(buf, [0x12, 0x34, 0x56]);
+}
+
+#[test]
+fn spi_unconnected() {
+    let hal = hal::FtHal::init_freq(SimDevice::new(), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    let mut read: [u8; 2] = [0; 2];
+    spi.read(&mut read).unwrap();
+    assert_eq!(read, [0xFF, 0xFF]);
+}
+
+#[test]
+fn i2c_no_ack() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    // nothing on the bus pulls SDA low
+    let err = i2c.write(0x48_u8, &[0x00]).unwrap_err();
+    assert_eq!(
+        eh1::i2c::Error::kind(&err),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+}
+
+#[test]
+fn clock_frequency() {
+    let mut hal = hal::FtHal::init_freq(SimDevice::new(), 1_000_000).unwrap();
+    hal.with_device(|sim| assert_eq!(sim.clock_frequency(), 1_000_000));
+
+    let mut hal = hal::FtHal::init_freq(SimDevice::new(), 10_000_000).unwrap();
+    hal.with_device(|sim| assert_eq!(sim.clock_frequency(), 10_000_000));
+}
+
+#[test]
+fn elapsed() {
+    let mut device = SimDevice::new();
+    device
+        .init(&MpsseSettings {
+            clock_frequency: Some(1_000_000),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let start = device.elapsed();
+    let cmd = MpsseCmdBuilder::new().clock_data_out(ftdi_mpsse::ClockDataOut::MsbNeg, &[0; 125]);
+    device.send(cmd.as_slice()).unwrap();
+    assert_eq!(
+        device.elapsed() - start,
+        std::time::Duration::from_millis(1)
+    );
+}
+
+#[test]
+fn invalid_command() {
+    let mut device = SimDevice::new();
+    device.init(&MpsseSettings::default()).unwrap();
+    device.send(&[0xAA]).unwrap();
+    let mut buf: [u8; 2] = [0; 2];
+    device.recv(&mut buf).unwrap();
+    assert_eq!(buf, [0xFA, 0xAA]);
+}
+
+#[test]
+fn split_command() {
+    let mut device = SimDevice::new();
+    device.init(&MpsseSettings::default()).unwrap();
+    device.send(&[0x80, 0x0F]).unwrap();
+    assert_eq!(device.lower_direction(), 0x00);
+    device.send(&[0xF0]).unwrap();
+    assert_eq!(device.lower_value(), 0x0F);
+    assert_eq!(device.lower_direction(), 0xF0);
+}
+
+#[test]
+fn i2c_registers() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    i2c.write(0x20_u8, &[0x01, 0xAB, 0xCD]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+    assert_eq!(target.register(2), 0xCD);
+
+    let mut buf: [u8; 3] = [0; 3];
+    i2c.write_read(0x20_u8, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB, 0xCD]);
+
+    // writes past the end of the register map are not acknowledged
+    let err = i2c.write(0x20_u8, &[0x03, 0x01, 0x02]).unwrap_err();
+    assert_eq!(
+        eh1::i2c::Error::kind(&err),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)
+    );
+    assert_eq!(target.register(3), 0x01);
+
+    target.set_present(false);
+    let err = i2c.read(0x20_u8, &mut buf).unwrap_err();
+    assert_eq!(
+        eh1::i2c::Error::kind(&err),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+}
+
+#[test]
+fn i2c_ten_bit() {
+    use eh0::blocking::i2c as i2c0;
+
+    let target = I2cRegisters::new_ten_bit(0x2A5, 4);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target.clone()));
+    let hal = hal::FtHal::init_freq(device, 400_000).unwrap();
+    let mut i2c = hal.i2c().unwrap();
+
+    // embedded-hal 1 transaction
+    I2c::write(&mut i2c, 0x2A5_u16, &[0x01, 0xAB, 0xCD]).unwrap();
+    assert_eq!(target.register(1), 0xAB);
+    assert_eq!(target.register(2), 0xCD);
+
+    let mut buf: [u8; 3] = [0; 3];
+    I2c::write_read(&mut i2c, 0x2A5_u16, &[0x00], &mut buf).unwrap();
+    assert_eq!(buf, [0x00, 0xAB, 0xCD]);
+
+    // reads continue from the register pointer
+    target.set_register(3, 0x77);
+    let mut buf: [u8; 1] = [0; 1];
+    I2c::read(&mut i2c, 0x2A5_u16, &mut buf).unwrap();
+    assert_eq!(buf, [0x77]);
+
+    // a write after a read sends the full address again
+    let mut first: [u8; 1] = [0; 1];
+    let mut second: [u8; 1] = [0; 1];
+    i2c.transaction(
+        0x2A5_u16,
+        &mut [
+            eh1::i2c::Operation::Read(&mut first),
+            eh1::i2c::Operation::Write(&[0x00, 0x11]),
+            eh1::i2c::Operation::Read(&mut second),
+        ],
+    )
+    .unwrap();
+    assert_eq!(target.register(0), 0x11);
+    assert_eq!(first, [0xFF]);
+    assert_eq!(second, [0xAB]);
+
+    // the header matches, the low address byte does not
+    let err = I2c::write(&mut i2c, 0x2A4_u16, &[0x00]).unwrap_err();
+    assert_eq!(
+        eh1::i2c::Error::kind(&err),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+    // the header does not match
+    let err = I2c::write(&mut i2c, 0x0A5_u16, &[0x00]).unwrap_err();
+    assert_eq!(
+        eh1::i2c::Error::kind(&err),
+        ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+    );
+
+    for fast in [false, true] {
+        i2c.set_fast(fast);
+        target.set_register(3, 0x00);
+
+        i2c0::Write::write(&mut i2c, 0x2A5_u16, &[0x03, 0x5A]).unwrap();
+        assert_eq!(target.register(3), 0x5A);
+
+        let mut buf: [u8; 1] = [0; 1];
+        i2c0::WriteRead::write_read(&mut i2c, 0x2A5_u16, &[0x02], &mut buf).unwrap();
+        assert_eq!(buf, [0xCD]);
+
+        i2c0::Read::read(&mut i2c, 0x2A5_u16, &mut buf).unwrap();
+        assert_eq!(buf, [0x5A]);
+
+        let err = i2c0::Read::read(&mut i2c, 0x1A5_u16, &mut buf).unwrap_err();
+        assert!(matches!(
+            eh1::i2c::Error::kind(&err),
+            ErrorKind::NoAcknowledge(_)
+        ));
+    }
+}
+
+#[test]
+fn spi_idle_high() {
+    let flash = SpiFlash::new([0xC2, 0x20, 0x16], 1 << 22);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_2,
+        flash.clone(),
+    ));
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    spi.set_mode(eh1::spi::MODE_2).unwrap();
+    let mut cs = hal.ad3().unwrap();
+
+    cs.set_low().unwrap();
+    let mut buf: [u8; 4] = [0x9F, 0, 0, 0];
+    spi.transfer_in_place(&mut buf).unwrap();
+    cs.set_high().unwrap();
+    assert_eq!(buf, [0xFF, 0xC2, 0x20, 0x16]);
+}
+
+#[test]
+fn spi_transfer_asymmetric() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    // read longer than write, padded with the fill byte
+    let mut read: [u8; 4] = [0xAA; 4];
+    spi.transfer(&mut read, &[0x12, 0x34]).unwrap();
+    assert_eq!(read, [0x12, 0x34, 0x00, 0x00]);
+    spi.set_fill_byte(0xFF);
+    spi.transfer(&mut read, &[0x56]).unwrap();
+    assert_eq!(read, [0x56, 0xFF, 0xFF, 0xFF]);
+
+    // write longer than read, the excess is discarded
+    let mut read: [u8; 2] = [0; 2];
+    spi.transfer(&mut read, &[0x01, 0x02, 0x03, 0x04]).unwrap();
+    assert_eq!(read, [0x01, 0x02]);
+    spi.transfer(&mut read, &[0x05, 0x06]).unwrap();
+    assert_eq!(read, [0x05, 0x06]);
+
+    spi.read(&mut read).unwrap();
+    assert_eq!(read, [0xFF, 0xFF]);
+}
+
+#[test]
+fn spi_device_transfer_asymmetric() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    spi.set_fill_byte(0xFF);
+
+    let mut long: [u8; 3] = [0; 3];
+    let mut short: [u8; 1] = [0];
+    let mut read: [u8; 2] = [0; 2];
+    spi.transaction_with(|bus| {
+        bus.transfer(&mut long, &[0x12])?;
+        bus.transfer(&mut short, &[0x34, 0x56])?;
+        bus.read(&mut read)
+    })
+    .unwrap();
+    assert_eq!(long, [0x12, 0xFF, 0xFF]);
+    assert_eq!(short, [0x34]);
+    assert_eq!(read, [0xFF, 0xFF]);
+
+    spi.transaction(&mut [
+        Operation::Transfer(&mut long, &[0x78]),
+        Operation::Transfer(&mut short, &[0x9A, 0xBC]),
+    ])
+    .unwrap();
+    assert_eq!(long, [0x78, 0xFF, 0xFF]);
+    assert_eq!(short, [0x9A]);
+}
+
+#[test]
+fn spi_full_duplex() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    // bytes are read in the order they were written
+    ehnb1::spi::FullDuplex::write(&mut spi, 0xA5).unwrap();
+    ehnb1::spi::FullDuplex::write(&mut spi, 0x5A).unwrap();
+    assert_eq!(ehnb1::spi::FullDuplex::read(&mut spi).unwrap(), 0xA5);
+    assert_eq!(ehnb1::spi::FullDuplex::read(&mut spi).unwrap(), 0x5A);
+    assert!(matches!(
+        ehnb1::spi::FullDuplex::read(&mut spi),
+        Err(nb::Error::WouldBlock)
+    ));
+
+    eh0::spi::FullDuplex::send(&mut spi, 0x12).unwrap();
+    assert_eq!(eh0::spi::FullDuplex::read(&mut spi).unwrap(), 0x12);
+    assert!(matches!(
+        eh0::spi::FullDuplex::read(&mut spi),
+        Err(nb::Error::WouldBlock)
+    ));
+}
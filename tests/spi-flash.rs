@@ -0,0 +1,82 @@
+//! Simulated version of `examples/spi-flash.rs`.
+
+use ftdi_embedded_hal::{self as hal, Pin};
+use hal::sim::{SimDevice, SpiFlash, SpiPeripheral};
+use spi_memory::prelude::*;
+use spi_memory::series25::Flash;
+
+const LINE: u32 = 0x10;
+
+#[test]
+fn spi_flash() {
+    let model = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        model.clone(),
+    ));
+    let data: [u8; 8] = [0x00, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70];
+
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let spi = hal.spi().unwrap();
+    let ncs = hal.ad3().unwrap();
+
+    let mut flash = Flash::init(spi, ncs).unwrap();
+    let id = flash.read_jedec_id().unwrap();
+    assert_eq!(id.mfr_code(), 0xEF);
+    assert_eq!(id.device_id(), [0x40, 0x14]);
+
+    let addrs: [u32; 5] = [0, LINE, 2 * LINE, 3 * LINE, 4 * LINE];
+    let zero: [u8; 8] = [0; 8];
+    let mut bytes_w: [u8; 8] = [0; 8];
+    let mut bytes_r: [u8; 8] = [0; 8];
+
+    for addr in addrs.iter() {
+        bytes_w.copy_from_slice(&data);
+        flash.write_bytes(*addr, &mut bytes_w).unwrap();
+    }
+
+    for addr in addrs.iter() {
+        bytes_r.copy_from_slice(&zero);
+        flash.read(*addr, &mut bytes_r).unwrap();
+        assert_eq!(data, bytes_r);
+    }
+
+    let mut buf = [0; LINE as usize];
+    let mut addr = 0;
+    while addr < 0x100 {
+        flash.read(addr, &mut buf).unwrap();
+        if addr < 5 * LINE {
+            assert_eq!(buf[..8], data);
+            assert_eq!(buf[8..], [0xFF; 8]);
+        } else {
+            assert_eq!(buf, [0xFF; LINE as usize]);
+        }
+        addr += LINE;
+    }
+
+    flash.erase_sectors(0, 1).unwrap();
+    assert!(model.memory()[..0x100].iter().all(|&byte| byte == 0xFF));
+}
+
+#[test]
+fn deselected() {
+    let model = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        model.clone(),
+    ));
+
+    let hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    let mut ncs = hal.ad3().unwrap();
+    eh1::digital::OutputPin::set_high(&mut ncs).unwrap();
+
+    // the flash ignores the bus and SDI floats high
+    let mut buf: [u8; 4] = [0x9F, 0, 0, 0];
+    eh1::spi::SpiBus::transfer_in_place(&mut spi, &mut buf).unwrap();
+    assert_eq!(buf, [0xFF; 4]);
+}
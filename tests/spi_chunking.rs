@@ -0,0 +1,181 @@
+use eh1::spi::{Operation, SpiBus, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Pin};
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{SimDevice, SimError};
+use std::collections::VecDeque;
+
+const MIB: usize = 1 << 20;
+
+/// Executor echoing the bytes shifted out, like a loopback, without
+/// simulating the bus to keep multi-megabyte transfers fast.
+#[derive(Debug, Default)]
+struct Echo {
+    in_transfer_size: usize,
+    /// RX FIFO.
+    rx: VecDeque<u8>,
+    /// Most bytes in the RX FIFO.
+    rx_max: usize,
+    /// Bytes shifted.
+    shifted: usize,
+    /// Sizes of the reads.
+    reads: Vec<usize>,
+}
+
+impl MpsseCmdExecutor for Echo {
+    type Error = SimError;
+
+    fn init(&mut self, settings: &MpsseSettings) -> Result<(), SimError> {
+        self.in_transfer_size = settings.in_transfer_size as usize;
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), SimError> {
+        let disassembly = disassemble(data);
+        assert!(disassembly.incomplete().is_empty());
+        for op in disassembly.ops() {
+            match op {
+                MpsseOp::ClockData { data, .. } => {
+                    self.shifted += data.len();
+                    self.rx.extend(data);
+                }
+                MpsseOp::ClockDataOut { data, .. } => self.shifted += data.len(),
+                _ => {}
+            }
+        }
+        self.rx_max = self.rx_max.max(self.rx.len());
+        Ok(())
+    }
+
+    fn recv(&mut self, data: &mut [u8]) -> Result<(), SimError> {
+        if data.len() > self.rx.len() {
+            return Err(SimError::ReadTimeout {
+                requested: data.len(),
+                available: self.rx.len(),
+            });
+        }
+        let len: usize = data.len();
+        assert!(len <= self.in_transfer_size, "{len} byte read");
+        self.reads.push(len);
+        for (dst, src) in data.iter_mut().zip(self.rx.drain(..len)) {
+            *dst = src;
+        }
+        Ok(())
+    }
+}
+
+/// Pseudo-random test pattern.
+fn pattern(len: usize) -> Vec<u8> {
+    let mut state: u32 = 0x1234_5678;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn bus() {
+    let mut hal = hal::FtHal::init_freq(Echo::default(), 30_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    let write: Vec<u8> = pattern(4 * MIB);
+    let mut read: Vec<u8> = vec![0; write.len()];
+    spi.transfer(&mut read, &write).unwrap();
+    assert!(read == write);
+
+    let mut buf: Vec<u8> = write.clone();
+    spi.transfer_in_place(&mut buf).unwrap();
+    assert!(buf == write);
+
+    spi.read(&mut buf).unwrap();
+    assert!(buf.iter().all(|&byte| byte == 0));
+
+    spi.write(&write).unwrap();
+
+    hal.with_device(|echo| {
+        assert_eq!(echo.shifted, 4 * write.len());
+        assert!(echo.rx.is_empty());
+        // reads are limited to the USB transfer size, with the next piece
+        // sent before the previous one is read
+        assert_eq!(echo.reads.iter().max(), Some(&4096));
+        assert_eq!(echo.rx_max, 2 * 4096);
+    });
+}
+
+#[test]
+fn in_transfer_size() {
+    let settings = MpsseSettings {
+        in_transfer_size: 64 * 1024,
+        clock_frequency: Some(30_000_000),
+        ..Default::default()
+    };
+    let mut hal = hal::FtHal::init(Echo::default(), &settings).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    let write: Vec<u8> = pattern(2 * MIB + 3);
+    let mut read: Vec<u8> = vec![0; write.len()];
+    spi.transfer(&mut read, &write).unwrap();
+    assert!(read == write);
+
+    hal.with_device(|echo| {
+        assert_eq!(echo.reads.len(), write.len().div_ceil(64 * 1024));
+        assert_eq!(echo.reads[0], 64 * 1024);
+        assert_eq!(echo.reads.last(), Some(&3));
+        assert_eq!(echo.rx_max, 2 * 64 * 1024);
+    });
+}
+
+#[test]
+fn device() {
+    let mut hal = hal::FtHal::init_freq(Echo::default(), 30_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+
+    let write: Vec<u8> = pattern(3 * MIB);
+    let mut read: Vec<u8> = vec![0; write.len()];
+    let mut tail: [u8; 2] = [0xAA; 2];
+    spi.transaction(&mut [
+        Operation::Write(&write),
+        Operation::Transfer(&mut read, &write),
+        Operation::Read(&mut tail),
+    ])
+    .unwrap();
+    assert!(read == write);
+    assert_eq!(tail, [0, 0]);
+
+    let mut words: Vec<u16> = vec![0; MIB];
+    let expected: Vec<u16> = (0..MIB).map(|idx| idx as u16).collect();
+    spi.transaction(&mut [Operation::Transfer(&mut words, &expected)])
+        .unwrap();
+    assert!(words == expected);
+
+    hal.with_device(|echo| {
+        assert_eq!(echo.shifted, 2 * write.len() + tail.len() + 2 * MIB);
+        assert!(echo.rx.is_empty());
+        assert_eq!(echo.reads.iter().max(), Some(&4096));
+        assert_eq!(echo.rx_max, 2 * 4096);
+    });
+}
+
+#[test]
+fn sim() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let hal = hal::FtHal::init_freq(device, 30_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+
+    // across the 64 KiB command limit
+    let write: Vec<u8> = pattern(80 * 1024);
+    let mut read: Vec<u8> = vec![0; write.len()];
+    spi.transfer(&mut read, &write).unwrap();
+    assert!(read == write);
+
+    let bits: usize = write.len() * 8 - 3;
+    spi.transfer_bits(&mut read, &write, bits).unwrap();
+    let last: usize = write.len() - 1;
+    assert!(read[..last] == write[..last]);
+    assert_eq!(read[last], write[last] & 0xF8);
+}
@@ -0,0 +1,438 @@
+use eh1::spi::{Operation, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Capability, Chip, ErrorKind, Pin, PinUse, SpiDeviceConfig};
+use ftdi_mpsse::{MpsseCmdExecutor, MpsseSettings};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{Lines, Peripheral, SimDevice, SpiFlash, SpiPeripheral};
+use hal::trace::{Direction, Trace, TracingExecutor};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn read_id<D: SpiDevice>(spi: &mut D) -> [u8; 3] {
+    let mut id: [u8; 3] = [0; 3];
+    spi.transaction(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut id)])
+        .unwrap();
+    id
+}
+
+/// Clock divisors and SCK idle levels set by the recorded commands.
+fn bus_config(trace: &Trace) -> (Vec<u16>, Vec<bool>) {
+    let mut divisors: Vec<u16> = Vec::new();
+    let mut idle: Vec<bool> = Vec::new();
+    let mut sck: Option<bool> = None;
+    for record in trace.records() {
+        for op in disassemble(&record.tx).ops() {
+            match *op {
+                MpsseOp::SetClockDivisor(divisor) => divisors.push(divisor),
+                MpsseOp::SetGpioLower { value, .. } => {
+                    let level: bool = value & 1 != 0;
+                    if sck.is_some_and(|prev| prev != level) {
+                        idle.push(level);
+                    }
+                    sck = Some(level);
+                }
+                _ => {}
+            }
+        }
+    }
+    (divisors, idle)
+}
+
+#[test]
+fn shared_bus() {
+    let flash = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+    let adc = SpiFlash::new([0xC2, 0x20, 0x16], 1 << 20);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        flash.clone(),
+    ));
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(4),
+        eh1::spi::MODE_2,
+        adc.clone(),
+    ));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 3_000_000).unwrap();
+
+    let mut spi_flash = hal.spi_device(3).unwrap();
+    assert_eq!(spi_flash.set_frequency(10_000_000).unwrap(), 10_000_000);
+    let mut spi_adc = hal.spi_device(4).unwrap();
+    assert_eq!(spi_adc.set_frequency(1_000_000).unwrap(), 1_000_000);
+    spi_adc.set_mode(eh1::spi::MODE_2).unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    assert_eq!(read_id(&mut spi_flash), [0xEF, 0x40, 0x14]);
+    assert_eq!(
+        hal.with_device(|d| d.device().clock_frequency()),
+        10_000_000
+    );
+    assert_eq!(read_id(&mut spi_adc), [0xC2, 0x20, 0x16]);
+    assert_eq!(hal.with_device(|d| d.device().clock_frequency()), 1_000_000);
+    assert!(hal.with_device(|d| d.device().level(Pin::Lower(0))));
+    assert_eq!(read_id(&mut spi_adc), [0xC2, 0x20, 0x16]);
+    assert_eq!(read_id(&mut spi_flash), [0xEF, 0x40, 0x14]);
+    assert_eq!(read_id(&mut spi_flash), [0xEF, 0x40, 0x14]);
+    assert!(!hal.with_device(|d| d.device().level(Pin::Lower(0))));
+
+    // reprogrammed only when switching devices
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    let (divisors, idle) = bus_config(&trace);
+    assert_eq!(divisors, [2, 5, 2]);
+    assert_eq!(idle, [true, false]);
+}
+
+#[test]
+fn bus_frequency() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let mut hal = hal::FtHal::init_freq(device, 3_000_000).unwrap();
+    let mut fast = hal.spi_device(3).unwrap();
+    fast.set_frequency(10_000_000).unwrap();
+    let mut default = hal.spi_device(4).unwrap();
+
+    read_id(&mut fast);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 10_000_000);
+    // devices without a frequency use the bus clock
+    read_id(&mut default);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 3_000_000);
+
+    // the bus clock is reprogrammed after it is changed
+    hal.set_clock_frequency(1_000_000).unwrap();
+    read_id(&mut fast);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 10_000_000);
+    read_id(&mut default);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 1_000_000);
+
+    // the clock is reprogrammed after a failed transaction
+    hal.with_device(|sim| *sim = SimDevice::new());
+    assert!(fast.write(&[0x00_u8]).is_err());
+    hal.with_device(|sim| {
+        sim.init(&MpsseSettings {
+            clock_frequency: Some(1_000_000),
+            ..Default::default()
+        })
+        .unwrap()
+    });
+    read_id(&mut fast);
+    assert_eq!(hal.with_device(|sim| sim.clock_frequency()), 10_000_000);
+
+    assert!(matches!(
+        fast.set_frequency(50_000_000),
+        Err(hal::Error::Hal(ErrorKind::ClockFrequency(50_000_000)))
+    ));
+}
+
+#[test]
+fn shared_pins() {
+    let hal = hal::FtHal::init_default(SimDevice::new()).unwrap();
+    let a = hal.spi_device(3).unwrap();
+    let b = hal.spi_device(4).unwrap();
+
+    // chip select pins are exclusive
+    assert!(matches!(
+        hal.spi_device(4),
+        Err(hal::Error::Hal(ErrorKind::PinInUse {
+            pin: Pin::Lower(4),
+            requested: PinUse::Output,
+            current: PinUse::Output,
+        }))
+    ));
+    assert!(matches!(
+        hal.spi(),
+        Err(hal::Error::Hal(ErrorKind::PinInUse {
+            pin: Pin::Lower(0),
+            current: PinUse::Spi,
+            ..
+        }))
+    ));
+
+    // the bus pins are released with the last device
+    drop(a);
+    assert!(hal.ad3().is_ok());
+    assert!(hal.spi().is_err());
+    drop(b);
+    assert!(hal.spi().is_ok());
+}
+
+#[test]
+fn single_transfer() {
+    let flash = SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20);
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        flash.clone(),
+    ));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 1_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    let mut mfr: [u8; 1] = [0];
+    let mut id: [u8; 2] = [0; 2];
+    let mut status: [u8; 2] = [0x05, 0];
+    spi.transaction(&mut [
+        Operation::Write(&[0x9F]),
+        Operation::Read(&mut mfr),
+        Operation::Transfer(&mut id, &[0xAA]),
+    ])
+    .unwrap();
+    spi.transaction(&mut [Operation::TransferInPlace(&mut status)])
+        .unwrap();
+    assert_eq!(mfr, [0xEF]);
+    assert_eq!(id, [0x40, 0x14]);
+    assert_eq!(status, [0xFF, 0x00]);
+
+    // chip select, data and chip select in one write, and one read
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    let directions: Vec<Direction> = trace.records().iter().map(|r| r.direction).collect();
+    assert_eq!(
+        directions,
+        [
+            Direction::Send,
+            Direction::Recv,
+            Direction::Send,
+            Direction::Recv
+        ]
+    );
+    assert_eq!(trace.records()[1].rx.len(), 3);
+
+    // delays are timed in the command stream
+    spi.transaction(&mut [
+        Operation::Write(&[0x9F]),
+        Operation::DelayNs(1_000),
+        Operation::Read(&mut id),
+        Operation::DelayNs(1_000),
+    ])
+    .unwrap();
+    assert_eq!(id, [0xEF, 0x40]);
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    let directions: Vec<Direction> = trace.records().iter().map(|r| r.direction).collect();
+    assert_eq!(directions, [Direction::Send, Direction::Recv]);
+}
+
+#[test]
+fn upper_cs() {
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Upper(0),
+        eh1::spi::MODE_0,
+        SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20),
+    ));
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        SpiFlash::new([0xC2, 0x20, 0x16], 1 << 20),
+    ));
+    let mut hal = hal::FtHal::init_freq(device, 1_000_000).unwrap();
+    let mut upper = hal.spi_device_pin(Pin::Upper(0)).unwrap();
+    let mut lower = hal.spi_device_pin(Pin::Lower(3)).unwrap();
+    assert_eq!(hal.with_device(|sim| sim.upper_direction()), 0x01);
+    assert!(hal.with_device(|sim| sim.level(Pin::Upper(0))));
+
+    assert_eq!(read_id(&mut upper), [0xEF, 0x40, 0x14]);
+    assert_eq!(read_id(&mut lower), [0xC2, 0x20, 0x16]);
+    assert!(hal.with_device(|sim| sim.level(Pin::Upper(0))));
+
+    assert!(matches!(
+        hal.c0(),
+        Err(hal::Error::Hal(ErrorKind::PinInUse {
+            pin: Pin::Upper(0),
+            ..
+        }))
+    ));
+    drop(upper);
+    assert!(hal.c0().is_ok());
+
+    hal.set_chip(Chip::FT4232H).unwrap();
+    assert!(matches!(
+        hal.spi_device_pin(Pin::Upper(1)),
+        Err(hal::Error::Hal(ErrorKind::Unsupported {
+            chip: Chip::FT4232H,
+            capability: Capability::Pin(Pin::Upper(1)),
+        }))
+    ));
+}
+
+/// Records the times at which SCK and CS change.
+struct EdgeLog {
+    cs: Pin,
+    last: Option<(bool, bool)>,
+    edges: Arc<Mutex<Vec<(Duration, bool, bool)>>>,
+}
+
+impl Peripheral for EdgeLog {
+    fn update(&mut self, now: Duration, levels: Lines) {
+        let state = (levels.contains(Pin::Lower(0)), levels.contains(self.cs));
+        if self.last != Some(state) {
+            self.last = Some(state);
+            self.edges.lock().unwrap().push((now, state.0, state.1));
+        }
+    }
+
+    fn drive_low(&self) -> Lines {
+        Lines::NONE
+    }
+}
+
+#[test]
+fn cs_config() {
+    let edges: Arc<Mutex<Vec<(Duration, bool, bool)>>> = Default::default();
+    let mut device = SimDevice::new();
+    device.attach(EdgeLog {
+        cs: Pin::Lower(3),
+        last: None,
+        edges: edges.clone(),
+    });
+    let mut hal = hal::FtHal::init_freq(device, 10_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    let config = SpiDeviceConfig {
+        cs_active_high: true,
+        cs_setup: Duration::from_nanos(500),
+        cs_hold: Duration::from_nanos(300),
+        cs_idle_min: Duration::from_micros(2),
+    };
+    spi.set_config(config).unwrap();
+    assert!(!hal.with_device(|sim| sim.level(Pin::Lower(3))));
+    edges.lock().unwrap().clear();
+
+    spi.write(&[0xFF_u8]).unwrap();
+    spi.write(&[0xFF_u8]).unwrap();
+    assert!(!hal.with_device(|sim| sim.level(Pin::Lower(3))));
+
+    // CS asserted, SCK edges, CS released, CS asserted again
+    let edges = edges.lock().unwrap().clone();
+    let (start, _, cs) = edges[0];
+    assert!(cs);
+    let sck: Vec<Duration> = edges[1..]
+        .iter()
+        .take_while(|&&(_, _, cs)| cs)
+        .map(|&(t, _, _)| t)
+        .collect();
+    let release: Duration = edges.iter().find(|&&(_, _, cs)| !cs).unwrap().0;
+    let next: Duration = edges
+        .iter()
+        .find(|&&(t, _, cs)| t > release && cs)
+        .unwrap()
+        .0;
+
+    // SCK stays idle for the setup and hold times, the idle time is 20
+    // cycles without data at 10 MHz
+    let period: Duration = Duration::from_nanos(100);
+    assert_eq!(sck.len(), 16);
+    assert!(sck[0] - start >= config.cs_setup);
+    assert!(release - sck[15] >= config.cs_hold);
+    let idle_time: Duration = next - release;
+    assert!(idle_time >= config.cs_idle_min && idle_time < config.cs_idle_min + period);
+
+    // the setup and hold times are limited to 1 ms, the idle time to 100 ms
+    assert!(matches!(
+        spi.set_config(SpiDeviceConfig {
+            cs_setup: Duration::from_millis(2),
+            ..config
+        }),
+        Err(hal::Error::Hal(ErrorKind::ChipSelectTime(time))) if time == Duration::from_millis(2)
+    ));
+    assert!(matches!(
+        spi.set_config(SpiDeviceConfig {
+            cs_idle_min: Duration::from_millis(101),
+            ..config
+        }),
+        Err(hal::Error::Hal(ErrorKind::ChipSelectTime(time))) if time == Duration::from_millis(101)
+    ));
+
+    // the FT2232D cannot clock without data for the idle time
+    hal.set_clock_frequency(1_000_000).unwrap();
+    hal.set_chip(Chip::FT2232D).unwrap();
+    assert!(matches!(
+        spi.set_config(config),
+        Err(hal::Error::Hal(ErrorKind::Unsupported {
+            chip: Chip::FT2232D,
+            capability: Capability::ClockWithoutData,
+        }))
+    ));
+    spi.set_config(SpiDeviceConfig {
+        cs_idle_min: Duration::ZERO,
+        ..config
+    })
+    .unwrap();
+}
+
+#[test]
+fn cs_config_read() {
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20),
+    ));
+    let hal = hal::FtHal::init_freq(device, 10_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+    spi.set_config(SpiDeviceConfig {
+        cs_setup: Duration::from_micros(1),
+        cs_hold: Duration::from_micros(1),
+        cs_idle_min: Duration::from_micros(1),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(read_id(&mut spi), [0xEF, 0x40, 0x14]);
+    assert_eq!(read_id(&mut spi), [0xEF, 0x40, 0x14]);
+}
+
+#[test]
+fn delay() {
+    let edges: Arc<Mutex<Vec<(Duration, bool, bool)>>> = Default::default();
+    let mut device = SimDevice::new();
+    device.attach(EdgeLog {
+        cs: Pin::Lower(3),
+        last: None,
+        edges: edges.clone(),
+    });
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20),
+    ));
+    let mut hal = hal::FtHal::init_default(device).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+
+    let delay: Duration = Duration::from_nanos(10_010);
+    for frequency in [1_000_000, 7_500_000] {
+        hal.set_clock_frequency(frequency).unwrap();
+        let mut id: [u8; 3] = [0; 3];
+        let mut elapsed = |operations: &mut [Operation<'_, u8>]| {
+            let start: Duration = hal.with_device(|sim| sim.elapsed());
+            spi.transaction(operations).unwrap();
+            hal.with_device(|sim| sim.elapsed()) - start
+        };
+        let frames: Duration = elapsed(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut id)]);
+        edges.lock().unwrap().clear();
+        let delayed: Duration = elapsed(&mut [
+            Operation::Write(&[0x9F]),
+            Operation::DelayNs(delay.as_nanos() as u32),
+            Operation::Read(&mut id),
+        ]);
+        assert_eq!(id, [0xEF, 0x40, 0x14], "{frequency} Hz");
+        assert!(delayed - frames >= delay, "{frequency} Hz");
+
+        // CS asserted, SCK idle during the delay, CS deasserted
+        let edges = edges.lock().unwrap().clone();
+        assert_eq!(edges.len(), 2 + 2 * 32, "{frequency} Hz");
+        let last: usize = edges.len() - 1;
+        assert!(edges[1..last].iter().all(|&(_, _, cs)| !cs));
+        let gap: Duration = edges[17].0 - edges[16].0;
+        assert!(gap >= delay, "{frequency} Hz: {gap:?}");
+    }
+
+    // the FT2232D times delays the same way
+    hal.set_clock_frequency(1_000_000).unwrap();
+    hal.set_chip(Chip::FT2232D).unwrap();
+    let mut id: [u8; 3] = [0; 3];
+    spi.transaction(&mut [
+        Operation::Write(&[0x9F]),
+        Operation::DelayNs(1_000),
+        Operation::Read(&mut id),
+    ])
+    .unwrap();
+    assert_eq!(id, [0xEF, 0x40, 0x14]);
+}
@@ -0,0 +1,172 @@
+use eh1::digital::OutputPin;
+use eh1::spi::{
+    MODE_0, MODE_1, MODE_2, MODE_3, Mode, Operation, Phase, Polarity, SpiBus, SpiDevice,
+};
+use ftdi_embedded_hal::{self as hal, BitOrder, Pin};
+use ftdi_mpsse::{ClockData, ClockDataOut};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{SimDevice, SpiFlash, SpiPeripheral};
+use hal::trace::{Trace, TracingExecutor};
+
+const MODES: [Mode; 4] = [MODE_0, MODE_1, MODE_2, MODE_3];
+const JEDEC_ID: [u8; 3] = [0xEF, 0x40, 0x14];
+
+fn flash(mode: Mode) -> SimDevice {
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        mode,
+        SpiFlash::new(JEDEC_ID, 1 << 20),
+    ));
+    device
+}
+
+/// Modes 0 and 3 sample on the rising edge, 1 and 2 on the falling edge.
+fn samples_on_rising_edge(mode: Mode) -> bool {
+    (mode.polarity == Polarity::IdleHigh) == (mode.phase == Phase::CaptureOnSecondTransition)
+}
+
+/// Read the JEDEC ID with the bus in `host` mode from a flash in `target` mode.
+fn read_id(target: Mode, host: Mode) -> [u8; 3] {
+    let hal = hal::FtHal::init_freq(flash(target), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    spi.set_mode(host).unwrap();
+    let mut cs = hal.ad3().unwrap();
+
+    cs.set_low().unwrap();
+    let mut buf: [u8; 4] = [0x9F, 0, 0, 0];
+    spi.transfer_in_place(&mut buf).unwrap();
+    cs.set_high().unwrap();
+    buf[1..].try_into().unwrap()
+}
+
+#[test]
+fn spi() {
+    for target in MODES {
+        for host in MODES {
+            // data is sampled a half clock early or late on the other edge
+            let id: [u8; 3] = read_id(target, host);
+            assert_eq!(
+                id == JEDEC_ID,
+                samples_on_rising_edge(target) == samples_on_rising_edge(host),
+                "{target:?} {host:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn spi_device() {
+    for mode in MODES {
+        let hal = hal::FtHal::init_freq(flash(mode), 1_000_000).unwrap();
+        let mut spi = hal.spi_device(3).unwrap();
+        spi.set_mode(mode).unwrap();
+
+        let mut id: [u8; 3] = [0; 3];
+        spi.transaction(&mut [Operation::Write(&[0x9F]), Operation::Read(&mut id)])
+            .unwrap();
+        assert_eq!(id, JEDEC_ID, "{mode:?}");
+    }
+}
+
+#[test]
+fn edges() {
+    let expected: [(bool, ClockData, ClockDataOut); 4] = [
+        (false, ClockData::MsbPosIn, ClockDataOut::MsbNeg),
+        (false, ClockData::MsbNegIn, ClockDataOut::MsbPos),
+        (true, ClockData::MsbNegIn, ClockDataOut::MsbPos),
+        (true, ClockData::MsbPosIn, ClockDataOut::MsbNeg),
+    ];
+
+    for (mode, (idle_high, clk, clk_out)) in MODES.into_iter().zip(expected) {
+        let mut hal =
+            hal::FtHal::init_freq(TracingExecutor::new(SimDevice::new()), 1_000_000).unwrap();
+        let mut spi = hal.spi().unwrap();
+        spi.set_mode(mode).unwrap();
+        assert_eq!(
+            hal.with_device(|d| d.device().level(Pin::Lower(0))),
+            idle_high,
+            "{mode:?}"
+        );
+        hal.with_device(|d| d.take_trace());
+
+        spi.write(&[0xA5_u8]).unwrap();
+        spi.transfer_in_place(&mut [0x5A_u8]).unwrap();
+
+        let trace: Trace = hal.with_device(|d| d.take_trace());
+        let ops: Vec<MpsseOp> = trace
+            .records()
+            .iter()
+            .flat_map(|record| disassemble(&record.tx).ops().cloned().collect::<Vec<_>>())
+            .filter(|op| *op != MpsseOp::SendImmediate)
+            .collect();
+        assert_eq!(
+            ops,
+            [
+                MpsseOp::ClockDataOut {
+                    mode: clk_out,
+                    data: vec![0xA5]
+                },
+                MpsseOp::ClockData {
+                    mode: clk,
+                    data: vec![0x5A]
+                },
+            ],
+            "{mode:?}"
+        );
+    }
+}
+
+#[test]
+fn bit_order() {
+    let reversed: [u8; 3] = JEDEC_ID.map(u8::reverse_bits);
+
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(flash(MODE_0)), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    spi.set_bit_order(BitOrder::LsbFirst);
+    // the bit order is kept when changing modes
+    spi.set_mode(MODE_0).unwrap();
+    let mut cs = hal.ad3().unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    // the flash shifts MSB first
+    cs.set_low().unwrap();
+    spi.write(&[0x9F_u8.reverse_bits()]).unwrap();
+    let mut id: [u8; 3] = [0; 3];
+    spi.transfer_in_place(&mut id).unwrap();
+    cs.set_high().unwrap();
+    assert_eq!(id, reversed);
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    let modes: Vec<MpsseOp> = trace
+        .records()
+        .iter()
+        .flat_map(|record| disassemble(&record.tx).ops().cloned().collect::<Vec<_>>())
+        .filter(|op| matches!(op, MpsseOp::ClockData { .. } | MpsseOp::ClockDataOut { .. }))
+        .collect();
+    assert_eq!(
+        modes,
+        [
+            MpsseOp::ClockDataOut {
+                mode: ClockDataOut::LsbNeg,
+                data: vec![0xF9]
+            },
+            MpsseOp::ClockData {
+                mode: ClockData::LsbPosIn,
+                data: vec![0; 3]
+            },
+        ]
+    );
+    drop(cs);
+    drop(spi);
+
+    let mut spi = hal.spi_device(3).unwrap();
+    spi.set_bit_order(BitOrder::LsbFirst);
+    let mut id: [u8; 3] = [0; 3];
+    spi.transaction(&mut [
+        Operation::Write(&[0x9F_u8.reverse_bits()]),
+        Operation::Read(&mut id),
+    ])
+    .unwrap();
+    assert_eq!(id, reversed);
+}
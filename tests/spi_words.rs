@@ -0,0 +1,164 @@
+use eh1::spi::{Operation, SpiBus, SpiDevice};
+use ftdi_embedded_hal::{self as hal, BitOrder, ByteOrder, Pin};
+use ftdi_mpsse::{ClockBits, ClockBitsOut, ClockData, ClockDataOut};
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{SimDevice, SpiFlash, SpiPeripheral};
+use hal::trace::{Trace, TracingExecutor};
+
+fn flash() -> SimDevice {
+    let mut device = SimDevice::new();
+    device.attach(SpiPeripheral::new(
+        Pin::Lower(3),
+        eh1::spi::MODE_0,
+        SpiFlash::new([0xEF, 0x40, 0x14], 1 << 20),
+    ));
+    device
+}
+
+fn loopback() -> TracingExecutor<SimDevice> {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    TracingExecutor::new(device)
+}
+
+/// Data shifting commands of a trace.
+fn shifts(trace: &Trace) -> Vec<MpsseOp> {
+    trace
+        .records()
+        .iter()
+        .flat_map(|record| disassemble(&record.tx).ops().cloned().collect::<Vec<_>>())
+        .filter(|op| {
+            matches!(
+                op,
+                MpsseOp::ClockData { .. }
+                    | MpsseOp::ClockDataOut { .. }
+                    | MpsseOp::ClockBits { .. }
+                    | MpsseOp::ClockBitsOut { .. }
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn u16_device() {
+    let hal = hal::FtHal::init_freq(flash(), 1_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+
+    // opcode and the manufacturer ID, followed by the device ID
+    let mut id: [u16; 1] = [0];
+    let mut words: [u16; 1] = [0x9F00];
+    spi.transaction(&mut [
+        Operation::TransferInPlace(&mut words),
+        Operation::Read(&mut id),
+    ])
+    .unwrap();
+    assert_eq!(words, [0xFFEF]);
+    assert_eq!(id, [0x4014]);
+
+    spi.set_byte_order(ByteOrder::LittleEndian);
+    let mut words: [u16; 2] = [0x009F, 0];
+    SpiDevice::<u16>::transfer_in_place(&mut spi, &mut words).unwrap();
+    assert_eq!(words, [0xEFFF, 0x1440]);
+}
+
+#[test]
+fn u32_bus() {
+    let mut hal = hal::FtHal::init_freq(loopback(), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    let mut read: [u32; 2] = [0; 2];
+    spi.transfer(&mut read, &[0x1234_5678, 0x9ABC_DEF0])
+        .unwrap();
+    assert_eq!(read, [0x1234_5678, 0x9ABC_DEF0]);
+    spi.set_byte_order(ByteOrder::LittleEndian);
+    spi.write(&[0x1234_5678_u32]).unwrap();
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(
+        shifts(&trace),
+        [
+            MpsseOp::ClockData {
+                mode: ClockData::MsbPosIn,
+                data: vec![0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]
+            },
+            MpsseOp::ClockDataOut {
+                mode: ClockDataOut::MsbNeg,
+                data: vec![0x78, 0x56, 0x34, 0x12]
+            },
+        ]
+    );
+}
+
+#[test]
+fn bits() {
+    let mut hal = hal::FtHal::init_freq(loopback(), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    // 9-bit frame 0x1A5
+    spi.write_bits(&[0xD2, 0x80], 9).unwrap();
+    // 12-bit frame 0xABC
+    let mut read: [u8; 2] = [0; 2];
+    spi.transfer_bits(&mut read, &[0xAB, 0xC0], 12).unwrap();
+    assert_eq!(read, [0xAB, 0xC0]);
+    // fewer than 8 bits
+    spi.transfer_bits(&mut read, &[0xA0], 3).unwrap();
+    assert_eq!(read, [0xA0, 0xC0]);
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(
+        shifts(&trace),
+        [
+            MpsseOp::ClockDataOut {
+                mode: ClockDataOut::MsbNeg,
+                data: vec![0xD2]
+            },
+            MpsseOp::ClockBitsOut {
+                mode: ClockBitsOut::MsbNeg,
+                bits: 0x80,
+                len: 1
+            },
+            MpsseOp::ClockData {
+                mode: ClockData::MsbPosIn,
+                data: vec![0xAB]
+            },
+            MpsseOp::ClockBits {
+                mode: ClockBits::MsbPosIn,
+                bits: 0xC0,
+                len: 4
+            },
+            MpsseOp::ClockBits {
+                mode: ClockBits::MsbPosIn,
+                bits: 0xA0,
+                len: 3
+            },
+        ]
+    );
+}
+
+#[test]
+fn bits_lsb_first() {
+    let hal = hal::FtHal::init_freq(loopback(), 1_000_000).unwrap();
+    let mut spi = hal.spi().unwrap();
+    spi.set_bit_order(BitOrder::LsbFirst);
+
+    let mut read: [u8; 2] = [0; 2];
+    spi.transfer_bits(&mut read, &[0xAB, 0xFC], 12).unwrap();
+    assert_eq!(read, [0xAB, 0x0C]);
+}
+
+#[test]
+fn bits_device() {
+    let hal = hal::FtHal::init_freq(flash(), 1_000_000).unwrap();
+    let mut spi = hal.spi_device(3).unwrap();
+
+    // the opcode followed by the manufacturer and device ID, 4 bits short
+    let mut id: [u8; 3] = [0; 3];
+    spi.transaction_with(|bus| {
+        bus.write_bits(&[0x9F], 8)?;
+        bus.read_bits(&mut id, 20)
+    })
+    .unwrap();
+    assert_eq!(id, [0xEF, 0x40, 0x10]);
+}
@@ -0,0 +1,193 @@
+use eh1::digital::{InputPin, OutputPin};
+use eh1::i2c::I2c;
+use eh1::spi::{SpiBus, SpiDevice};
+use ftdi_embedded_hal::{self as hal, Pin};
+use ftdi_mpsse::MpsseCmdExecutor;
+use hal::disasm::{MpsseOp, disassemble};
+use hal::sim::{I2cPeripheral, I2cRegisters, SimDevice};
+use hal::trace::{Direction, Origin, Record, Trace, TracingExecutor};
+use std::time::Duration;
+
+fn origins(trace: &Trace) -> Vec<Origin> {
+    let mut origins: Vec<Origin> = trace.records().iter().map(|r| r.origin).collect();
+    origins.dedup();
+    origins
+}
+
+#[test]
+fn gpio() {
+    let mut hal = hal::FtHal::init_default(TracingExecutor::new(SimDevice::new())).unwrap();
+    // the clock divisor set by the HAL
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(origins(&trace), [Origin::FtHal]);
+    let mut output = hal.ad6().unwrap();
+    let mut input = hal.ci1().unwrap();
+    output.set_low().unwrap();
+    assert!(input.is_high().unwrap());
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(
+        origins(&trace),
+        [
+            Origin::OutputPin(Pin::Lower(6)),
+            Origin::InputPin(Pin::Upper(1)),
+            Origin::OutputPin(Pin::Lower(6)),
+            Origin::InputPin(Pin::Upper(1)),
+        ]
+    );
+
+    let read: &[Record] = &trace.records()[3..];
+    assert_eq!(read.len(), 2);
+    assert_eq!(read[0].direction, Direction::Send);
+    assert_eq!(
+        disassemble(&read[0].tx).ops().next(),
+        Some(&MpsseOp::GpioUpper)
+    );
+    assert!(read[0].rx.is_empty());
+    assert_eq!(read[1].direction, Direction::Recv);
+    assert!(read[1].tx.is_empty());
+    assert_eq!(read[1].rx, [0xFF]);
+    assert!(read[1].timestamp >= read[0].timestamp);
+    assert!(read.iter().all(|r| r.error.is_none()));
+}
+
+#[test]
+fn i2c_and_spi() {
+    let target = I2cRegisters::new(0x20, 4);
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    device.attach(I2cPeripheral::new(target));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 400_000).unwrap();
+    hal.with_device(|d| d.take_trace());
+
+    {
+        let mut i2c = hal.i2c().unwrap();
+        i2c.write(0x20_u8, &[0x00, 0x12]).unwrap();
+        let mut buf: [u8; 1] = [0];
+        i2c.write_read(0x20_u8, &[0x00], &mut buf).unwrap();
+        assert_eq!(buf, [0x12]);
+    }
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(origins(&trace), [Origin::I2c]);
+    assert!(
+        trace
+            .records()
+            .iter()
+            .any(|r| r.direction == Direction::Recv && r.rx.ends_with(&[0x12]))
+    );
+
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 1_000_000).unwrap();
+    hal.with_device(|d| d.take_trace());
+    {
+        let mut spi = hal.spi().unwrap();
+        let mut buf: [u8; 2] = [0; 2];
+        spi.transfer(&mut buf, &[0xAB, 0xCD]).unwrap();
+        assert_eq!(buf, [0xAB, 0xCD]);
+    }
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(origins(&trace), [Origin::Spi]);
+    let recv: &Record = trace.records().last().unwrap();
+    assert_eq!(recv.direction, Direction::Recv);
+    assert_eq!(recv.rx, [0xAB, 0xCD]);
+}
+
+#[test]
+fn spi_device() {
+    let mut device = SimDevice::new();
+    device.connect(Pin::Lower(1), Pin::Lower(2));
+    let mut hal = hal::FtHal::init_freq(TracingExecutor::new(device), 1_000_000).unwrap();
+    hal.with_device(|d| d.take_trace());
+    let mut spi = hal.spi_device(3).unwrap();
+    spi.write(&[0x01_u8]).unwrap();
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(origins(&trace), [Origin::SpiDevice]);
+    assert!(trace.records().iter().all(|r| r.error.is_none()));
+}
+
+#[test]
+fn error() {
+    let mut hal = hal::FtHal::init_default(TracingExecutor::new(SimDevice::new())).unwrap();
+    hal.with_device(|d| d.take_trace());
+    let mut buf: [u8; 2] = [0; 2];
+    hal.with_device(|d| d.recv(&mut buf)).unwrap_err();
+
+    let trace: Trace = hal.with_device(|d| d.take_trace());
+    assert_eq!(trace.records().len(), 1);
+    let record: &Record = &trace.records()[0];
+    assert_eq!(record.origin, Origin::Unknown);
+    assert_eq!(record.direction, Direction::Recv);
+    assert_eq!(record.rx.len(), 2);
+    assert!(record.error.is_some());
+}
+
+#[test]
+fn file_format() {
+    let mut trace = Trace::new();
+    trace.push(Record {
+        timestamp: Duration::from_nanos(1500),
+        duration: Duration::from_nanos(20),
+        origin: Origin::OutputPin(Pin::Upper(7)),
+        direction: Direction::Send,
+        tx: vec![0x82, 0x80, 0x80, 0x87],
+        rx: Vec::new(),
+        error: None,
+    });
+    trace.push(Record {
+        timestamp: Duration::from_micros(3),
+        duration: Duration::from_millis(5),
+        origin: Origin::I2c,
+        direction: Direction::Recv,
+        tx: Vec::new(),
+        rx: vec![0x00, 0xFF],
+        error: Some(String::from("read timeout")),
+    });
+    trace.push(Record {
+        timestamp: Duration::from_micros(9000),
+        duration: Duration::ZERO,
+        origin: Origin::SpiDevice,
+        direction: Direction::Xfer,
+        tx: vec![0x31, 0x00, 0x00, 0x9F, 0x87],
+        rx: vec![0xEF],
+        error: None,
+    });
+
+    let text: String = trace.to_string();
+    assert_eq!(
+        text,
+        "ftdi-embedded-hal trace 1\n\
+         1500 20 output:AC7 send 82808087 -\n\
+         3000 5000000 i2c recv - 00ff error: read timeout\n\
+         9000000 0 spi-device xfer 3100009f87 ef\n"
+    );
+    assert_eq!(Trace::read(text.as_bytes()).unwrap(), trace);
+
+    let path = std::env::temp_dir().join(format!("ftdi-trace-{}.txt", std::process::id()));
+    trace.save(&path).unwrap();
+    let loaded = Trace::load(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap(), trace);
+}
+
+#[test]
+fn malformed() {
+    for text in [
+        "",
+        "not a trace\n",
+        "ftdi-embedded-hal trace 1\n0 0 i2c send 8 -\n",
+        "ftdi-embedded-hal trace 1\n0 0 bogus send - -\n",
+        "ftdi-embedded-hal trace 1\n0 0 input:AB1 recv - 00\n",
+        "ftdi-embedded-hal trace 1\n0 0 spi send 87\n",
+        "ftdi-embedded-hal trace 1\n0 0 spi send 87 - oops\n",
+    ] {
+        let err = Trace::read(text.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{text:?}");
+    }
+
+    let trace =
+        Trace::read("ftdi-embedded-hal trace 1\n\n# comment\n0 0 hal send 87 -\n".as_bytes())
+            .unwrap();
+    assert_eq!(trace.records().len(), 1);
+}